@@ -5,7 +5,8 @@
 //! (`jsonsm-json`, `jsonsm-n1ql`) parse their respective surface syntaxes *into* this
 //! AST; the `jsonsm` engine compiles this AST into an executable match definition.
 //!
-//! Regex/`LIKE` patterns are represented here as plain strings (see [`Expr::Matches`]).
+//! Regex and `LIKE` patterns are represented here as plain strings (see [`Expr::Matches`] and
+//! [`Expr::Like`]).
 //! How a pattern is compiled and executed — and how values collate — is decided at
 //! compile time by the engine's collation strategy, not by this tree.
 
@@ -117,9 +118,9 @@ pub struct Func {
 /// An expression node.
 ///
 /// The tree mixes boolean-valued nodes (combinators, comparisons, existence, loops) with
-/// value-valued nodes ([`Expr::Value`], [`Expr::Field`], [`Expr::Func`]) that appear as
-/// operands. Validation that operands and boolean nodes are used in the right positions
-/// is the compiler's responsibility.
+/// value-valued nodes ([`Expr::Value`], [`Expr::Field`], [`Expr::Func`], [`Expr::Param`],
+/// [`Expr::Case`]) that appear as operands. Validation that operands and boolean nodes are used
+/// in the right positions is the compiler's responsibility.
 #[derive(Debug, Clone, PartialEq)]
 pub enum Expr {
     /// Constant `true`.
@@ -133,6 +134,17 @@ pub enum Expr {
     Field(Field),
     /// A function-call operand.
     Func(Func),
+    /// A named parameter operand (`$name`), bound per match call rather than at compile
+    /// time. The name is stored without the `$`.
+    Param(String),
+
+    /// A conditional operand (`CASE WHEN c1 THEN r1 … ELSE e END`): the value of the `then`
+    /// of the first `when` whose condition is true, else of `otherwise` — `NULL` if there is
+    /// none. An UNKNOWN condition is not true, so it falls through like a false one.
+    Case {
+        whens: Vec<(Expr, Expr)>,
+        otherwise: Option<Box<Expr>>,
+    },
 
     /// Logical negation.
     Not(Box<Expr>),
@@ -145,6 +157,12 @@ pub enum Expr {
     Exists(Box<Expr>),
     /// True if the operand path does *not* exist in the document.
     NotExists(Box<Expr>),
+    /// True if the operand is present and not `NULL` (`IS VALUED`). Never UNKNOWN: an absent
+    /// operand is simply not valued. `IS NOT VALUED` is [`Expr::Not`] over this node.
+    Valued(Box<Expr>),
+    /// True if the boolean sub-expression's result is TRUE or FALSE rather than UNKNOWN
+    /// (`IS KNOWN`). Never UNKNOWN itself. `IS UNKNOWN` is [`Expr::Not`] over this node.
+    Known(Box<Expr>),
 
     /// A binary comparison between two operands.
     Compare {
@@ -153,10 +171,31 @@ pub enum Expr {
         rhs: Box<Expr>,
     },
 
-    /// Pattern match (regex / `LIKE`). `pattern` is expected to resolve to a string;
+    /// Regex pattern match (`REGEXP_CONTAINS`). `pattern` is expected to resolve to a string;
     /// it is compiled and executed by the collation strategy chosen at compile time.
     Matches { lhs: Box<Expr>, pattern: Box<Expr> },
 
+    /// SQL `LIKE`: `lhs` matches `pattern` as a whole, `%` standing for any run of characters
+    /// and `_` for one. `escape`, if given, makes the character after it literal. Compiled by
+    /// the collation, as [`Expr::Matches`] is. `NOT LIKE` is [`Expr::Not`] over this node.
+    Like {
+        lhs: Box<Expr>,
+        pattern: Box<Expr>,
+        escape: Option<char>,
+    },
+
+    /// List membership (`lhs IN (…)`): true when `lhs` equals any constant in `list`, under
+    /// the same equality as [`CompareOp::Equals`]. `NOT IN` is [`Expr::Not`] over this node.
+    In { lhs: Box<Expr>, list: Vec<Literal> },
+
+    /// Inclusive range test (`lhs BETWEEN low AND high`): exactly `lhs >= low AND lhs <= high`,
+    /// three-valued logic included. `NOT BETWEEN` is [`Expr::Not`] over this node.
+    Between {
+        lhs: Box<Expr>,
+        low: Box<Expr>,
+        high: Box<Expr>,
+    },
+
     /// Array iteration with a quantifier. Binds `var` to each element of `in_expr`
     /// while evaluating `sub_expr`.
     Loop {
@@ -209,24 +248,43 @@ impl Expr {
     /// Apply `f` to each direct sub-expression.
     fn for_each_child<'e>(&'e self, f: &mut impl FnMut(&'e Expr)) {
         match self {
-            Expr::Not(e) | Expr::Exists(e) | Expr::NotExists(e) => f(e),
+            Expr::Not(e)
+            | Expr::Exists(e)
+            | Expr::NotExists(e)
+            | Expr::Valued(e)
+            | Expr::Known(e) => f(e),
             Expr::And(es) | Expr::Or(es) => es.iter().for_each(f),
             Expr::Func(func) => func.args.iter().for_each(f),
             Expr::Compare { lhs, rhs, .. } => {
                 f(lhs);
                 f(rhs);
             }
-            Expr::Matches { lhs, pattern } => {
+            Expr::Matches { lhs, pattern } | Expr::Like { lhs, pattern, .. } => {
                 f(lhs);
                 f(pattern);
             }
+            Expr::In { lhs, .. } => f(lhs),
+            Expr::Case { whens, otherwise } => {
+                for (cond, then) in whens {
+                    f(cond);
+                    f(then);
+                }
+                if let Some(e) = otherwise {
+                    f(e);
+                }
+            }
+            Expr::Between { lhs, low, high } => {
+                f(lhs);
+                f(low);
+                f(high);
+            }
             Expr::Loop {
                 in_expr, sub_expr, ..
             } => {
                 f(in_expr);
                 f(sub_expr);
             }
-            Expr::Value(_) | Expr::Field(_) | Expr::True | Expr::False => {}
+            Expr::Value(_) | Expr::Field(_) | Expr::Param(_) | Expr::True | Expr::False => {}
         }
     }
 }
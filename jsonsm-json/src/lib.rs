@@ -12,11 +12,21 @@
 //!
 //! This mirrors the format used by `gojsonsm`. Node types:
 //!
-//! - operands: `["value", X]`, `["field", <root?>, <key>…]`, `["func", name, arg…]`;
+//! - operands: `["value", X]`, `["field", <root?>, <key>…]`, `["func", name, arg…]`,
+//!   `["param", name]` (a named parameter, bound at match time);
 //! - logic: `["not", e]`, `["and", e…]`, `["or", e…]`;
-//! - existence: `["exists", e]`, `["notexists", e]`;
+//! - existence: `["exists", e]`, `["notexists", e]`, `["valued", e]` (present and not null);
+//! - definiteness: `["known", e]`, true when the predicate `e` is not UNKNOWN;
 //! - comparisons: `["equals"|"notequals"|"lessthan"|"lessequals"|"greaterthan"|"greaterequals", lhs, rhs]`;
-//! - pattern match: `["like", lhs, pattern]` (pattern is `["value", "…"]` or `["regex", "…"]`);
+//! - regex match: `["like", lhs, pattern]`, a regex search, where pattern is `["value", "…"]`,
+//!   `["regex", "…"]`, or an operand that reads it from the document or a parameter (also
+//!   as `["regex", operand]`);
+//! - SQL `LIKE`: `["sqllike", lhs, pattern, <escape?>]`, anchored with `%`/`_` wildcards,
+//!   optionally with a one-character escape string;
+//! - list membership: `["in", lhs, [v…]]`, where each `v` is a bare JSON scalar (`NOT IN` is
+//!   `["not", ["in", …]]`);
+//! - inclusive range: `["between", lhs, low, high]`;
+//! - conditional operand: `["case", ["when", cond, x]…, ["else", y]]`, the `else` optional;
 //! - loops: `["anyin"|"everyin"|"anyeveryin", <var-id>, in, sub]`.
 //!
 //! In a `field`, an optional leading integer is the root variable id (a loop variable);
@@ -140,9 +150,17 @@ fn parse_expr(v: &Value) -> Result<Expr, ParseError> {
         "value" => Ok(Expr::Value(parse_literal(arg(arr, 1, "value")?)?)),
         "field" => parse_field(arr),
         "func" => parse_func(arr),
+        "param" => Ok(Expr::Param(
+            arg(arr, 1, "param")?
+                .as_str()
+                .ok_or(ParseError::Malformed("param"))?
+                .to_owned(),
+        )),
         "not" => Ok(Expr::Not(boxed(arg(arr, 1, "not")?)?)),
         "exists" => Ok(Expr::Exists(boxed(arg(arr, 1, "exists")?)?)),
         "notexists" => Ok(Expr::NotExists(boxed(arg(arr, 1, "notexists")?)?)),
+        "valued" => Ok(Expr::Valued(boxed(arg(arr, 1, "valued")?)?)),
+        "known" => Ok(Expr::Known(boxed(arg(arr, 1, "known")?)?)),
         "or" => Ok(Expr::Or(parse_list(&arr[1..])?)),
         "and" => Ok(Expr::And(parse_list(&arr[1..])?)),
         "anyin" => parse_loop(arr, LoopType::Any),
@@ -154,18 +172,25 @@ fn parse_expr(v: &Value) -> Result<Expr, ParseError> {
         "lessequals" => parse_cmp(arr, CompareOp::LessEquals),
         "greaterthan" => parse_cmp(arr, CompareOp::GreaterThan),
         "greaterequals" => parse_cmp(arr, CompareOp::GreaterEquals),
+        "in" => parse_in(arr),
+        "between" => Ok(Expr::Between {
+            lhs: boxed(arg(arr, 1, "between")?)?,
+            low: boxed(arg(arr, 2, "between")?)?,
+            high: boxed(arg(arr, 3, "between")?)?,
+        }),
         "like" => Ok(Expr::Matches {
             lhs: boxed(arg(arr, 1, "like")?)?,
             pattern: boxed(arg(arr, 2, "like")?)?,
         }),
-        // A regex operand is just a string pattern in this AST; the enclosing `like`
-        // gives it match semantics.
-        "regex" => {
-            let p = arg(arr, 1, "regex")?
-                .as_str()
-                .ok_or(ParseError::Malformed("regex"))?;
-            Ok(Expr::Value(Literal::String(p.to_owned())))
-        }
+        "sqllike" => parse_sql_like(arr),
+        "case" => parse_case(arr),
+        // A regex operand is just its pattern in this AST, a string or an operand that
+        // yields one; the enclosing `like` gives it match semantics.
+        "regex" => match arg(arr, 1, "regex")? {
+            Value::String(p) => Ok(Expr::Value(Literal::String(p.clone()))),
+            p @ Value::Array(_) => parse_expr(p),
+            _ => Err(ParseError::Malformed("regex")),
+        },
         "true" => Ok(Expr::True),
         "false" => Ok(Expr::False),
         // gojsonsm has a `["time", "..."]` node; date comparison here is the `DATE()`
@@ -178,6 +203,57 @@ fn parse_expr(v: &Value) -> Result<Expr, ParseError> {
     }
 }
 
+/// `["case", ["when", cond, then]…, ["else", otherwise]?]`: at least one `when`, and an
+/// `else` only last.
+fn parse_case(arr: &[Value]) -> Result<Expr, ParseError> {
+    let mut whens = Vec::new();
+    let mut otherwise = None;
+    for (i, branch) in arr.iter().enumerate().skip(1) {
+        let branch = branch.as_array().ok_or(ParseError::Malformed("case"))?;
+        match (branch.first().and_then(Value::as_str), branch.len()) {
+            (Some("when"), 3) => {
+                whens.push((parse_expr(&branch[1])?, parse_expr(&branch[2])?));
+            }
+            (Some("else"), 2) if i == arr.len() - 1 => otherwise = Some(boxed(&branch[1])?),
+            _ => return Err(ParseError::Malformed("case")),
+        }
+    }
+    if whens.is_empty() {
+        return Err(ParseError::Malformed("case"));
+    }
+    Ok(Expr::Case { whens, otherwise })
+}
+
+/// `["sqllike", lhs, pattern, <escape?>]`: a SQL `LIKE`, with an optional one-character
+/// escape string. A `["regex", …]` pattern is refused rather than read as a `LIKE` pattern.
+fn parse_sql_like(arr: &[Value]) -> Result<Expr, ParseError> {
+    let lhs = boxed(arg(arr, 1, "sqllike")?)?;
+    let pattern = arg(arr, 2, "sqllike")?;
+    let is_regex = pattern
+        .as_array()
+        .and_then(|p| p.first())
+        .and_then(Value::as_str)
+        == Some("regex");
+    if is_regex || arr.len() > 4 {
+        return Err(ParseError::Malformed("sqllike"));
+    }
+    let escape = match arr.get(3) {
+        None => None,
+        Some(e) => {
+            let mut chars = e.as_str().ok_or(ParseError::Malformed("sqllike"))?.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Some(c),
+                _ => return Err(ParseError::Malformed("sqllike")),
+            }
+        }
+    };
+    Ok(Expr::Like {
+        lhs,
+        pattern: boxed(pattern)?,
+        escape,
+    })
+}
+
 fn arg<'a>(arr: &'a [Value], i: usize, name: &'static str) -> Result<&'a Value, ParseError> {
     arr.get(i).ok_or(ParseError::Malformed(name))
 }
@@ -249,6 +325,19 @@ fn parse_cmp(arr: &[Value], op: CompareOp) -> Result<Expr, ParseError> {
     ))
 }
 
+fn parse_in(arr: &[Value]) -> Result<Expr, ParseError> {
+    let list = arg(arr, 2, "in")?
+        .as_array()
+        .ok_or(ParseError::Malformed("in"))?
+        .iter()
+        .map(parse_literal)
+        .collect::<Result<_, _>>()?;
+    Ok(Expr::In {
+        lhs: boxed(arg(arr, 1, "in")?)?,
+        list,
+    })
+}
+
 fn parse_literal(v: &Value) -> Result<Literal, ParseError> {
     Ok(match v {
         Value::Null => Literal::Null,
@@ -272,14 +361,15 @@ fn parse_literal(v: &Value) -> Result<Literal, ParseError> {
 /// Serialize an expression back to the JSON-array [`Value`] form.
 ///
 /// The inverse of parsing for every node the format supports. Array indices round-trip as
-/// `"[N]"` segments, matching gojsonsm's string-only field paths. Currently total (never
-/// `None`), but kept fallible for formats nodes added later may not represent.
+/// `"[N]"` segments, matching gojsonsm's string-only field paths. `None` for a regex match
+/// whose pattern is not a string literal, which the format has no spelling for.
 pub fn to_value(expr: &Expr) -> Option<Value> {
     Some(match expr {
         Expr::True => Value::Array(vec!["true".into()]),
         Expr::False => Value::Array(vec!["false".into()]),
         Expr::Value(lit) => arr2("value", literal_to_value(lit)),
         Expr::Field(f) => field_to_value(f)?,
+        Expr::Param(name) => arr2("param", Value::from(name.clone())),
         Expr::Func(f) => {
             let mut items = vec![Value::from("func"), Value::from(f.name.clone())];
             for a in &f.args {
@@ -290,6 +380,8 @@ pub fn to_value(expr: &Expr) -> Option<Value> {
         Expr::Not(e) => arr2("not", to_value(e)?),
         Expr::Exists(e) => arr2("exists", to_value(e)?),
         Expr::NotExists(e) => arr2("notexists", to_value(e)?),
+        Expr::Valued(e) => arr2("valued", to_value(e)?),
+        Expr::Known(e) => arr2("known", to_value(e)?),
         Expr::And(es) => list_to_value("and", es)?,
         Expr::Or(es) => list_to_value("or", es)?,
         Expr::Compare { op, lhs, rhs } => Value::Array(vec![
@@ -300,6 +392,42 @@ pub fn to_value(expr: &Expr) -> Option<Value> {
         Expr::Matches { lhs, pattern } => {
             Value::Array(vec!["like".into(), to_value(lhs)?, to_value(pattern)?])
         }
+        Expr::Like {
+            lhs,
+            pattern,
+            escape,
+        } => {
+            let mut items = vec!["sqllike".into(), to_value(lhs)?, to_value(pattern)?];
+            if let Some(c) = escape {
+                items.push(Value::from(c.to_string()));
+            }
+            Value::Array(items)
+        }
+        Expr::Between { lhs, low, high } => Value::Array(vec![
+            "between".into(),
+            to_value(lhs)?,
+            to_value(low)?,
+            to_value(high)?,
+        ]),
+        Expr::Case { whens, otherwise } => {
+            let mut items = vec![Value::from("case")];
+            for (cond, then) in whens {
+                items.push(Value::Array(vec![
+                    "when".into(),
+                    to_value(cond)?,
+                    to_value(then)?,
+                ]));
+            }
+            if let Some(e) = otherwise {
+                items.push(arr2("else", to_value(e)?));
+            }
+            Value::Array(items)
+        }
+        Expr::In { lhs, list } => Value::Array(vec![
+            "in".into(),
+            to_value(lhs)?,
+            list.iter().map(literal_to_value).collect(),
+        ]),
         Expr::Loop {
             loop_type,
             var,
@@ -422,6 +550,43 @@ mod tests {
         assert!(!m.matches(br#"{"a": [20, 10]}"#).unwrap().matched());
     }
 
+    #[test]
+    fn predicate_funcs_stand_as_conditions() {
+        let def = compile_str(
+            r#"["and", ["func", "isObject", ["field", "a"]], ["not", ["func", "isArray", ["field", "b"]]]]"#,
+            &jsonsm::compile::Projection::new(),
+            &jsonsm::collation::DefaultCollation,
+        )
+        .unwrap();
+        let mut m = jsonsm::matcher::FastMatcher::new(&def);
+        assert!(m.matches(br#"{"a": {}, "b": 1}"#).unwrap().matched());
+        assert!(!m.matches(br#"{"a": {}, "b": []}"#).unwrap().matched());
+        assert!(!m.matches(br#"{"a": {}}"#).unwrap().matched());
+    }
+
+    #[test]
+    fn geo_funcs_read_lat_and_lon_in_either_order() {
+        let def = compile_str(
+            r#"["and",
+                ["lessthan",
+                    ["func", "geoDistance", ["field", "loc", "lat"], ["field", "loc", "lon"],
+                        ["value", 0], ["value", 0]],
+                    ["value", 200000]],
+                ["func", "geoWithinPolygon", ["field", "loc", "lat"], ["field", "loc", "lon"],
+                    ["value", -1], ["value", -1], ["value", 1], ["value", -1],
+                    ["value", 1], ["value", 1], ["value", -1], ["value", 1]]]"#,
+            &jsonsm::compile::Projection::new(),
+            &jsonsm::collation::DefaultCollation,
+        )
+        .unwrap();
+        let mut m = jsonsm::matcher::FastMatcher::new(&def);
+        let mut matched = |doc: &[u8]| m.matches(doc).unwrap().matched();
+        assert!(matched(br#"{"loc": {"lat": 0.5, "lon": 0.5}}"#));
+        assert!(matched(br#"{"loc": {"lon": 0.5, "lat": 0.5}}"#));
+        assert!(!matched(br#"{"loc": {"lon": 1.5, "lat": 0.5}}"#));
+        assert!(!matched(br#"{"loc": {"lon": 0.5}}"#));
+    }
+
     #[test]
     fn parses_string_equals() {
         let e = parse_str(r#"["equals", ["field", "name"], ["value", "Daphne"]]"#).unwrap();
@@ -523,6 +688,61 @@ mod tests {
                 pattern: Box::new(Expr::Value(Literal::String("^a.*z$".into()))),
             }
         );
+        assert_eq!(
+            parse_str(r#"["like", ["field", "x"], ["regex", ["param", "re"]]]"#).unwrap(),
+            Expr::Matches {
+                lhs: Box::new(Expr::Field(Field::root(vec![key("x")]))),
+                pattern: Box::new(Expr::Param("re".into())),
+            }
+        );
+        // The baseline form keeps its regex meaning whatever the pattern operand.
+        assert_eq!(
+            parse_str(r#"["like", ["field", "x"], ["value", "a%"]]"#).unwrap(),
+            Expr::Matches {
+                lhs: Box::new(Expr::Field(Field::root(vec![key("x")]))),
+                pattern: Box::new(Expr::Value(Literal::String("a%".into()))),
+            }
+        );
+        assert_eq!(
+            parse_str(r#"["sqllike", ["field", "x"], ["value", "a!%%"], "!"]"#).unwrap(),
+            Expr::Like {
+                lhs: Box::new(Expr::Field(Field::root(vec![key("x")]))),
+                pattern: Box::new(Expr::Value(Literal::String("a!%%".into()))),
+                escape: Some('!'),
+            }
+        );
+        assert_eq!(
+            parse_str(r#"["case", ["when", ["true"], ["value", 1]], ["else", ["field", "x"]]]"#)
+                .unwrap(),
+            Expr::Case {
+                whens: vec![(Expr::True, Expr::Value(Literal::Int(1)))],
+                otherwise: Some(Box::new(Expr::Field(Field::root(vec![key("x")])))),
+            }
+        );
+        for bad in [
+            r#"["case"]"#,
+            r#"["case", ["else", ["value", 1]]]"#,
+            r#"["case", ["else", ["value", 1]], ["when", ["true"], ["value", 1]]]"#,
+            r#"["case", ["when", ["true"]]]"#,
+            r#"["case", ["true"]]"#,
+        ] {
+            assert!(
+                matches!(parse_str(bad), Err(ParseError::Malformed("case"))),
+                "{bad}"
+            );
+        }
+        for bad in [
+            r#"["sqllike", ["field", "x"]]"#,
+            r#"["sqllike", ["field", "x"], ["value", "a%"], "!!"]"#,
+            r#"["sqllike", ["field", "x"], ["value", "a%"], 1]"#,
+            r#"["sqllike", ["field", "x"], ["value", "a%"], "!", "!"]"#,
+            r#"["sqllike", ["field", "x"], ["regex", "a"]]"#,
+        ] {
+            assert!(
+                matches!(parse_str(bad), Err(ParseError::Malformed("sqllike"))),
+                "{bad}"
+            );
+        }
         assert_eq!(
             parse_str(r#"["exists", ["field", "sometimes"]]"#).unwrap(),
             Expr::Exists(Box::new(Expr::Field(Field::root(vec![key("sometimes")]))))
@@ -546,6 +766,18 @@ mod tests {
             parse_str(r#"["value", [1,2]]"#),
             Err(ParseError::UnsupportedValue)
         ));
+        assert!(matches!(
+            parse_str(r#"["between", ["field","a"], ["value", 1]]"#),
+            Err(ParseError::Malformed("between"))
+        ));
+        assert!(matches!(
+            parse_str(r#"["in", ["field","a"], "a"]"#),
+            Err(ParseError::Malformed("in"))
+        ));
+        assert!(matches!(
+            parse_str(r#"["in", ["field","a"], [1, [2]]]"#),
+            Err(ParseError::UnsupportedValue)
+        ));
         assert!(matches!(
             parse_str(r#"["time", "2020-01-01"]"#),
             Err(ParseError::Unsupported(msg)) if msg.starts_with("time")
@@ -577,6 +809,14 @@ mod tests {
             Expr::Not(Box::new(Expr::Exists(Box::new(Expr::Field(Field::root(
                 vec![key("x")],
             )))))),
+            Expr::Not(Box::new(Expr::Valued(Box::new(Expr::Field(Field::root(
+                vec![key("x")],
+            )))))),
+            Expr::Known(Box::new(Expr::compare(
+                CompareOp::LessThan,
+                Expr::Field(Field::root(vec![key("a")])),
+                Expr::Field(Field::root(vec![key("b")])),
+            ))),
             Expr::Loop {
                 loop_type: LoopType::AnyEvery,
                 var: 2,
@@ -596,11 +836,73 @@ mod tests {
                 lhs: Box::new(Expr::Field(Field::root(vec![key("email")]))),
                 pattern: Box::new(Expr::Value(Literal::String("@example\\.com$".into()))),
             },
+            Expr::Matches {
+                lhs: Box::new(Expr::Field(Field::root(vec![key("value")]))),
+                pattern: Box::new(Expr::Field(Field::root(vec![key("rule")]))),
+            },
+            Expr::Like {
+                lhs: Box::new(Expr::Field(Field::root(vec![key("sku")]))),
+                pattern: Box::new(Expr::Value(Literal::String("AB\\_%".into()))),
+                escape: Some('\\'),
+            },
+            Expr::Not(Box::new(Expr::Like {
+                lhs: Box::new(Expr::Field(Field::root(vec![key("name")]))),
+                pattern: Box::new(Expr::Param("prefix".into())),
+                escape: None,
+            })),
+            Expr::compare(
+                CompareOp::Equals,
+                Expr::Field(Field::root(vec![key("id")])),
+                Expr::Param("allowed_ids".into()),
+            ),
+            Expr::compare(
+                CompareOp::GreaterThan,
+                Expr::Case {
+                    whens: vec![
+                        (
+                            Expr::Exists(Box::new(Expr::Field(Field::root(vec![key("a")])))),
+                            Expr::Field(Field::root(vec![key("a")])),
+                        ),
+                        (Expr::True, Expr::Value(Literal::Int(0))),
+                    ],
+                    otherwise: Some(Box::new(Expr::Value(Literal::Null))),
+                },
+                Expr::Value(Literal::Int(1)),
+            ),
+            Expr::compare(
+                CompareOp::Equals,
+                Expr::Case {
+                    whens: vec![(Expr::False, Expr::Param("p".into()))],
+                    otherwise: None,
+                },
+                Expr::Value(Literal::Int(1)),
+            ),
+            Expr::Between {
+                lhs: Box::new(Expr::Field(Field::root(vec![key("ts")]))),
+                low: Box::new(Expr::Value(Literal::Int(100))),
+                high: Box::new(Expr::Param("until".into())),
+            },
+            Expr::Not(Box::new(Expr::In {
+                lhs: Box::new(Expr::Field(Field::root(vec![key("status")]))),
+                list: vec![
+                    Literal::String("a".into()),
+                    Literal::Int(-1),
+                    Literal::Null,
+                    Literal::Bool(true),
+                ],
+            })),
         ];
         for e in exprs {
             let v = to_value(&e).expect("serializable");
             let back = parse_expr(&v).expect("re-parses");
             assert_eq!(back, e, "round-trip mismatch for {e:?}");
         }
+        // A regex whose pattern is a non-string constant is spelled too, for the compiler to
+        // reject.
+        let e = Expr::Matches {
+            lhs: Box::new(Expr::Field(Field::root(vec![key("x")]))),
+            pattern: Box::new(Expr::Value(Literal::Int(1))),
+        };
+        assert_eq!(parse_expr(&to_value(&e).expect("serializable")).unwrap(), e);
     }
 }
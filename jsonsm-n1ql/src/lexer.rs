@@ -68,6 +68,12 @@ pub enum Token {
     Null,
     #[token("missing", ignore(ascii_case))]
     Missing,
+    #[token("valued", ignore(ascii_case))]
+    Valued,
+    #[token("known", ignore(ascii_case))]
+    Known,
+    #[token("unknown", ignore(ascii_case))]
+    Unknown,
     #[token("true", ignore(ascii_case))]
     True,
     #[token("false", ignore(ascii_case))]
@@ -82,10 +88,24 @@ pub enum Token {
     Every,
     #[token("in", ignore(ascii_case))]
     In,
+    #[token("between", ignore(ascii_case))]
+    Between,
+    #[token("like", ignore(ascii_case))]
+    Like,
+    #[token("escape", ignore(ascii_case))]
+    Escape,
     #[token("satisfies", ignore(ascii_case))]
     Satisfies,
     #[token("end", ignore(ascii_case))]
     End,
+    #[token("case", ignore(ascii_case))]
+    Case,
+    #[token("when", ignore(ascii_case))]
+    When,
+    #[token("then", ignore(ascii_case))]
+    Then,
+    #[token("else", ignore(ascii_case))]
+    Else,
     #[regex(r"[0-9]+(\.[0-9]+)?([eE][-+]?[0-9]+)?", |l| l.slice().to_owned())]
     Num(String),
     #[regex(r#""([^"\\]|\\.)*""#, |l| l.slice().to_owned())]
@@ -96,6 +116,10 @@ pub enum Token {
     BqIdent(String),
     #[regex(r"[A-Za-z_$][A-Za-z0-9_$]*", |l| l.slice().to_owned())]
     Ident(String),
+    /// A named parameter, `$name`, carried without its `$`. It outranks `Ident` — which also
+    /// accepts a leading `$` — so a field whose name starts with `$` must be backtick-quoted.
+    #[regex(r"\$[A-Za-z_][A-Za-z0-9_]*", |l| l.slice()[1..].to_owned(), priority = 10)]
+    Param(String),
 }
 
 impl std::fmt::Display for Token {
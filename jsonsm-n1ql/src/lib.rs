@@ -4,12 +4,35 @@
 //! `REGEXP_CONTAINS(email, "@example\\.com$")` into a [`jsonsm_ast::Expr`], using an
 //! LALRPOP-generated LR parser (`grammar.lalrpop`). The grammar mirrors gojsonsm's
 //! `filterExprParser`: comparisons (`= == <> != < <= > >=`), `AND`/`OR`/`NOT` (also
-//! `&& || !`), `IS [NOT] NULL`/`MISSING`, arithmetic (`+ - * / %`, unary `-`) lowered to
-//! math functions, function calls, `EXISTS(field)`, and `REGEXP_CONTAINS(field, pat)`.
-//! Field paths support `a.b`, `a[0]`, and backtick-quoted segments. Keywords are
-//! case-insensitive. Array loops are written `ANY`/`EVERY`/`ANY AND EVERY <var> IN <array>
-//! SATISFIES <predicate> END`; the loop variable is bound by name and resolved to the
-//! AST's numeric variable id in a post-parse pass.
+//! `&& || !`), `IS [NOT] NULL`/`MISSING`/`VALUED`, `(pred) IS [NOT] KNOWN`/`UNKNOWN`,
+//! `[NOT] IN (…)` over a list of constants, `[NOT] BETWEEN … AND …`,
+//! `[NOT] LIKE pat [ESCAPE 'c']`, arithmetic (`+ - * / %`, unary `-`) lowered to math
+//! functions, function calls (math, `DATE` and the date functions `DATE_PART`, `DATE_ADD`,
+//! `DATE_DIFF`, `DATE_TRUNC` — each also with N1QL's `_STR` and `_MILLIS` suffixes —
+//! `STR_TO_MILLIS` or `MILLIS`, `MILLIS_TO_STR`, `MILLIS_TO_UTC`, `MILLIS_TO_TZ`, and
+//! `NOW`, `NOW_MILLIS`, `NOW_STR` or `NOW_UTC`, which read the matcher's clock; the string
+//! functions `LOWER`, `UPPER`, `LENGTH`, `SUBSTR`, `TRIM`, `CONTAINS`, `STARTS_WITH`,
+//! `ENDS_WITH`, and `TYPE` with the type predicates `IS_STRING`, `IS_NUMBER`, `IS_BOOLEAN`,
+//! `IS_ARRAY`, `IS_OBJECT`; a predicate may stand as a condition; `IFMISSING`, `IFNULL`,
+//! `IFMISSINGORNULL`, `COALESCE` for defaults; `ARRAY_LENGTH`, `ARRAY_CONTAINS`,
+//! `ARRAY_SUM`, `ARRAY_AVG`, `ARRAY_MIN`, `ARRAY_MAX` over arrays; `OBJECT_LENGTH`,
+//! `OBJECT_NAMES`, `OBJECT_VALUES`, `OBJECT_PAIRS` over objects; the conversions
+//! `TONUMBER`, `TOSTRING`, `TOBOOLEAN`; the bit functions `BITAND`, `BITOR`, `BITXOR`,
+//! `BITNOT`, `BITSHIFT`, `BITTEST` or `ISBITSET`; the geo functions `GEO_DISTANCE`,
+//! `WITHIN_BBOX`, `WITHIN_POLYGON`; the IP functions `IP_IN_CIDR`, `IP_IN_RANGE`,
+//! `IP_VERSION`, `IP_NORMALIZE`; and `SEMVER_CMP`, `SEMVER_LT` over semantic versions),
+//! `EXISTS(field)`, and `REGEXP_CONTAINS(field, pat)`.
+//! `CASE WHEN cond THEN x [WHEN …] [ELSE y] END` is an operand. Field paths support `a.b`,
+//! `a[0]`, and backtick-quoted segments. Keywords are case-insensitive. Array loops are
+//! written `ANY`/`EVERY`/`ANY AND EVERY <var> IN <array> SATISFIES <predicate> END`, where
+//! `<array>` may also be `OBJECT_VALUES`, `OBJECT_NAMES` or `OBJECT_PAIRS` of a field to
+//! loop over an object's members; the loop variable is bound by name and resolved to the
+//! AST's numeric variable id in a post-parse pass. `$name` is a named parameter, bound when
+//! the compiled filter is matched rather than when it is parsed. Any other function name is
+//! passed through as written and resolved when the expression is compiled: against a
+//! [`FunctionRegistry`](jsonsm::registry::FunctionRegistry) handed to
+//! [`compile_with`](jsonsm::compile::compile_with), or else as a compile error, so a
+//! misspelled function is reported rather than read as missing.
 
 use jsonsm_ast::{Expr, Func, Literal, PathComponent, VariableId};
 
@@ -105,16 +128,33 @@ fn resolve(e: &mut Expr, names: &[String], scope: &mut Vec<(String, VariableId)>
             }
         }
         Expr::Func(func) => func.args.iter_mut().for_each(|a| resolve(a, names, scope)),
-        Expr::Not(s) | Expr::Exists(s) | Expr::NotExists(s) => resolve(s, names, scope),
+        Expr::Not(s) | Expr::Exists(s) | Expr::NotExists(s) | Expr::Valued(s) | Expr::Known(s) => {
+            resolve(s, names, scope)
+        }
         Expr::And(v) | Expr::Or(v) => v.iter_mut().for_each(|x| resolve(x, names, scope)),
         Expr::Compare { lhs, rhs, .. } => {
             resolve(lhs, names, scope);
             resolve(rhs, names, scope);
         }
-        Expr::Matches { lhs, pattern } => {
+        Expr::Matches { lhs, pattern } | Expr::Like { lhs, pattern, .. } => {
             resolve(lhs, names, scope);
             resolve(pattern, names, scope);
         }
+        Expr::In { lhs, .. } => resolve(lhs, names, scope),
+        Expr::Case { whens, otherwise } => {
+            for (cond, then) in whens {
+                resolve(cond, names, scope);
+                resolve(then, names, scope);
+            }
+            if let Some(e) = otherwise {
+                resolve(e, names, scope);
+            }
+        }
+        Expr::Between { lhs, low, high } => {
+            resolve(lhs, names, scope);
+            resolve(low, names, scope);
+            resolve(high, names, scope);
+        }
         Expr::Loop {
             var,
             in_expr,
@@ -127,7 +167,7 @@ fn resolve(e: &mut Expr, names: &[String], scope: &mut Vec<(String, VariableId)>
             resolve(sub_expr, names, scope);
             scope.pop();
         }
-        Expr::Value(_) | Expr::True | Expr::False => {}
+        Expr::Value(_) | Expr::Param(_) | Expr::True | Expr::False => {}
     }
 }
 
@@ -200,7 +240,8 @@ pub(crate) fn and_join(l: Expr, r: Expr) -> Expr {
 }
 
 /// A bare operand used at boolean position: a boolean literal becomes the constant
-/// `True`/`False`; anything else is left as-is (the compiler rejects non-boolean operands).
+/// `True`/`False`; anything else is left as-is (the compiler rejects non-boolean operands,
+/// other than a call to a predicate function such as `CONTAINS` or `IS_STRING`).
 pub(crate) fn as_condition(e: Expr) -> Expr {
     match e {
         Expr::Value(Literal::Bool(true)) => Expr::True,
@@ -209,6 +250,53 @@ pub(crate) fn as_condition(e: Expr) -> Expr {
     }
 }
 
+/// Build `(e) IS KNOWN` when `known`, else `(e) IS UNKNOWN` — its negation, since a `Known`
+/// node is never UNKNOWN itself.
+pub(crate) fn known(e: Expr, known: bool) -> Expr {
+    let k = Expr::Known(Box::new(e));
+    if known {
+        k
+    } else {
+        Expr::Not(Box::new(k))
+    }
+}
+
+/// Build an inclusive range test. The `AND` between the bounds belongs to `BETWEEN`, so
+/// `a BETWEEN 1 AND 2 AND b = 3` is a range conjoined with a comparison.
+pub(crate) fn between(lhs: Expr, low: Expr, high: Expr) -> Expr {
+    Expr::Between {
+        lhs: Box::new(lhs),
+        low: Box::new(low),
+        high: Box::new(high),
+    }
+}
+
+/// Build a `LIKE` test. Without an `ESCAPE` clause the escape character is `\`, as in N1QL;
+/// since a string literal consumes one level of backslashes itself, the pattern for the
+/// literal text `100%` is written `'100\\%'`.
+pub(crate) fn like(lhs: Expr, pattern: Expr, escape: Option<char>) -> Expr {
+    Expr::Like {
+        lhs: Box::new(lhs),
+        pattern: Box::new(pattern),
+        escape: Some(escape.unwrap_or('\\')),
+    }
+}
+
+/// The character of an `ESCAPE` clause's string literal `s`, found at byte `at`.
+pub(crate) fn like_escape(
+    at: usize,
+    s: &str,
+) -> Result<char, lalrpop_util::ParseError<usize, lexer::Token, lexer::LexError>> {
+    let s = string_literal(s);
+    let mut chars = s.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok(c),
+        _ => Err(lalrpop_util::ParseError::User {
+            error: lexer::LexError(at),
+        }),
+    }
+}
+
 /// Negate an operand: fold numeric literals; otherwise wrap in `mathNegate`.
 pub(crate) fn negate(e: Expr) -> Expr {
     match e {
@@ -227,9 +315,27 @@ pub(crate) fn func(name: &str, args: Vec<Expr>) -> Expr {
     })
 }
 
+/// Negate a numeric constant in an `IN` list, where there is no operand to wrap in
+/// `mathNegate`. `-9223372036854775808` lexes as a `Uint` and negates to `i64::MIN`.
+pub(crate) fn neg_literal(lit: Literal) -> Literal {
+    match lit {
+        Literal::Int(i) => Literal::Int(-i),
+        Literal::Uint(u) => 0i64
+            .checked_sub_unsigned(u)
+            .map_or(Literal::Float(-(u as f64)), Literal::Int),
+        Literal::Float(f) => Literal::Float(-f),
+        other => other,
+    }
+}
+
 /// Parse a numeric literal token into an `Int`/`Uint`/`Float` value.
 pub(crate) fn num_literal(s: &str) -> Expr {
-    let lit = if s.bytes().any(|b| matches!(b, b'.' | b'e' | b'E')) {
+    Expr::Value(num_value(s))
+}
+
+/// Parse a numeric literal token into an `Int`/`Uint`/`Float` constant.
+pub(crate) fn num_value(s: &str) -> Literal {
+    if s.bytes().any(|b| matches!(b, b'.' | b'e' | b'E')) {
         Literal::Float(s.parse().unwrap_or(f64::NAN))
     } else if let Ok(i) = s.parse::<i64>() {
         Literal::Int(i)
@@ -237,8 +343,7 @@ pub(crate) fn num_literal(s: &str) -> Expr {
         Literal::Uint(u)
     } else {
         Literal::Float(s.parse().unwrap_or(f64::NAN))
-    };
-    Expr::Value(lit)
+    }
 }
 
 /// Decode a quoted string literal token (surrounding quotes stripped, escapes resolved).
@@ -308,6 +413,62 @@ fn map_func_name(name: &str) -> String {
         "PI" => "mathPi",
         "E" => "mathE",
         "DATE" => "date",
+        "DATE_PART" | "DATE_PART_STR" | "DATE_PART_MILLIS" => "datePart",
+        "DATE_ADD" | "DATE_ADD_STR" | "DATE_ADD_MILLIS" => "dateAdd",
+        "DATE_DIFF" | "DATE_DIFF_STR" | "DATE_DIFF_MILLIS" => "dateDiff",
+        "DATE_TRUNC" | "DATE_TRUNC_STR" | "DATE_TRUNC_MILLIS" => "dateTrunc",
+        "STR_TO_MILLIS" | "MILLIS" => "strToMillis",
+        "MILLIS_TO_STR" => "millisToStr",
+        "MILLIS_TO_UTC" => "millisToUtc",
+        "MILLIS_TO_TZ" => "millisToTz",
+        "NOW" => "now",
+        "NOW_MILLIS" => "nowMillis",
+        "NOW_STR" | "NOW_UTC" => "nowStr",
+        "LOWER" => "strLower",
+        "UPPER" => "strUpper",
+        "LENGTH" => "strLength",
+        "SUBSTR" => "strSubstr",
+        "CONTAINS" => "strContains",
+        "STARTS_WITH" => "strStartsWith",
+        "ENDS_WITH" => "strEndsWith",
+        "TRIM" => "strTrim",
+        "TYPE" => "type",
+        "IS_STRING" => "isString",
+        "IS_NUMBER" => "isNumber",
+        "IS_BOOLEAN" => "isBoolean",
+        "IS_ARRAY" => "isArray",
+        "IS_OBJECT" => "isObject",
+        "IFMISSING" => "ifMissing",
+        "IFNULL" => "ifNull",
+        "IFMISSINGORNULL" | "COALESCE" => "ifMissingOrNull",
+        "ARRAY_LENGTH" => "arrayLength",
+        "ARRAY_CONTAINS" => "arrayContains",
+        "ARRAY_SUM" => "arraySum",
+        "ARRAY_AVG" => "arrayAvg",
+        "ARRAY_MIN" => "arrayMin",
+        "ARRAY_MAX" => "arrayMax",
+        "OBJECT_LENGTH" => "objectLength",
+        "OBJECT_NAMES" => "objectNames",
+        "OBJECT_VALUES" => "objectValues",
+        "OBJECT_PAIRS" => "objectPairs",
+        "TONUMBER" => "toNumber",
+        "TOSTRING" => "toString",
+        "TOBOOLEAN" => "toBoolean",
+        "BITAND" => "bitAnd",
+        "BITOR" => "bitOr",
+        "BITXOR" => "bitXor",
+        "BITNOT" => "bitNot",
+        "BITSHIFT" => "bitShift",
+        "BITTEST" | "ISBITSET" => "bitTest",
+        "GEO_DISTANCE" => "geoDistance",
+        "WITHIN_BBOX" => "geoWithinBBox",
+        "WITHIN_POLYGON" => "geoWithinPolygon",
+        "IP_IN_CIDR" => "ipInCidr",
+        "IP_IN_RANGE" => "ipInRange",
+        "IP_VERSION" => "ipVersion",
+        "IP_NORMALIZE" => "ipNormalize",
+        "SEMVER_CMP" => "semverCmp",
+        "SEMVER_LT" => "semverLess",
         _ => return name.to_string(), // already-internal (mathAdd, …) or unknown: pass through
     };
     mapped.to_string()
@@ -382,6 +543,266 @@ mod tests {
         );
     }
 
+    #[test]
+    fn between() {
+        let range = |l: i64, h: i64| {
+            super::between(
+                fld(&["ts"]),
+                Expr::Value(Literal::Int(l)),
+                Expr::Value(Literal::Int(h)),
+            )
+        };
+        assert_eq!(p("ts BETWEEN 100 AND 200"), range(100, 200));
+        assert_eq!(
+            p("ts not between 100 and 200"),
+            Expr::Not(Box::new(range(100, 200)))
+        );
+        // The bounds' `AND` binds to BETWEEN; a following one joins the conjunction.
+        assert_eq!(
+            p("ts BETWEEN 100 AND 200 AND ok = TRUE"),
+            Expr::And(vec![
+                range(100, 200),
+                Expr::compare(
+                    CompareOp::Equals,
+                    fld(&["ok"]),
+                    Expr::Value(Literal::Bool(true))
+                ),
+            ])
+        );
+        assert_eq!(
+            p("ts BETWEEN -1 AND 2 OR ts BETWEEN 5 AND 6"),
+            Expr::Or(vec![range(-1, 2), range(5, 6)])
+        );
+        // Bounds are operands: arithmetic and fields are fine.
+        assert!(matches!(
+            p("ts BETWEEN start AND start + 10"),
+            Expr::Between { .. }
+        ));
+        assert!(parse_str("ts BETWEEN 1").is_err());
+
+        use jsonsm::collation::DefaultCollation;
+        use jsonsm::matcher::FastMatcher;
+        let def = compile_str(
+            "ts BETWEEN start AND start + 10",
+            &Projection::new(),
+            &DefaultCollation,
+        )
+        .unwrap();
+        let mut m = FastMatcher::new(&def);
+        let mut matched = |doc: &str| m.matches(doc.as_bytes()).unwrap().matched();
+        assert!(matched(r#"{"ts": 15, "start": 10}"#));
+        assert!(!matched(r#"{"start": 10, "ts": 21}"#));
+    }
+
+    #[test]
+    fn like() {
+        let like = |pat: &str, escape: char| Expr::Like {
+            lhs: Box::new(fld(&["name"])),
+            pattern: Box::new(Expr::Value(Literal::String(pat.into()))),
+            escape: Some(escape),
+        };
+        // Without an ESCAPE clause the escape character is a backslash.
+        assert_eq!(p("name LIKE 'ab%'"), like("ab%", '\\'));
+        assert_eq!(
+            p("name not like \"a_\""),
+            Expr::Not(Box::new(like("a_", '\\')))
+        );
+        assert_eq!(p("name LIKE 'a!%' ESCAPE '!'"), like("a!%", '!'));
+        assert_eq!(
+            p("name LIKE 'a!%' ESCAPE '!' AND ok = TRUE"),
+            Expr::And(vec![
+                like("a!%", '!'),
+                Expr::compare(
+                    CompareOp::Equals,
+                    fld(&["ok"]),
+                    Expr::Value(Literal::Bool(true))
+                ),
+            ])
+        );
+        assert!(parse_str("name LIKE 'a' ESCAPE '!!'").is_err());
+        assert!(parse_str("name LIKE 'a' ESCAPE ''").is_err());
+
+        use jsonsm::collation::DefaultCollation;
+        use jsonsm::matcher::FastMatcher;
+        let def = compile_str(
+            r"name LIKE 'J_n%' AND code NOT LIKE '100\\%'",
+            &Projection::new(),
+            &DefaultCollation,
+        )
+        .unwrap();
+        let mut m = FastMatcher::new(&def);
+        let mut matched = |doc: &str| m.matches(doc.as_bytes()).unwrap().matched();
+        assert!(matched(r#"{"name": "Janet", "code": "1000"}"#));
+        assert!(matched(r#"{"name": "Jon", "code": "100"}"#));
+        assert!(!matched(r#"{"name": "Jon", "code": "100%"}"#));
+        assert!(!matched(r#"{"name": "John", "code": "1"}"#));
+        assert!(!matched(r#"{"name": 7, "code": "1"}"#));
+        assert!(!matched(r#"{"code": "1"}"#));
+    }
+
+    #[test]
+    fn case_expressions() {
+        let is_a = Expr::compare(
+            CompareOp::Equals,
+            fld(&["type"]),
+            Expr::Value(Literal::String("a".into())),
+        );
+        assert_eq!(
+            p("CASE WHEN type = 'a' THEN price * 1.2 ELSE price END > 100"),
+            Expr::compare(
+                CompareOp::GreaterThan,
+                Expr::Case {
+                    whens: vec![(
+                        is_a.clone(),
+                        func("mathMultiply", vec![fld(&["price"]), num_literal("1.2")]),
+                    )],
+                    otherwise: Some(Box::new(fld(&["price"]))),
+                },
+                Expr::Value(Literal::Int(100)),
+            )
+        );
+        // Several WHENs, any condition, and no ELSE.
+        assert_eq!(
+            p("case when type = 'a' or x IS MISSING then 1 when ok then 2 end = 1"),
+            Expr::compare(
+                CompareOp::Equals,
+                Expr::Case {
+                    whens: vec![
+                        (
+                            Expr::Or(vec![is_a, Expr::NotExists(Box::new(fld(&["x"])))]),
+                            Expr::Value(Literal::Int(1)),
+                        ),
+                        (fld(&["ok"]), Expr::Value(Literal::Int(2))),
+                    ],
+                    otherwise: None,
+                },
+                Expr::Value(Literal::Int(1)),
+            )
+        );
+        assert!(parse_str("CASE ELSE 1 END = 1").is_err());
+        assert!(parse_str("CASE WHEN a = 1 THEN 1 = 1").is_err());
+
+        // Loop variables resolve inside the branches.
+        let e = p("ANY i IN items SATISFIES \
+                   CASE WHEN i.alt IS NOT MISSING THEN i.alt ELSE i.name END = 'x' END");
+        let Expr::Loop { sub_expr, .. } = e else {
+            panic!("expected a loop");
+        };
+        let Expr::Compare { lhs, .. } = *sub_expr else {
+            panic!("expected a comparison");
+        };
+        let Expr::Case { otherwise, .. } = *lhs else {
+            panic!("expected a CASE");
+        };
+        assert!(matches!(
+            *otherwise.unwrap(),
+            Expr::Field(Field { root: 1, .. })
+        ));
+
+        use jsonsm::collation::DefaultCollation;
+        use jsonsm::matcher::FastMatcher;
+        let def = compile_str(
+            "CASE WHEN type = 'a' THEN price * 2 ELSE price END > 100",
+            &Projection::new(),
+            &DefaultCollation,
+        )
+        .unwrap();
+        let mut m = FastMatcher::new(&def);
+        let mut matched = |doc: &str| m.matches(doc.as_bytes()).unwrap().matched();
+        assert!(matched(r#"{"price": 60, "type": "a"}"#));
+        assert!(!matched(r#"{"type": "b", "price": 60}"#));
+        assert!(!matched(r#"{"price": 60}"#));
+        assert!(matched(r#"{"price": 160}"#));
+    }
+
+    #[test]
+    fn in_lists() {
+        let e = Expr::In {
+            lhs: Box::new(fld(&["status"])),
+            list: vec![
+                Literal::String("a".into()),
+                Literal::Int(-2),
+                Literal::Float(-1.5),
+                Literal::Null,
+                Literal::Bool(true),
+            ],
+        };
+        assert_eq!(p(r#"status IN ("a", -2, -1.5, NULL, TRUE)"#), e);
+        assert_eq!(p(r#"status in ['a', -2, -1.5, null, true]"#), e);
+        assert_eq!(
+            p(r#"status NOT IN ("a", -2, -1.5, NULL, TRUE)"#),
+            Expr::Not(Box::new(e))
+        );
+        assert_eq!(
+            p("n IN (-9223372036854775808)"),
+            Expr::In {
+                lhs: Box::new(fld(&["n"])),
+                list: vec![Literal::Int(i64::MIN)]
+            }
+        );
+        // Only constants may appear in the list.
+        assert!(parse_str("a IN (b)").is_err());
+        assert!(parse_str("a IN ($x)").is_err());
+
+        use jsonsm::collation::DefaultCollation;
+        use jsonsm::matcher::FastMatcher;
+        let def = compile_str(
+            "ANY t IN tags SATISFIES t IN ('x', 'y') END AND n NOT IN (1, 2)",
+            &Projection::new(),
+            &DefaultCollation,
+        )
+        .unwrap();
+        let mut m = FastMatcher::new(&def);
+        let mut matched = |doc: &str| m.matches(doc.as_bytes()).unwrap().matched();
+        assert!(matched(r#"{"tags": ["z", "y"], "n": 3}"#));
+        assert!(!matched(r#"{"tags": ["z"], "n": 3}"#));
+        assert!(!matched(r#"{"tags": ["x"], "n": 2}"#));
+        assert!(!matched(r#"{"tags": ["x"]}"#));
+    }
+
+    #[test]
+    fn named_parameters() {
+        use jsonsm::collation::DefaultCollation;
+        use jsonsm::matcher::FastMatcher;
+        use jsonsm::params::Params;
+
+        assert_eq!(
+            p("age >= $min_age"),
+            Expr::compare(
+                CompareOp::GreaterEquals,
+                fld(&["age"]),
+                Expr::Param("min_age".into())
+            )
+        );
+        // A `$`-prefixed field name is still reachable, backtick-quoted.
+        assert_eq!(
+            p("`$meta`.id = 1"),
+            Expr::compare(
+                CompareOp::Equals,
+                fld(&["$meta", "id"]),
+                Expr::Value(Literal::Int(1))
+            )
+        );
+
+        let def = compile_str(
+            "id = $ids AND age < $max",
+            &Projection::new(),
+            &DefaultCollation,
+        )
+        .unwrap();
+        let mut m = FastMatcher::new(&def);
+        let params = Params::new()
+            .set("ids", [Literal::Int(1), Literal::Int(2)], &DefaultCollation)
+            .scalar("max", Literal::Int(50));
+        let doc = br#"{"id": 2, "age": 30}"#;
+        assert!(m.matches_with(doc, &params).unwrap().matched());
+        assert!(!m
+            .matches_with(br#"{"id": 3, "age": 30}"#, &params)
+            .unwrap()
+            .matched());
+        assert!(!m.matches(doc).unwrap().matched());
+    }
+
     #[test]
     fn indexed_paths_match_end_to_end() {
         use jsonsm::collation::DefaultCollation;
@@ -455,6 +876,32 @@ mod tests {
         assert_eq!(p("x IS NOT MISSING"), Expr::Exists(Box::new(fld(&["x"]))));
     }
 
+    #[test]
+    fn is_valued_and_known() {
+        let valued = || Expr::Valued(Box::new(fld(&["x"])));
+        assert_eq!(p("x IS VALUED"), valued());
+        assert_eq!(p("x is not valued"), Expr::Not(Box::new(valued())));
+
+        let known = || {
+            Expr::Known(Box::new(Expr::compare(
+                CompareOp::GreaterThan,
+                fld(&["x"]),
+                Expr::Value(Literal::Int(1)),
+            )))
+        };
+        assert_eq!(p("(x > 1) IS KNOWN"), known());
+        assert_eq!(p("(x > 1) IS NOT UNKNOWN"), known());
+        assert_eq!(p("(x > 1) IS UNKNOWN"), Expr::Not(Box::new(known())));
+        assert_eq!(p("(x > 1) IS NOT KNOWN"), Expr::Not(Box::new(known())));
+        // It is a term, so NOT and AND bind around it.
+        assert_eq!(
+            p("NOT (x > 1) IS KNOWN AND x IS VALUED"),
+            Expr::And(vec![Expr::Not(Box::new(known())), valued()])
+        );
+        // The predicate has to be parenthesised.
+        assert!(parse_str("x > 1 IS KNOWN").is_err());
+    }
+
     #[test]
     fn functions_and_arithmetic() {
         assert_eq!(
@@ -496,6 +943,377 @@ mod tests {
         );
     }
 
+    #[test]
+    fn string_functions() {
+        let lower = func("strLower", vec![fld(&["name"])]);
+        assert_eq!(
+            p("lower(name) = 'ada'"),
+            Expr::compare(
+                CompareOp::Equals,
+                lower,
+                Expr::Value(Literal::String("ada".into()))
+            )
+        );
+        // The boolean ones stand as conditions; the compiler takes them as `= TRUE`.
+        let starts = func(
+            "strStartsWith",
+            vec![fld(&["sku"]), Expr::Value(Literal::String("AB".into()))],
+        );
+        assert_eq!(p("NOT STARTS_WITH(sku, 'AB')"), Expr::Not(Box::new(starts)));
+
+        use jsonsm::collation::DefaultCollation;
+        use jsonsm::matcher::FastMatcher;
+        let def = compile_str(
+            "UPPER(TRIM(code)) = 'AB' AND CONTAINS(LOWER(name), 'da') AND LENGTH(name) = 3 \
+             AND SUBSTR(name, -2, 1) = 'D'",
+            &Projection::new(),
+            &DefaultCollation,
+        )
+        .unwrap();
+        let mut m = FastMatcher::new(&def);
+        let mut matched = |doc: &str| m.matches(doc.as_bytes()).unwrap().matched();
+        assert!(matched(r#"{"code": " ab ", "name": "ADa"}"#));
+        assert!(matched(r#"{"name": "A\u0044a", "code": "aB"}"#));
+        assert!(!matched(r#"{"code": "ab", "name": "Adam"}"#));
+        assert!(!matched(r#"{"code": 7, "name": "Ada"}"#));
+    }
+
+    #[test]
+    fn type_functions() {
+        assert_eq!(p("IS_STRING(a)"), func("isString", vec![fld(&["a"])]));
+        use jsonsm::collation::DefaultCollation;
+        use jsonsm::matcher::FastMatcher;
+        let def = compile_str(
+            "TYPE(a) = 'array' OR (IS_NUMBER(a.n) AND NOT IS_BOOLEAN(b))",
+            &Projection::new(),
+            &DefaultCollation,
+        )
+        .unwrap();
+        let mut m = FastMatcher::new(&def);
+        let mut matched = |doc: &str| m.matches(doc.as_bytes()).unwrap().matched();
+        assert!(matched(r#"{"a": [1]}"#));
+        assert!(!matched(r#"{"a": {"n": "1"}, "b": null}"#));
+        // An absent `b` leaves `NOT IS_BOOLEAN(b)` UNKNOWN.
+        assert!(!matched(r#"{"a": {"n": 1}}"#));
+        assert!(matched(r#"{"a": {"n": 1}, "b": null}"#));
+        assert!(!matched(r#"{"a": {"n": 1}, "b": true}"#));
+    }
+
+    #[test]
+    fn missing_and_null_defaults() {
+        assert_eq!(
+            p("COALESCE(a, b) = 1"),
+            Expr::compare(
+                CompareOp::Equals,
+                func("ifMissingOrNull", vec![fld(&["a"]), fld(&["b"])]),
+                Expr::Value(Literal::Int(1))
+            )
+        );
+        use jsonsm::collation::DefaultCollation;
+        use jsonsm::matcher::FastMatcher;
+        let def = compile_str(
+            "IFMISSING(priority, 0) > 3 OR IFNULL(owner, 'nobody') = 'nobody'",
+            &Projection::new(),
+            &DefaultCollation,
+        )
+        .unwrap();
+        let mut m = FastMatcher::new(&def);
+        let mut matched = |doc: &str| m.matches(doc.as_bytes()).unwrap().matched();
+        assert!(matched(r#"{"priority": 4}"#));
+        assert!(matched(r#"{"priority": 1, "owner": null}"#));
+        assert!(!matched(r#"{"priority": 1, "owner": "ann"}"#));
+        // An absent `priority` defaults to 0; an absent `owner` is not null, so stays missing.
+        assert!(!matched(r#"{}"#));
+    }
+
+    #[test]
+    fn array_functions() {
+        use jsonsm::collation::DefaultCollation;
+        use jsonsm::matcher::FastMatcher;
+        let def = compile_str(
+            "ARRAY_LENGTH(tags) > 2 AND ARRAY_CONTAINS(tags, 'red') \
+             AND ARRAY_SUM(qty) = 6 AND ARRAY_MAX(qty) = 3",
+            &Projection::new(),
+            &DefaultCollation,
+        )
+        .unwrap();
+        let mut m = FastMatcher::new(&def);
+        let mut matched = |doc: &str| m.matches(doc.as_bytes()).unwrap().matched();
+        assert!(matched(
+            r#"{"tags": ["r\u0065d", "blue", "x"], "qty": [1, 2.0, 3, null]}"#
+        ));
+        assert!(!matched(r#"{"tags": ["red", "blue"], "qty": [1, 2, 3]}"#));
+        assert!(!matched(r#"{"tags": ["red", "blue", "x"], "qty": [6]}"#));
+        assert!(!matched(r#"{"tags": "red blue x", "qty": [1, 2, 3]}"#));
+        // Only a plain array field is aggregated; there is no `[*]` projection.
+        assert!(parse_str("ARRAY_SUM(items[*].qty) > 1").is_err());
+    }
+
+    #[test]
+    fn object_functions_and_member_loops() {
+        use jsonsm::collation::DefaultCollation;
+        use jsonsm::matcher::FastMatcher;
+        let def = compile_str(
+            "OBJECT_LENGTH(labels) > 1 \
+             AND ANY v IN OBJECT_VALUES(labels) SATISFIES v = 'x' END \
+             AND EVERY n IN OBJECT_NAMES(labels) SATISFIES n LIKE 'l%' END \
+             AND ANY p IN OBJECT_PAIRS(labels) SATISFIES p.name = owner AND p.val = 'y' END",
+            &Projection::new(),
+            &DefaultCollation,
+        )
+        .unwrap();
+        let mut m = FastMatcher::new(&def);
+        let mut matched = |doc: &str| m.matches(doc.as_bytes()).unwrap().matched();
+        assert!(matched(
+            r#"{"labels": {"l1": "x", "l2": "y"}, "owner": "l2"}"#
+        ));
+        // The pair's name comes from an outer field, read wherever it lies in the document.
+        assert!(matched(
+            r#"{"owner": "l2", "labels": {"l2": "y", "l1": "x"}}"#
+        ));
+        assert!(!matched(
+            r#"{"labels": {"l1": "x", "l2": "y"}, "owner": "l1"}"#
+        ));
+        assert!(!matched(
+            r#"{"labels": {"l1": "x", "m2": "y"}, "owner": "m2"}"#
+        ));
+        assert!(!matched(r#"{"labels": ["x", "y"], "owner": "l2"}"#));
+    }
+
+    #[test]
+    fn type_conversions() {
+        use jsonsm::collation::DefaultCollation;
+        use jsonsm::matcher::FastMatcher;
+        let def = compile_str(
+            "TONUMBER(qty) = 42 AND TOSTRING(code) = '7' AND TOBOOLEAN(tags)",
+            &Projection::new(),
+            &DefaultCollation,
+        )
+        .unwrap();
+        let mut m = FastMatcher::new(&def);
+        let mut matched = |doc: &str| m.matches(doc.as_bytes()).unwrap().matched();
+        assert!(matched(r#"{"qty": "42", "code": 7, "tags": [1]}"#));
+        assert!(matched(r#"{"qty": 42.0, "code": "7", "tags": "x"}"#));
+        assert!(!matched(r#"{"qty": "42.0x", "code": 7, "tags": [1]}"#));
+        assert!(!matched(r#"{"qty": "42", "code": 7.5, "tags": [1]}"#));
+        assert!(!matched(r#"{"qty": "42", "code": 7, "tags": []}"#));
+    }
+
+    #[test]
+    fn date_functions() {
+        use jsonsm::collation::DefaultCollation;
+        use jsonsm::matcher::FastMatcher;
+        let def = compile_str(
+            "DATE_PART(ts, 'hour') BETWEEN 9 AND 17 \
+             AND DATE_TRUNC(ts, 'day') = '2021-05-19T00:00:00+05:30' \
+             AND DATE_DIFF_MILLIS(STR_TO_MILLIS(ts), created, 'day') < 7",
+            &Projection::new(),
+            &DefaultCollation,
+        )
+        .unwrap();
+        let mut m = FastMatcher::new(&def);
+        let mut matched = |doc: &str| m.matches(doc.as_bytes()).unwrap().matched();
+        // 2021-05-13T00:00:00Z as epoch milliseconds.
+        assert!(matched(
+            r#"{"ts": "2021-05-19T17:45:00+05:30", "created": 1620864000000}"#
+        ));
+        assert!(!matched(
+            r#"{"ts": "2021-05-19T18:00:00+05:30", "created": 1620864000000}"#
+        ));
+        assert!(!matched(
+            r#"{"ts": "2021-05-19T12:15:00Z", "created": 1620864000000}"#
+        ));
+        assert!(!matched(
+            r#"{"ts": "2021-05-19T17:45:00+05:30", "created": 1620777600000}"#
+        ));
+        assert!(!matched(
+            r#"{"ts": 1621426500000, "created": 1620864000000}"#
+        ));
+    }
+
+    #[test]
+    fn bit_functions() {
+        use jsonsm::collation::DefaultCollation;
+        use jsonsm::matcher::FastMatcher;
+        let def = compile_str(
+            "BITTEST(flags, 3) AND NOT ISBITSET(flags, perms, TRUE) \
+             AND BITAND(flags, 12) = 4 AND BITSHIFT(1, BITNOT(-3)) = 4",
+            &Projection::new(),
+            &DefaultCollation,
+        )
+        .unwrap();
+        let mut m = FastMatcher::new(&def);
+        let mut matched = |doc: &str| m.matches(doc.as_bytes()).unwrap().matched();
+        assert!(matched(r#"{"flags": 5, "perms": [1, 2]}"#));
+        assert!(!matched(r#"{"flags": 7, "perms": [1, 2]}"#));
+        assert!(!matched(r#"{"flags": 1, "perms": [1, 2]}"#));
+        assert!(!matched(r#"{"flags": 5.0, "perms": [1, 2]}"#));
+        assert!(!matched(r#"{"flags": 5, "perms": [1, 3]}"#));
+    }
+
+    #[test]
+    fn geo_functions() {
+        use jsonsm::collation::DefaultCollation;
+        use jsonsm::matcher::FastMatcher;
+        // Within 5 km of central Paris, inside a box around Île-de-France, and with a stop
+        // inside a triangle.
+        let def = compile_str(
+            "GEO_DISTANCE(loc.lat, loc.lon, 48.8566, 2.3522) < 5000 \
+             AND WITHIN_BBOX(loc.lat, loc.lon, 48.1, 1.4, 49.3, 3.6) \
+             AND ANY s IN stops SATISFIES WITHIN_POLYGON(s.lat, s.lon, 48, 2, 49, 2, 49, 3) END",
+            &Projection::new(),
+            &DefaultCollation,
+        )
+        .unwrap();
+        let mut m = FastMatcher::new(&def);
+        let mut matched = |doc: &str| m.matches(doc.as_bytes()).unwrap().matched();
+        let stops = r#""stops": [{"lat": 48.2, "lon": 2.9}, {"lon": 2.1, "lat": 48.8}]"#;
+        // `lat` and `lon` in either order
+        assert!(matched(&format!(
+            r#"{{"loc": {{"lat": 48.86, "lon": 2.34}}, {stops}}}"#
+        )));
+        assert!(matched(&format!(
+            r#"{{{stops}, "loc": {{"lon": 2.34, "lat": 48.86}}}}"#
+        )));
+        // Versailles is 17 km out
+        assert!(!matched(&format!(
+            r#"{{"loc": {{"lat": 48.8049, "lon": 2.1204}}, {stops}}}"#
+        )));
+        // no stop inside the triangle
+        assert!(!matched(
+            r#"{"loc": {"lat": 48.86, "lon": 2.34}, "stops": [{"lat": 48.2, "lon": 2.9}]}"#
+        ));
+        // a coordinate missing or out of range
+        assert!(!matched(&format!(
+            r#"{{"loc": {{"lat": 48.86}}, {stops}}}"#
+        )));
+        assert!(!matched(&format!(
+            r#"{{"loc": {{"lat": 48.86, "lon": 182.34}}, {stops}}}"#
+        )));
+    }
+
+    #[test]
+    fn ip_functions() {
+        use jsonsm::collation::DefaultCollation;
+        use jsonsm::matcher::FastMatcher;
+        let def = compile_str(
+            "(IP_IN_CIDR(src_ip, '10.0.0.0/8') OR IP_IN_CIDR(src_ip, 'fd00::/8')) \
+             AND NOT IP_IN_RANGE(dst_ip, '192.0.2.0', '192.0.2.255') \
+             AND IP_VERSION(dst_ip) = 4",
+            &Projection::new(),
+            &DefaultCollation,
+        )
+        .unwrap();
+        let mut m = FastMatcher::new(&def);
+        let mut matched = |doc: &str| m.matches(doc.as_bytes()).unwrap().matched();
+        assert!(matched(
+            r#"{"src_ip": "10.4.5.6", "dst_ip": "198.51.100.7"}"#
+        ));
+        assert!(matched(
+            r#"{"src_ip": "fd12::1", "dst_ip": "198.51.100.7"}"#
+        ));
+        assert!(matched(
+            r#"{"src_ip": "::ffff:10.4.5.6", "dst_ip": "198.51.100.7"}"#
+        ));
+        assert!(!matched(
+            r#"{"src_ip": "11.4.5.6", "dst_ip": "198.51.100.7"}"#
+        ));
+        assert!(!matched(
+            r#"{"src_ip": "10.4.5.6", "dst_ip": "192.0.2.80"}"#
+        ));
+        // malformed addresses are missing, so nothing about them matches
+        assert!(!matched(
+            r#"{"src_ip": "10.4.5", "dst_ip": "198.51.100.7"}"#
+        ));
+        assert!(!matched(
+            r#"{"src_ip": "10.4.5.6", "dst_ip": "198.51.100.7:80"}"#
+        ));
+    }
+
+    #[test]
+    fn semver_functions() {
+        use jsonsm::collation::DefaultCollation;
+        use jsonsm::matcher::FastMatcher;
+        let def = compile_str(
+            "SEMVER_CMP(app_version, '1.9.0') >= 0 AND SEMVER_LT(app_version, '2.0.0-rc.1')",
+            &Projection::new(),
+            &DefaultCollation,
+        )
+        .unwrap();
+        let mut m = FastMatcher::new(&def);
+        let mut matched = |v: &str| {
+            let doc = format!(r#"{{"app_version": "{v}"}}"#);
+            m.matches(doc.as_bytes()).unwrap().matched()
+        };
+        assert!(matched("1.9.0"));
+        assert!(matched("1.10.0"));
+        assert!(matched("2.0.0-beta.11"));
+        assert!(!matched("1.8.99"));
+        assert!(!matched("1.9.0-rc.1"));
+        assert!(!matched("2.0.0-rc.1"));
+        assert!(!matched("2.0.0"));
+        // not a version: missing, so UNKNOWN, even under NOT
+        assert!(!matched("v1.10.0"));
+        let def = compile_str(
+            "NOT SEMVER_LT(app_version, '1.9.0')",
+            &Projection::new(),
+            &DefaultCollation,
+        )
+        .unwrap();
+        let mut m = FastMatcher::new(&def);
+        assert!(m
+            .matches(br#"{"app_version": "1.10.0"}"#)
+            .unwrap()
+            .matched());
+        assert!(!m.matches(br#"{"app_version": "1.10"}"#).unwrap().matched());
+    }
+
+    #[test]
+    fn custom_functions_resolve_at_compile_time() {
+        use jsonsm::collation::DefaultCollation;
+        use jsonsm::compile::{compile_with, CompileError};
+        use jsonsm::matcher::FastMatcher;
+        use jsonsm::registry::{CallError, Functions};
+        use jsonsm::value::{FastVal, Num};
+        let functions = Functions::new()
+            .function("luhn_digit", 1..=1, |args| match args[0].as_num() {
+                Some(Num::I(n)) => FastVal::Int(n % 10),
+                _ => FastVal::Missing,
+            })
+            .predicate("isVip", 1..=1, |args| {
+                FastVal::Bool(args[0].as_num() == Some(Num::I(7)))
+            });
+        let expr = parse_str("luhn_digit(card) = 3 AND NOT isVip(tier)").unwrap();
+        let def = compile_with(&[expr], &Projection::new(), &DefaultCollation, &functions).unwrap();
+        let mut m = FastMatcher::new(&def);
+        let mut matched = |doc: &str| m.matches(doc.as_bytes()).unwrap().matched();
+        assert!(matched(r#"{"card": 4013, "tier": 1}"#));
+        assert!(!matched(r#"{"card": 4013, "tier": 7}"#));
+        assert!(!matched(r#"{"card": 4014, "tier": 1}"#));
+        // a missing argument is not passed to the function: UNKNOWN, even under NOT
+        assert!(!matched(r#"{"card": 4013}"#));
+
+        // Without the registry, or misspelled, or with the wrong arity: a compile error.
+        let err =
+            |text: &str| compile_str(text, &Projection::new(), &DefaultCollation).unwrap_err();
+        assert!(matches!(
+            err("luhn_digit(card) = 3"),
+            BuildError::Compile(CompileError::Call(CallError::Unknown(name))) if name == "luhn_digit"
+        ));
+        assert!(matches!(
+            err("LOWERR(name) = 'x'"),
+            BuildError::Compile(CompileError::Call(CallError::Unknown(_)))
+        ));
+        assert_eq!(
+            err("LOWER(name, 'x') = 'x'").to_string(),
+            "function `strLower` takes 1 argument, but was given 2"
+        );
+        assert!(matches!(
+            err("WITHIN_POLYGON(loc.lat, loc.lon, 0, 0, 0, 10, 10, 10, 10)"),
+            BuildError::Compile(CompileError::Call(CallError::Unpaired { got: 9, .. }))
+        ));
+    }
+
     #[test]
     fn regexp_and_exists_and_bools() {
         assert_eq!(
@@ -693,6 +1511,66 @@ mod tests {
         // missing / non-date field -> DATE() is Missing -> comparison false
         assert!(!m.matches(br#"{"other": 1}"#).unwrap().matched());
         assert!(!m.matches(br#"{"ts": "not a date"}"#).unwrap().matched());
+        assert!(!m.matches(br#"{"ts": "2020-13-01"}"#).unwrap().matched());
+    }
+
+    #[test]
+    fn date_with_a_format() {
+        use jsonsm::collation::DefaultCollation;
+        use jsonsm::matcher::FastMatcher;
+        let def = compile_str(
+            "DATE(ts, '%d/%b/%Y:%H:%M:%S %z') < DATE('2006-01-02T22:30:00Z') \
+             AND DATE(sent, '%a, %d %b %Y %T %z') = DATE(at, '%Q')",
+            &Projection::new(),
+            &DefaultCollation,
+        )
+        .unwrap();
+        let mut m = FastMatcher::new(&def);
+        let mut matched = |doc: &str| m.matches(doc.as_bytes()).unwrap().matched();
+        let sent = r#""sent": "Mon, 02 Jan 2006 15:04:05 -0700", "at": "1136239445000""#;
+        assert!(matched(&format!(
+            r#"{{"ts": "02/Jan/2006:15:04:05 -0700", {sent}}}"#
+        )));
+        assert!(!matched(&format!(
+            r#"{{"ts": "02/Jan/2006:15:04:05 -0800", {sent}}}"#
+        )));
+        assert!(!matched(&format!(
+            r#"{{"ts": "2006-01-02T15:04:05-07:00", {sent}}}"#
+        )));
+    }
+
+    #[test]
+    fn now_reads_the_matchers_clock() {
+        use jsonsm::clock::FixedClock;
+        use jsonsm::collation::DefaultCollation;
+        use jsonsm::matcher::FastMatcher;
+        use std::sync::Arc;
+        let def = compile_str(
+            "DATE(created) > NOW() - 86400 AND STR_TO_MILLIS(created) <= NOW_MILLIS()",
+            &Projection::new(),
+            &DefaultCollation,
+        )
+        .unwrap();
+        let mut m = FastMatcher::new(&def);
+        let doc = br#"{"created": "2020-01-01T00:00:00Z"}"#;
+        // no clock: the current time is missing, so nothing matches
+        assert!(!m.matches(doc).unwrap().matched());
+        m.set_clock(Arc::new(FixedClock(1_577_836_800_000 + 3_600_000)));
+        assert!(m.matches(doc).unwrap().matched());
+        m.set_clock(Arc::new(FixedClock(1_577_836_800_000 + 2 * 86_400_000)));
+        assert!(!m.matches(doc).unwrap().matched());
+        m.set_clock(Arc::new(FixedClock(1_577_836_800_000 - 1)));
+        assert!(!m.matches(doc).unwrap().matched());
+
+        let def = compile_str(
+            "NOW_STR() = '2020-01-01T00:00:00Z'",
+            &Projection::new(),
+            &DefaultCollation,
+        )
+        .unwrap();
+        let mut m = FastMatcher::new(&def);
+        m.set_clock(Arc::new(FixedClock(1_577_836_800_000)));
+        assert!(m.matches(b"{}").unwrap().matched());
     }
 
     #[test]
@@ -54,6 +54,11 @@ const CASES: &[(&str, &str, bool)] = &[
     ("ANY t IN xs SATISFIES t.a = 1 END", r#"{"xs":[{"b":1},{"a":1}]}"#, true),
     ("ANY t IN xs SATISFIES t.a = 1 END", r#"{"xs":[{"b":1},{"a":2}]}"#, false),
     ("EVERY t IN xs SATISFIES t.a = 1 END", r#"{"xs":[{"a":1},{"b":2}]}"#, false),
+    // -- IS KNOWN asks which: the loop above that found only an UNKNOWN element is not known,
+    //    and nor is a loop over an absent array.
+    ("(ANY t IN xs SATISFIES t.a = 1 END) IS KNOWN", r#"{"xs":[{"b":1},{"a":2}]}"#, false),
+    ("(ANY t IN xs SATISFIES t.a = 1 END) IS KNOWN", r#"{"xs":[{"b":1},{"a":1}]}"#, true),
+    ("(EVERY t IN xs SATISFIES t.a = 1 END) IS UNKNOWN", r#"{"other":1}"#, true),
     // -- Comparison is strict: different logical types are never equal, whatever their
     //    spelling. Numbers compare exactly and across representations.
     ("n = '5'", r#"{"n":5}"#, false),
@@ -65,6 +70,235 @@ const CASES: &[(&str, &str, bool)] = &[
     // -- Strings compare by decoded value, so escaped and literal spellings are equal.
     (r#"s = 'a/b'"#, r#"{"s":"a\/b"}"#, true),
     (r#"s = 'ab'"#, r#"{"s":"ab"}"#, true),
+    // -- String functions see the decoded string and count codepoints; the boolean ones
+    //    stand as conditions, UNKNOWN on a missing argument.
+    ("LOWER(s) = 'é/b'", r#"{"s":"\u00c9\/B"}"#, true),
+    ("LENGTH(s) = 2", r#"{"s":"é!"}"#, true),
+    ("NOT CONTAINS(s, 'x')", r#"{"other":1}"#, false),
+    // -- TYPE names the JSON type (an absent field is "missing"); the IS_* predicates stand as
+    //    conditions, UNKNOWN on an absent argument and false on null.
+    ("TYPE(n) = 'null'", r#"{"n":null}"#, true),
+    ("TYPE(n) = 'missing'", r#"{"other":1}"#, false),
+    ("NOT IS_STRING(n)", r#"{"n":null}"#, true),
+    ("NOT IS_STRING(n)", r#"{"other":1}"#, false),
+    // -- IFMISSING and friends see an absent field, and answer for it with a default.
+    ("IFMISSING(n, 0) = 0", r#"{"other":1}"#, true),
+    ("IFMISSING(n, 0) = 0", r#"{"n":null}"#, false),
+    ("IFMISSINGORNULL(n, 0) = 0", r#"{"n":null}"#, true),
+    ("IFNULL(n, 0) = 0", r#"{"other":1}"#, false),
+    // -- Array functions compare elements as `=` does, skip nulls, and are missing for anything
+    //    but an array.
+    ("ARRAY_CONTAINS(xs, 'a/b')", r#"{"xs":["a\/b"]}"#, true),
+    ("ARRAY_MIN(xs) = 2", r#"{"xs":[null,"s",2]}"#, true),
+    ("NOT ARRAY_CONTAINS(xs, 1)", r#"{"xs":1}"#, false),
+    // -- Object lists are sorted by name; a loop over one quantifies over members, and is
+    //    unknown for anything but an object.
+    (
+        "ARRAY_MIN(OBJECT_NAMES(o)) = 'a'",
+        r#"{"o":{"b":1,"a":2}}"#,
+        true,
+    ),
+    ("OBJECT_LENGTH(o) = 0", r#"{"o":[]}"#, false),
+    (
+        "EVERY v IN OBJECT_VALUES(o) SATISFIES v > 1 END",
+        r#"{"o":{}}"#,
+        true,
+    ),
+    (
+        "NOT EVERY v IN OBJECT_VALUES(o) SATISFIES v > 1 END",
+        r#"{"o":[0]}"#,
+        false,
+    ),
+    (
+        "ANY p IN OBJECT_PAIRS(o) SATISFIES p.name = p.val END",
+        r#"{"o":{"a":"b","c":"c"}}"#,
+        true,
+    ),
+    // -- Conversions cross types explicitly, and fail to missing.
+    ("TONUMBER(n) = 42", r#"{"n":"4.2e1"}"#, true),
+    ("IFMISSING(TONUMBER(n), -1) = -1", r#"{"n":" 42"}"#, true),
+    ("TOSTRING(n) = '42'", r#"{"n":42.0}"#, true),
+    ("TOBOOLEAN(n)", r#"{"n":"false"}"#, true),
+    ("NOT TOBOOLEAN(n)", r#"{"n":null}"#, false),
+    // -- Integer arithmetic is exact where an f64 would round.
+    (
+        "id + 1 = 9007199254740993",
+        r#"{"id":9007199254740992}"#,
+        true,
+    ),
+    (
+        "id + 1 = 9007199254740992",
+        r#"{"id":9007199254740992}"#,
+        false,
+    ),
+    (
+        "-n = 9223372036854775808",
+        r#"{"n":-9223372036854775808}"#,
+        true,
+    ),
+    // -- DATE reads other layouts by a format, and rejects components out of range.
+    (
+        "DATE(ts, '%d/%b/%Y:%H:%M:%S %z') = DATE('2006-01-02T22:04:05Z')",
+        r#"{"ts":"02/Jan/2006:15:04:05 -0700"}"#,
+        true,
+    ),
+    ("DATE(ts, '%Q') = 1.5", r#"{"ts":"1500"}"#, true),
+    ("IFMISSING(DATE(ts), 0) = 0", r#"{"ts":"2021-02-29"}"#, true),
+    // -- Date functions read a string on its own wall clock, and epoch milliseconds in UTC.
+    (
+        "DATE_PART(ts, 'hour') = 9",
+        r#"{"ts":"2021-05-19T09:30:00-04:00"}"#,
+        true,
+    ),
+    (
+        "DATE_PART(ts, 'hour') = 13",
+        r#"{"ts":1621431000000}"#,
+        true,
+    ),
+    (
+        "DATE_ADD(ts, 1, 'month') = '2021-02-28T00:00:00Z'",
+        r#"{"ts":"2021-01-31"}"#,
+        true,
+    ),
+    (
+        "DATE_DIFF(a, b, 'day') = 1",
+        r#"{"a":"2021-01-02T00:01:00Z","b":"2021-01-01T23:59:00Z"}"#,
+        true,
+    ),
+    (
+        "IFMISSING(MILLIS(ts), -1) = -1",
+        r#"{"ts":"2021-02-30x"}"#,
+        true,
+    ),
+    // -- MILLIS_TO_STR and MILLIS_TO_UTC write in UTC, MILLIS_TO_TZ at its offset, each laid
+    //    out by an optional format in DATE's directives.
+    (
+        "MILLIS_TO_STR(ms) = '2021-05-19T13:30:00Z'",
+        r#"{"ms":1621431000000}"#,
+        true,
+    ),
+    (
+        "MILLIS_TO_STR(ms, '%d/%m/%Y') = '19/05/2021'",
+        r#"{"ms":1621431000000}"#,
+        true,
+    ),
+    (
+        "MILLIS_TO_UTC(ms, '%H:%M %Z') = '13:30 UTC'",
+        r#"{"ms":1621431000000}"#,
+        true,
+    ),
+    (
+        "MILLIS_TO_TZ(ms, '+05:30') = '2021-05-19T19:00:00+05:30'",
+        r#"{"ms":1621431000000}"#,
+        true,
+    ),
+    (
+        "MILLIS_TO_TZ(ms, '-04:00', '%F %I:%M %p') = '2021-05-19 09:30 AM'",
+        r#"{"ms":1621431000000}"#,
+        true,
+    ),
+    (
+        "MILLIS_TO_UTC(ms, '%q') IS MISSING",
+        r#"{"ms":1621431000000}"#,
+        true,
+    ),
+    // -- With no clock set on the matcher, the current time is missing.
+    ("IFMISSING(NOW_MILLIS(), -1) = -1", "{}", true),
+    ("DATE(ts) > NOW() - 86400", r#"{"ts":"2021-01-01"}"#, false),
+    // -- Geo functions read a point's two fields in either order; a coordinate out of range
+    //    is missing.
+    (
+        "GEO_DISTANCE(loc.lat, loc.lon, 0, 1) BETWEEN 111000 AND 111400",
+        r#"{"loc":{"lon":0,"lat":0}}"#,
+        true,
+    ),
+    (
+        "WITHIN_BBOX(loc.lat, loc.lon, -10, 170, 10, -170)",
+        r#"{"loc":{"lat":0,"lon":-175}}"#,
+        true,
+    ),
+    (
+        "NOT WITHIN_BBOX(loc.lat, loc.lon, -10, 170, 10, -170)",
+        r#"{"loc":{"lat":0,"lon":200}}"#,
+        false,
+    ),
+    (
+        "WITHIN_POLYGON(loc.lat, loc.lon, 0, 0, 0, 10, 10, 0)",
+        r#"{"loc":{"lat":5,"lon":5}}"#,
+        true,
+    ),
+    // -- IP functions compare addresses as numbers, read mapped IPv4 as IPv4, and find a
+    //    malformed address missing.
+    (
+        "IP_IN_CIDR(ip, '10.0.0.0/8')",
+        r#"{"ip":"::ffff:10.1.2.3"}"#,
+        true,
+    ),
+    ("IP_IN_CIDR(ip, '0.0.0.0/0')", r#"{"ip":"::1"}"#, false),
+    (
+        "IP_IN_RANGE(ip, '10.0.0.2', '10.0.0.10')",
+        r#"{"ip":"10.0.0.9"}"#,
+        true,
+    ),
+    (
+        "NOT IP_IN_CIDR(ip, '10.0.0.0/8')",
+        r#"{"ip":"10.0.0.256"}"#,
+        false,
+    ),
+    // -- Semantic versions order by SemVer precedence; a non-version is missing.
+    ("SEMVER_CMP(v, '1.9.0') = 1", r#"{"v":"1.10.0"}"#, true),
+    (
+        "SEMVER_LT(v, '1.0.0-beta.11')",
+        r#"{"v":"1.0.0-beta.2"}"#,
+        true,
+    ),
+    ("NOT SEMVER_LT(v, '1.0.0')", r#"{"v":"1.0"}"#, false),
+    // -- Bit functions take integers only; positions count from 1.
+    ("BITAND(n, 6) = 4", r#"{"n":13}"#, true),
+    ("BITTEST(n, 1)", r#"{"n":13}"#, true),
+    ("BITTEST(n, 2)", r#"{"n":13}"#, false),
+    ("NOT BITTEST(n, 2)", r#"{"n":13.0}"#, false),
+    ("BITSHIFT(n, -2) = 3", r#"{"n":13}"#, true),
+    // -- `EXISTS` and pattern matching take a function as their operand: a function
+    //    "exists" when it produced a value, including when it read more than one field.
+    ("DATE(ts) IS MISSING", r#"{"ts":"nope"}"#, true),
+    ("DATE(ts) IS NOT MISSING", r#"{"ts":"2020-01-01"}"#, true),
+    ("IFMISSING(a, b) IS NOT MISSING", r#"{"b":1}"#, true),
+    ("IFMISSING(a, b) IS VALUED", r#"{"c":1}"#, false),
+    (
+        "REGEXP_CONTAINS(LOWER(email), '@corp')",
+        r#"{"email":"Ada@CORP.example"}"#,
+        true,
+    ),
+    ("LOWER(name) LIKE 'ad%'", r#"{"name":"ADA"}"#, true),
+    ("NOT (LOWER(name) LIKE 'ad%')", r#"{"age":1}"#, false),
+    // -- A regex may come from the document; one that is absent or invalid is UNKNOWN.
+    (
+        "REGEXP_CONTAINS(value, rule)",
+        r#"{"value":"abc","rule":"^ab.*"}"#,
+        true,
+    ),
+    (
+        "NOT REGEXP_CONTAINS(value, rule)",
+        r#"{"value":"abc","rule":"("}"#,
+        false,
+    ),
+    (
+        "NOT REGEXP_CONTAINS(value, rule)",
+        r#"{"value":"abc"}"#,
+        false,
+    ),
+    // -- A loop may iterate an enclosing scope's array, wherever it lies in the document.
+    (
+        "ANY o IN orders SATISFIES ANY t IN tags SATISFIES t = o.tag END END",
+        r#"{"orders":[{"tag":"x"},{"tag":"b"}],"tags":["a","b"]}"#,
+        true,
+    ),
+    (
+        "ANY o IN orders SATISFIES ANY t IN tags SATISFIES t = o.tag END END",
+        r#"{"tags":["a","b"],"orders":[{"tag":"x"}]}"#,
+        false,
+    ),
     // -- A path running through a scalar is absent, not an error.
     ("a.x = 1", r#"{"a":5}"#, false),
     ("a.x IS MISSING", r#"{"a":5}"#, true),
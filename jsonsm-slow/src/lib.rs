@@ -17,10 +17,14 @@
 
 #![forbid(unsafe_code)]
 
+use jsonsm::clock::Clock;
 use jsonsm::collation::{Collation, CollationError, DefaultCollation, ValueMatcher};
+use jsonsm::params::{ParamValue, Params};
+use jsonsm::registry::{self, Builtins, CallError, FunctionRegistry};
 use jsonsm::value::{FastStr, FastVal};
 use jsonsm_ast::{CompareOp, Expr, Field, PathComponent, VariableId};
 use serde_json::Value;
+use std::sync::Arc;
 
 /// An error from the reference matcher.
 #[derive(Debug, thiserror::Error)]
@@ -35,6 +39,8 @@ pub enum SlowError {
     NonStringPattern,
     #[error(transparent)]
     Collation(#[from] CollationError),
+    #[error(transparent)]
+    Call(#[from] CallError),
 }
 
 /// A reference matcher over a single expression.
@@ -77,6 +83,8 @@ impl std::ops::Not for Tri {
 pub struct SlowMatcher<C = DefaultCollation> {
     expr: Expr,
     collation: C,
+    clock: Option<Arc<dyn Clock>>,
+    functions: Arc<dyn FunctionRegistry>,
 }
 
 impl SlowMatcher<DefaultCollation> {
@@ -85,6 +93,8 @@ impl SlowMatcher<DefaultCollation> {
         SlowMatcher {
             expr,
             collation: DefaultCollation,
+            clock: None,
+            functions: Arc::new(Builtins),
         }
     }
 }
@@ -92,7 +102,25 @@ impl SlowMatcher<DefaultCollation> {
 impl<C: Collation> SlowMatcher<C> {
     /// Build a reference matcher with an explicit collation.
     pub fn with_collation(expr: Expr, collation: C) -> Self {
-        SlowMatcher { expr, collation }
+        SlowMatcher {
+            expr,
+            collation,
+            clock: None,
+            functions: Arc::new(Builtins),
+        }
+    }
+
+    /// Read the current time for `now` and its kin from `clock`, once per document, as the
+    /// fast matcher does; without one they are missing.
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = Some(clock);
+    }
+
+    /// Resolve calls to functions that are not built in against `functions`, as
+    /// [`compile_with`](jsonsm::compile::compile_with) does; without it only built-ins resolve.
+    /// A call that does not resolve is a [`SlowError::Call`] when it is evaluated.
+    pub fn set_functions(&mut self, functions: Arc<dyn FunctionRegistry>) {
+        self.functions = functions;
     }
 
     /// Match against a parsed JSON document.
@@ -102,7 +130,17 @@ impl<C: Collation> SlowMatcher<C> {
     /// reads as no match, exactly like `False`. The collapse happens *only* here; everywhere
     /// below, `Unknown` stays distinct so negation cannot turn it into a match.
     pub fn matches(&self, doc: &Value) -> Result<bool, SlowError> {
-        let mut env: Env<'_> = Vec::new();
+        self.matches_with(doc, &Params::new())
+    }
+
+    /// Match against a parsed JSON document with the expression's named parameters bound from
+    /// `params`; an unbound parameter is missing.
+    pub fn matches_with(&self, doc: &Value, params: &Params) -> Result<bool, SlowError> {
+        let mut env = Env {
+            vars: Vec::new(),
+            params,
+            now: self.clock.as_ref().map(|c| c.now_millis()),
+        };
         Ok(self.eval(&self.expr, doc, &mut env)? == Tri::True)
     }
 
@@ -118,7 +156,7 @@ impl<C: Collation> SlowMatcher<C> {
     /// inline: the fast engine reaches these answers through a flat logic tree that resolves
     /// nodes as ops report and seals absent fields at container boundaries, and the differential
     /// sweep is only worth anything if the two arrive by genuinely different routes.
-    fn eval<'v>(&self, e: &Expr, doc: &'v Value, env: &mut Env<'v>) -> Result<Tri, SlowError> {
+    fn eval<'v>(&self, e: &Expr, doc: &'v Value, env: &mut Env<'v, '_>) -> Result<Tri, SlowError> {
         match e {
             Expr::True => Ok(Tri::True),
             Expr::False => Ok(Tri::False),
@@ -150,29 +188,81 @@ impl<C: Collation> SlowMatcher<C> {
                 Ok(if unknown { Tri::Unknown } else { Tri::False }) // empty OR is false
             }
             // Presence questions are always answerable — absence *is* their answer — so these
-            // two are the only way an absent field yields a definite result.
+            // three, and `Known` below, are the only way an absent field yields a definite result.
             Expr::Exists(sub) => Ok(Tri::from(!self.resolve(sub, doc, env)?.is_missing())),
             Expr::NotExists(sub) => Ok(Tri::from(self.resolve(sub, doc, env)?.is_missing())),
+            Expr::Valued(sub) => {
+                let v = self.resolve(sub, doc, env)?;
+                Ok(Tri::from(!v.is_missing() && !matches!(v, Owned::Null)))
+            }
+            Expr::Known(sub) => Ok(Tri::from(self.eval(sub, doc, env)? != Tri::Unknown)),
             Expr::Compare { op, lhs, rhs } => self.eval_compare(*op, lhs, rhs, doc, env),
             Expr::Matches { lhs, pattern } => self.eval_matches(lhs, pattern, doc, env),
+            Expr::Like {
+                lhs,
+                pattern,
+                escape,
+            } => self.eval_like(lhs, pattern, *escape, doc, env),
+            // The `Or` of one equality per member, except that a missing value is `Unknown`
+            // even against an empty list: there is no value to look for.
+            Expr::In { lhs, list } => {
+                let v = self.resolve(lhs, doc, env)?;
+                if v.is_missing() {
+                    return Ok(Tri::Unknown);
+                }
+                Ok(Tri::from(list.iter().any(|lit| {
+                    self.collation
+                        .equals(&Owned::from_literal(lit).as_fastval(), &v.as_fastval())
+                })))
+            }
+            // Literally the two comparisons it abbreviates, joined by Kleene's `AND`.
+            Expr::Between { lhs, low, high } => {
+                let ge = self.eval_compare(CompareOp::GreaterEquals, lhs, low, doc, env)?;
+                let le = self.eval_compare(CompareOp::LessEquals, lhs, high, doc, env)?;
+                Ok(match (ge, le) {
+                    (Tri::False, _) | (_, Tri::False) => Tri::False,
+                    (Tri::Unknown, _) | (_, Tri::Unknown) => Tri::Unknown,
+                    (Tri::True, Tri::True) => Tri::True,
+                })
+            }
             Expr::Loop {
                 loop_type,
                 var,
                 in_expr,
                 sub_expr,
             } => self.eval_loop(*loop_type, *var, in_expr, sub_expr, doc, env),
+            // A predicate function answers with a boolean, or with missing, which has no answer.
+            Expr::Func(func) if self.is_predicate(func)? => {
+                Ok(match self.resolve(e, doc, env)? {
+                    Owned::Missing => Tri::Unknown,
+                    v => Tri::from(matches!(v, Owned::Bool(true))),
+                })
+            }
             // Operand nodes are not booleans.
-            Expr::Value(_) | Expr::Field(_) | Expr::Func(_) => Err(SlowError::NotABoolean),
+            Expr::Value(_)
+            | Expr::Field(_)
+            | Expr::Func(_)
+            | Expr::Param(_)
+            | Expr::Case { .. } => Err(SlowError::NotABoolean),
         }
     }
 
+    /// Whether a call answers as a condition: a built-in or custom predicate.
+    fn is_predicate(&self, func: &jsonsm_ast::Func) -> Result<bool, SlowError> {
+        let custom = registry::resolve(&func.name, func.args.len(), &*self.functions)?;
+        Ok(custom.map_or_else(
+            || jsonsm::func::is_predicate(&func.name),
+            |c| c.is_predicate(),
+        ))
+    }
+
     fn eval_compare<'v>(
         &self,
         op: CompareOp,
         lhs: &Expr,
         rhs: &Expr,
         doc: &'v Value,
-        env: &mut Env<'v>,
+        env: &mut Env<'v, '_>,
     ) -> Result<Tri, SlowError> {
         // `!=` is the negation of `==`, and under three-valued logic that lowering is exactly
         // right: a missing operand makes both `Unknown`, so neither direction manufactures a
@@ -181,6 +271,36 @@ impl<C: Collation> SlowMatcher<C> {
             return Ok(!self.eval_compare(CompareOp::Equals, lhs, rhs, doc, env)?);
         }
 
+        // A set-valued parameter is not a value: `=` against it asks for membership, and there
+        // is nothing to order it against. Checked member by member rather than by search, so the
+        // engine's sorted lookup is tested against a route that cannot share its mistakes.
+        let set_side = |e: &Expr| match e {
+            Expr::Param(name) => match env.params.get(name) {
+                Some(ParamValue::Set(set)) => Some(set),
+                _ => None,
+            },
+            _ => None,
+        };
+        let (set, other) = match (set_side(lhs), set_side(rhs)) {
+            (None, None) => (None, lhs),
+            (Some(set), None) => (Some(set), rhs),
+            (None, Some(set)) => (Some(set), lhs),
+            (Some(_), Some(_)) => return Ok(Tri::Unknown),
+        };
+        if let Some(set) = set {
+            if op != CompareOp::Equals {
+                return Ok(Tri::Unknown);
+            }
+            let v = self.resolve(other, doc, env)?;
+            if v.is_missing() {
+                return Ok(Tri::Unknown);
+            }
+            return Ok(Tri::from(
+                set.iter()
+                    .any(|m| self.collation.equals(m, &v.as_fastval())),
+            ));
+        }
+
         let l = self.resolve(lhs, doc, env)?;
         let r = self.resolve(rhs, doc, env)?;
         // Not an ordering question with an awkward answer — there is no value to order.
@@ -208,21 +328,70 @@ impl<C: Collation> SlowMatcher<C> {
         lhs: &Expr,
         pattern: &Expr,
         doc: &'v Value,
-        env: &mut Env<'v>,
+        env: &mut Env<'v, '_>,
     ) -> Result<Tri, SlowError> {
         let l = self.resolve(lhs, doc, env)?;
         if l.is_missing() {
             return Ok(Tri::Unknown);
         }
+        // A constant pattern is the expression's to get right; one read from the document has
+        // no answer when it is not a string, or not a regex.
+        let constant = matches!(pattern, Expr::Value(_));
         let p = self.resolve(pattern, doc, env)?;
         let pattern_str = match &p {
             Owned::Str(s) => s.as_str(),
-            _ => return Err(SlowError::NonStringPattern),
+            _ if constant => return Err(SlowError::NonStringPattern),
+            _ => return Ok(Tri::Unknown),
+        };
+        let matcher: Box<dyn ValueMatcher> = match self.collation.compile_matcher(pattern_str) {
+            Ok(m) => m,
+            Err(CollationError::InvalidPattern(_)) if !constant => return Ok(Tri::Unknown),
+            Err(e) => return Err(e.into()),
         };
-        let matcher: Box<dyn ValueMatcher> = self.collation.compile_matcher(pattern_str)?;
         Ok(Tri::from(matcher.matches(&l.as_fastval())))
     }
 
+    /// `LIKE` by a direct backtracking walk over characters rather than the engine's
+    /// prefix/suffix/regex lowering, so the two do not share a translation.
+    fn eval_like<'v>(
+        &self,
+        lhs: &Expr,
+        pattern: &Expr,
+        escape: Option<char>,
+        doc: &'v Value,
+        env: &mut Env<'v, '_>,
+    ) -> Result<Tri, SlowError> {
+        let l = self.resolve(lhs, doc, env)?;
+        if l.is_missing() {
+            return Ok(Tri::Unknown);
+        }
+        let p = match self.resolve(pattern, doc, env)? {
+            Owned::Str(s) => s,
+            _ => return Err(SlowError::NonStringPattern),
+        };
+        // Each pattern element as `(is_wildcard, char)`, with escapes already applied.
+        let mut elems = Vec::new();
+        let mut chars = p.chars();
+        while let Some(c) = chars.next() {
+            if Some(c) == escape {
+                let Some(next) = chars.next() else {
+                    return Err(CollationError::InvalidPattern(format!(
+                        "LIKE pattern `{p}` ends with its escape character"
+                    ))
+                    .into());
+                };
+                elems.push((false, next));
+            } else {
+                elems.push((c == '%' || c == '_', c));
+            }
+        }
+        let Owned::Str(s) = &l else {
+            return Ok(Tri::False);
+        };
+        let text: Vec<char> = s.chars().collect();
+        Ok(Tri::from(like_match(&elems, &text)))
+    }
+
     fn eval_loop<'v>(
         &self,
         loop_type: jsonsm_ast::LoopType,
@@ -230,16 +399,49 @@ impl<C: Collation> SlowMatcher<C> {
         in_expr: &Expr,
         sub_expr: &Expr,
         doc: &'v Value,
-        env: &mut Env<'v>,
+        env: &mut Env<'v, '_>,
     ) -> Result<Tri, SlowError> {
         use jsonsm_ast::LoopType::*;
 
-        // The `in` operand must resolve to an array. An absent field is not an empty array:
-        // there is nothing to quantify over, so the loop is unanswerable rather than false.
-        // A value that is present but not an array is a type error for the quantifier, which
-        // has no answer either.
-        let Some(Value::Array(items)) = self.resolve_field_value(in_expr, doc, env) else {
-            return Ok(Tri::Unknown);
+        // The `in` operand must resolve to an array, or be an object list function of a field
+        // that resolves to an object. An absent field is not an empty array: there is nothing
+        // to quantify over, so the loop is unanswerable rather than false. A value that is
+        // present but of the wrong type is a type error for the quantifier, which has no
+        // answer either.
+        let (list, target) = match in_expr {
+            Expr::Func(f)
+                if f.args.len() == 1
+                    && matches!(&*f.name, "objectValues" | "objectNames" | "objectPairs") =>
+            {
+                (Some(&*f.name), &f.args[0])
+            }
+            _ => (None, in_expr),
+        };
+        // Names and pairs are built here, so they live shorter than the document; the body
+        // runs in an environment narrowed to their lifetime.
+        let built: Vec<Value>;
+        let items: Vec<&Value> = match (list, self.resolve_field_value(target, doc, env)) {
+            (None, Some(Value::Array(items))) => items.iter().collect(),
+            (Some("objectValues"), Some(Value::Object(m))) => m.values().collect(),
+            (Some(list), Some(Value::Object(m))) => {
+                built = m
+                    .iter()
+                    .map(|(k, v)| match list {
+                        "objectNames" => Value::String(k.clone()),
+                        _ => Value::Object(serde_json::Map::from_iter([
+                            ("name".to_owned(), Value::String(k.clone())),
+                            ("val".to_owned(), v.clone()),
+                        ])),
+                    })
+                    .collect();
+                built.iter().collect()
+            }
+            _ => return Ok(Tri::Unknown),
+        };
+        let mut env = Env {
+            vars: env.vars.clone(),
+            params: env.params,
+            now: env.now,
         };
 
         // A quantifier is a connective over elements, so it takes Kleene's tables too: `Any` is
@@ -248,10 +450,10 @@ impl<C: Collation> SlowMatcher<C> {
         // settles it outright.
         let mut unknown = false;
         let mut saw_true = false;
-        for item in items {
-            env.push((var, item));
-            let matched = self.eval(sub_expr, doc, env);
-            env.pop();
+        for &item in &items {
+            env.vars.push((var, item));
+            let matched = self.eval(sub_expr, doc, &mut env);
+            env.vars.pop();
             match matched? {
                 Tri::True => {
                     if loop_type == Any {
@@ -279,23 +481,49 @@ impl<C: Collation> SlowMatcher<C> {
 
     /// Resolve an operand expression to an owned value; absent fields become
     /// [`Owned::Missing`].
-    fn resolve<'v>(&self, e: &Expr, doc: &'v Value, env: &Env<'v>) -> Result<Owned, SlowError> {
+    fn resolve<'v>(&self, e: &Expr, doc: &'v Value, env: &Env<'v, '_>) -> Result<Owned, SlowError> {
         match e {
             Expr::Value(lit) => Ok(Owned::from_literal(lit)),
+            Expr::Param(name) => Ok(match env.params.get(name) {
+                Some(ParamValue::Scalar(v)) => Owned::from_fastval(v),
+                Some(ParamValue::Set(_)) | None => Owned::Missing,
+            }),
             Expr::Field(f) => Ok(self
                 .resolve_field(f, doc, env)
                 .map_or(Owned::Missing, Owned::from_value)),
             Expr::Func(func) => {
-                // Resolve args, then apply the shared function implementation so the oracle
-                // and the fast engine evaluate functions identically.
+                // Resolve the name and the args, then apply the shared function implementation
+                // (or the same custom one) so the oracle and the fast engine evaluate functions
+                // identically.
+                let custom = registry::resolve(&func.name, func.args.len(), &*self.functions)?;
                 let mut owned_args = Vec::with_capacity(func.args.len());
                 for arg in &func.args {
                     owned_args.push(self.resolve(arg, doc, env)?);
                 }
                 let fvals: Vec<FastVal<'_>> = owned_args.iter().map(Owned::as_fastval).collect();
-                Ok(Owned::from_fastval(&jsonsm::func::apply(
-                    &func.name, &fvals,
-                )))
+                Ok(Owned::from_fastval(&match custom {
+                    Some(custom) => custom.call(&fvals),
+                    None => jsonsm::func::apply(&func.name, &fvals, &self.collation, env.now),
+                }))
+            }
+            // The first branch whose condition is `True`; `Unknown` does not select one.
+            // Conditions get their own copy of the environment: `eval` takes it mutably, to bind
+            // loop variables, and `resolve` only holds it shared.
+            Expr::Case { whens, otherwise } => {
+                let mut cond_env = Env {
+                    vars: env.vars.clone(),
+                    params: env.params,
+                    now: env.now,
+                };
+                for (cond, then) in whens {
+                    if self.eval(cond, doc, &mut cond_env)? == Tri::True {
+                        return self.resolve(then, doc, env);
+                    }
+                }
+                match otherwise {
+                    Some(e) => self.resolve(e, doc, env),
+                    None => Ok(Owned::Null),
+                }
             }
             _ => Err(SlowError::NotAnOperand),
         }
@@ -307,7 +535,7 @@ impl<C: Collation> SlowMatcher<C> {
         &self,
         e: &Expr,
         doc: &'v Value,
-        env: &Env<'v>,
+        env: &Env<'v, '_>,
     ) -> Option<&'v Value> {
         match e {
             Expr::Field(f) => self.resolve_field(f, doc, env),
@@ -315,11 +543,12 @@ impl<C: Collation> SlowMatcher<C> {
         }
     }
 
-    fn resolve_field<'v>(&self, f: &Field, doc: &'v Value, env: &Env<'v>) -> Option<&'v Value> {
+    fn resolve_field<'v>(&self, f: &Field, doc: &'v Value, env: &Env<'v, '_>) -> Option<&'v Value> {
         let mut cur = if f.root == jsonsm_ast::ROOT_VAR {
             doc
         } else {
-            *env.iter()
+            *env.vars
+                .iter()
                 .rev()
                 .find(|(id, _)| *id == f.root)
                 .map(|(_, v)| v)?
@@ -334,8 +563,23 @@ impl<C: Collation> SlowMatcher<C> {
     }
 }
 
-/// The loop-variable environment: (variable id, bound document value), innermost last.
-type Env<'v> = Vec<(VariableId, &'v Value)>;
+/// The evaluation environment: the loop variables in scope — (variable id, bound document
+/// value), innermost last — the call's parameter bindings, and the clock's reading for it.
+struct Env<'v, 'p> {
+    vars: Vec<(VariableId, &'v Value)>,
+    params: &'p Params,
+    now: Option<i64>,
+}
+
+/// Whether `text` matches a parsed `LIKE` pattern of `(is_wildcard, char)` elements.
+fn like_match(pattern: &[(bool, char)], text: &[char]) -> bool {
+    match pattern.split_first() {
+        None => text.is_empty(),
+        Some((&(true, '%'), rest)) => (0..=text.len()).any(|i| like_match(rest, &text[i..])),
+        Some((&(true, _), rest)) => !text.is_empty() && like_match(rest, &text[1..]),
+        Some((&(false, c), rest)) => text.first() == Some(&c) && like_match(rest, &text[1..]),
+    }
+}
 
 /// An owned resolved operand value. Owning it sidesteps borrow gymnastics; it lends a
 /// borrowing [`FastVal`] for the duration of a comparison via [`Owned::as_fastval`].
@@ -389,6 +633,8 @@ impl Owned {
             }
             FastVal::Array(b) => Owned::Array(b.to_vec()),
             FastVal::Object(b) => Owned::Object(b.to_vec()),
+            FastVal::OwnedArray(b) => Owned::Array(b.to_vec()),
+            FastVal::OwnedObject(b) => Owned::Object(b.to_vec()),
         }
     }
 
@@ -524,6 +770,104 @@ mod tests {
         assert!(m(Expr::Exists(Box::new(field(&["other"]))), &d));
     }
 
+    #[test]
+    fn valued_and_known_are_never_unknown() {
+        let d = doc(r#"{"x": null, "y": 1}"#);
+        let valued = |f| Expr::Valued(Box::new(field(&[f])));
+        assert!(!m(valued("x"), &d));
+        assert!(m(valued("y"), &d));
+        assert!(m(Expr::Not(Box::new(valued("z"))), &d));
+        let known = |f| {
+            Expr::Known(Box::new(Expr::compare(
+                CompareOp::Equals,
+                field(&[f]),
+                Expr::Value(Literal::Int(1)),
+            )))
+        };
+        assert!(m(known("x"), &d));
+        assert!(m(Expr::Not(Box::new(known("z"))), &d));
+    }
+
+    #[test]
+    fn integer_arithmetic_is_exact() {
+        let d = doc(r#"{"id": 9007199254740992, "big": 18446744073709551615}"#);
+        let add = |f: &str, k: i64| {
+            Expr::Func(jsonsm_ast::Func {
+                name: "mathAdd".to_owned(),
+                args: vec![field(&[f]), Expr::Value(Literal::Int(k))],
+            })
+        };
+        let eq = |lhs, rhs| Expr::compare(CompareOp::Equals, lhs, Expr::Value(rhs));
+        // 2^53 + 1 is exact, where in f64 it would round back to 2^53.
+        assert!(m(eq(add("id", 1), Literal::Int(9007199254740993)), &d));
+        assert!(!m(eq(add("id", 1), Literal::Int(9007199254740992)), &d));
+        // Past u64::MAX the sum falls back to f64, which rounds it to 2^64.
+        assert!(m(
+            eq(add("big", 1), Literal::Float(18446744073709551616.0)),
+            &d
+        ));
+        assert!(m(eq(add("big", -1), Literal::Uint(u64::MAX - 1)), &d));
+    }
+
+    #[test]
+    fn now_reads_the_clock() {
+        use jsonsm::clock::FixedClock;
+        let d = doc(r#"{"expires": 1000}"#);
+        let expr = Expr::compare(
+            CompareOp::GreaterThan,
+            field(&["expires"]),
+            Expr::Func(jsonsm_ast::Func {
+                name: "nowMillis".to_owned(),
+                args: vec![],
+            }),
+        );
+        let mut sm = SlowMatcher::new(expr);
+        // no clock: the current time is missing
+        assert!(!sm.matches(&d).unwrap());
+        sm.set_clock(Arc::new(FixedClock(999)));
+        assert!(sm.matches(&d).unwrap());
+        sm.set_clock(Arc::new(FixedClock(1000)));
+        assert!(!sm.matches(&d).unwrap());
+    }
+
+    #[test]
+    fn custom_functions_come_from_the_registry() {
+        use jsonsm::registry::{CallError, Functions};
+        use jsonsm::value::Num;
+        let d = doc(r#"{"n": 21}"#);
+        let call = |name: &str| {
+            Expr::Func(jsonsm_ast::Func {
+                name: name.to_owned(),
+                args: vec![field(&["n"])],
+            })
+        };
+        let is_42 = |e: Expr| Expr::compare(CompareOp::Equals, e, Expr::Value(Literal::Int(42)));
+        let mut sm = SlowMatcher::new(is_42(call("double")));
+        // not built in, and not registered: an error, not a missing value
+        assert!(matches!(
+            sm.matches(&d),
+            Err(SlowError::Call(CallError::Unknown(_)))
+        ));
+        sm.set_functions(Arc::new(Functions::new().function(
+            "double",
+            1..=1,
+            |args| match args[0].as_num() {
+                Some(Num::I(n)) => FastVal::Int(n * 2),
+                _ => FastVal::Missing,
+            },
+        )));
+        assert!(sm.matches(&d).unwrap());
+        assert!(!sm.matches(&doc(r#"{"n": 20}"#)).unwrap());
+        // a custom predicate stands as a condition
+        let mut sm = SlowMatcher::new(call("big"));
+        sm.set_functions(Arc::new(Functions::new().predicate("big", 1..=1, |args| {
+            FastVal::Bool(args[0].as_num().is_some_and(|n| n.as_f64() > 20.0))
+        })));
+        assert!(sm.matches(&d).unwrap());
+        assert!(!sm.matches(&doc(r#"{"n": 20}"#)).unwrap());
+        assert!(!sm.matches(&doc("{}")).unwrap());
+    }
+
     #[test]
     fn null_is_present_and_orderable() {
         let d = doc(r#"{"x": null}"#);
@@ -595,6 +939,79 @@ mod tests {
         assert!(!m(empty(LoopType::AnyEvery), &d));
     }
 
+    #[test]
+    fn between() {
+        let d = doc(r#"{"n": 5, "lo": 1}"#);
+        let b = |lhs: Expr, low: Expr, high: i64| Expr::Between {
+            lhs: Box::new(lhs),
+            low: Box::new(low),
+            high: Box::new(Expr::Value(Literal::Int(high))),
+        };
+        let five = || Expr::Value(Literal::Int(5));
+        assert!(m(b(field(&["n"]), five(), 5), &d));
+        assert!(m(b(field(&["n"]), field(&["lo"]), 9), &d));
+        assert!(!m(b(field(&["n"]), five(), 4), &d));
+        // A missing bound leaves the range UNKNOWN unless the other side is already false.
+        assert!(!m(
+            Expr::Not(Box::new(b(field(&["n"]), field(&["x"]), 9))),
+            &d
+        ));
+        assert!(m(
+            Expr::Not(Box::new(b(field(&["n"]), field(&["x"]), 4))),
+            &d
+        ));
+        assert!(!m(Expr::Not(Box::new(b(field(&["x"]), five(), 9))), &d));
+    }
+
+    #[test]
+    fn in_lists() {
+        let d = doc(r#"{"n": 5, "s": "b"}"#);
+        let in_list = |f: &str, list: Vec<Literal>| Expr::In {
+            lhs: Box::new(field(&[f])),
+            list,
+        };
+        assert!(m(
+            in_list("n", vec![Literal::Int(1), Literal::Float(5.0)]),
+            &d
+        ));
+        assert!(!m(in_list("n", vec![Literal::String("5".into())]), &d));
+        assert!(m(
+            Expr::Not(Box::new(in_list("s", vec![Literal::String("a".into())]))),
+            &d
+        ));
+        // Absent: UNKNOWN, under NOT and against an empty list alike.
+        assert!(!m(Expr::Not(Box::new(in_list("x", vec![]))), &d));
+        assert!(m(Expr::Not(Box::new(in_list("n", vec![]))), &d));
+    }
+
+    #[test]
+    fn parameters() {
+        use jsonsm::collation::DefaultCollation;
+        let d = doc(r#"{"id": 7, "age": 30}"#);
+        let params = Params::new().scalar("max", Literal::Int(50)).set(
+            "ids",
+            [Literal::Int(3), Literal::Int(7)],
+            &DefaultCollation,
+        );
+        let with = |e: Expr| SlowMatcher::new(e).matches_with(&d, &params).unwrap();
+        let cmp =
+            |op, f: &str, name: &str| Expr::compare(op, field(&[f]), Expr::Param(name.into()));
+        assert!(with(cmp(CompareOp::LessThan, "age", "max")));
+        assert!(with(cmp(CompareOp::Equals, "id", "ids")));
+        assert!(!with(cmp(CompareOp::NotEquals, "id", "ids")));
+        // Ordering against a set, and an unbound parameter, are UNKNOWN.
+        assert!(!with(Expr::Not(Box::new(cmp(
+            CompareOp::LessThan,
+            "id",
+            "ids"
+        )))));
+        assert!(!with(Expr::Not(Box::new(cmp(
+            CompareOp::Equals,
+            "id",
+            "nope"
+        )))));
+    }
+
     #[test]
     fn matches_uses_default_regex() {
         let d = doc(r#"{"email": "a@example.com", "n": 5}"#);
@@ -621,4 +1038,31 @@ mod tests {
             &d
         ));
     }
+
+    #[test]
+    fn a_pattern_from_the_document_that_is_no_regex_is_unknown() {
+        let rule = |d: &str| {
+            let e = Expr::Matches {
+                lhs: Box::new(field(&["value"])),
+                pattern: Box::new(field(&["rule"])),
+            };
+            let d = doc(d);
+            (m(e.clone(), &d), m(Expr::Not(Box::new(e)), &d))
+        };
+        assert_eq!(rule(r#"{"value": "abc", "rule": "^ab"}"#), (true, false));
+        assert_eq!(rule(r#"{"value": "abc", "rule": "^b"}"#), (false, true));
+        for bad in [r#""(""#, "1", "null"] {
+            let d = format!(r#"{{"value": "abc", "rule": {bad}}}"#);
+            assert_eq!(rule(&d), (false, false), "{d}");
+        }
+        // A constant one is the expression's mistake, and still an error.
+        let e = Expr::Matches {
+            lhs: Box::new(field(&["value"])),
+            pattern: Box::new(Expr::Value(Literal::Int(1))),
+        };
+        assert!(matches!(
+            SlowMatcher::new(e).matches(&doc(r#"{"value": "abc"}"#)),
+            Err(SlowError::NonStringPattern)
+        ));
+    }
 }
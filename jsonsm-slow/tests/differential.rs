@@ -3,19 +3,24 @@
 //!
 //! A deterministic generator produces random documents and random expressions drawn from
 //! the subset the compiler currently supports (field-vs-constant comparisons, boolean
-//! logic, exists, loops, regex matches). Each pair is compiled and run through the fast matcher
-//! and run the oracle, and assert identical results. Any divergence is a bug in one side.
+//! logic, exists, loops, regex and `LIKE` matches, `IN` lists, `BETWEEN`, `CASE` operands,
+//! `IS VALUED` and `IS KNOWN`, string functions).
+//! Each pair is compiled and run through the fast matcher and the oracle, and the results
+//! must be identical. Any divergence is a bug in one side.
 //!
 //! A second sweep does the same for **field projection**: the values the fast matcher
 //! captures during the scan are compared against navigating the same document with
 //! `serde_json` — an independent oracle for "what value lives at this path". It also
 //! re-checks that adding a projection does not change the match result.
 
+use jsonsm::clock::FixedClock;
 use jsonsm::collation::DefaultCollation;
-use jsonsm::compile::{compile, Projection};
+use jsonsm::compile::{compile, compile_with, MatchDef, Projection};
 use jsonsm::matcher::FastMatcher;
+use jsonsm::registry::Functions;
 use jsonsm_ast::{CompareOp, Expr, Field, Literal, LoopType, PathComponent};
 use jsonsm_slow::SlowMatcher;
+use std::sync::Arc;
 
 /// A matcher per scan backend this CPU supports.
 ///
@@ -59,6 +64,73 @@ fn build_matchers<'d>(def: &'d jsonsm::compile::MatchDef) -> Vec<(String, FastMa
 }
 use serde_json::{json, Value};
 
+/// What a sweep's two sides share beyond the expression: the time their clocks read, and a
+/// registry of custom functions.
+#[derive(Default)]
+struct Env {
+    now: Option<i64>,
+    functions: Option<Arc<Functions>>,
+}
+
+/// Compile `expr`, which a sweep of `what` must be able to, with `env`'s registry if any.
+fn compile_sweep(what: &str, expr: &Expr, env: &Env) -> MatchDef {
+    let exprs = std::slice::from_ref(expr);
+    let def = match &env.functions {
+        Some(functions) => compile_with(exprs, &Projection::new(), &DefaultCollation, &**functions),
+        None => compile(exprs, &Projection::new(), &DefaultCollation),
+    };
+    def.unwrap_or_else(|e| panic!("{what} must compile: {e}\n  expr: {expr:?}"))
+}
+
+/// The oracle for `expr`, with `env`'s clock and registry.
+fn oracle(expr: &Expr, env: &Env) -> SlowMatcher {
+    let mut slow = SlowMatcher::new(expr.clone());
+    if let Some(ms) = env.now {
+        slow.set_clock(Arc::new(FixedClock(ms)));
+    }
+    if let Some(functions) = &env.functions {
+        slow.set_functions(functions.clone());
+    }
+    slow
+}
+
+/// Match `bytes` on every backend and `doc` on the oracle, with `env`'s clock, and assert they
+/// all agree. Returns how many backends matched.
+fn agree_on(
+    fms: &mut [(String, FastMatcher<'_>)],
+    slow: &SlowMatcher,
+    env: &Env,
+    expr: &Expr,
+    doc: &Value,
+    bytes: &[u8],
+) -> usize {
+    let want = slow.matches(doc).expect("slow match");
+    let mut matched = 0;
+    for (backend, fm) in fms {
+        if let Some(ms) = env.now {
+            fm.set_clock(Arc::new(FixedClock(ms)));
+        }
+        let fast = fm.matches(bytes).expect("fast match").matched();
+        assert_eq!(
+            fast,
+            want,
+            "mismatch ({backend})\n  expr: {expr:?}\n  doc:  {}\n  now:  {:?}\n  fast={fast} slow={want}",
+            String::from_utf8_lossy(bytes),
+            env.now,
+        );
+        matched += usize::from(fast);
+    }
+    matched
+}
+
+/// [`agree_on`] for an expression checked on one document: compile it for a sweep of `what`,
+/// and build its matchers and oracle, all under `env`.
+fn assert_agrees(what: &str, expr: &Expr, doc: &Value, bytes: &[u8], env: &Env) -> usize {
+    let def = compile_sweep(what, expr, env);
+    let slow = oracle(expr, env);
+    agree_on(&mut matchers(&def), &slow, env, expr, doc, bytes)
+}
+
 /// A tiny deterministic PRNG (SplitMix64-ish) so failures reproduce.
 struct Rng(u64);
 impl Rng {
@@ -97,6 +169,21 @@ const FIELDS: &[&str] = &["a", "b", "c", "abcdefgh"];
 // changed the match rate barely at all, and caught no mutant the plain generator missed.
 const STRINGS: &[&str] = &["p", "q", "r", "a\\b"];
 const PATTERNS: &[&str] = &["p", "^q", "[pr]", "q$"];
+/// `LIKE` patterns and their escape characters, covering each literal shape the engine
+/// special-cases as well as ones that need its regex.
+const LIKE_PATTERNS: &[(&str, Option<char>)] = &[
+    ("p", None),
+    ("p%", None),
+    ("%r", None),
+    ("%b%", None),
+    ("_", None),
+    ("%", None),
+    ("a%a", None),
+    ("%\u{e9}_", None),
+    (r"a\\b", Some('\\')),
+    (r"a\b", Some('\\')),
+    ("%!%", Some('!')),
+];
 
 /// Strings long enough to reach the tokenizer's bulk scan path.
 ///
@@ -199,7 +286,19 @@ fn gen_doc(rng: &mut Rng) -> Value {
                         .collect(),
                 )
             }
-            2 => json!({"x": gen_scalar(rng)}),
+            2 => {
+                // An object: `x` for paths like `c.x`, and a few more members, one of them
+                // sometimes an object itself, for loops over members to quantify over.
+                let mut obj = serde_json::Map::new();
+                obj.insert("x".into(), gen_scalar(rng));
+                if rng.chance(3) {
+                    obj.insert("y".into(), json!({"x": gen_scalar(rng)}));
+                } else if rng.chance(2) {
+                    obj.insert("y".into(), gen_scalar(rng));
+                }
+                add_decoys(rng, &mut obj);
+                Value::Object(obj)
+            }
             3 => {
                 // Array of objects each holding an inner array (`z`) plus a scalar `x`:
                 // material for nested loops whose inner body reads an outer scope.
@@ -272,7 +371,11 @@ fn gen_indexed(rng: &mut Rng) -> Expr {
 }
 
 fn gen_const(rng: &mut Rng) -> Expr {
-    Expr::Value(match gen_scalar(rng) {
+    Expr::Value(gen_literal(rng))
+}
+
+fn gen_literal(rng: &mut Rng) -> Literal {
+    match gen_scalar(rng) {
         Value::Null => Literal::Null,
         Value::Bool(b) => Literal::Bool(b),
         Value::String(s) => Literal::String(s),
@@ -284,7 +387,7 @@ fn gen_const(rng: &mut Rng) -> Expr {
             }
         }
         _ => Literal::Null,
-    })
+    }
 }
 
 const OPS: &[CompareOp] = &[
@@ -297,7 +400,37 @@ const OPS: &[CompareOp] = &[
 ];
 
 fn gen_leaf(rng: &mut Rng) -> Expr {
-    match rng.below(8) {
+    match rng.below(10) {
+        9 => {
+            // BETWEEN two constants, or with one bound read from another field.
+            let lhs = if rng.chance(3) {
+                gen_indexed(rng)
+            } else {
+                field(&[FIELDS[rng.below(FIELDS.len())]])
+            };
+            let low = if rng.chance(4) {
+                field(&[FIELDS[rng.below(FIELDS.len())]])
+            } else {
+                gen_const(rng)
+            };
+            Expr::Between {
+                lhs: Box::new(lhs),
+                low: Box::new(low),
+                high: Box::new(gen_const(rng)),
+            }
+        }
+        8 => {
+            // IN over a list of constants, sometimes long enough to be hashed.
+            let len = if rng.chance(4) { 20 } else { rng.below(5) };
+            Expr::In {
+                lhs: Box::new(if rng.chance(3) {
+                    gen_indexed(rng)
+                } else {
+                    field(&[FIELDS[rng.below(FIELDS.len())]])
+                }),
+                list: (0..len).map(|_| gen_literal(rng)).collect(),
+            }
+        }
         7 => {
             // cross-field comparison (compiles only at the root context; skipped elsewhere),
             // sometimes between two array elements.
@@ -317,9 +450,61 @@ fn gen_leaf(rng: &mut Rng) -> Expr {
         } else {
             field(&[FIELDS[rng.below(FIELDS.len())]])
         })),
+        1 if rng.chance(2) => Expr::Valued(Box::new(if rng.chance(3) {
+            gen_indexed(rng)
+        } else {
+            field(&[FIELDS[rng.below(FIELDS.len())]])
+        })),
         1 => Expr::NotExists(Box::new(field(&[FIELDS[rng.below(FIELDS.len())]]))),
+        // whether another leaf, or a pair of them, has an answer
+        2 if rng.chance(2) => Expr::Known(Box::new(if rng.chance(3) {
+            Expr::Or(vec![gen_leaf(rng), gen_leaf(rng)])
+        } else {
+            gen_leaf(rng)
+        })),
         2 => Expr::True,
+        // a type, array or conversion predicate standing bare as a condition
+        3 if rng.chance(2) => {
+            const PREDICATES: &[&str] = &[
+                "isString",
+                "isNumber",
+                "isBoolean",
+                "isArray",
+                "isObject",
+                "arrayContains",
+                "toBoolean",
+                "bitTest",
+            ];
+            let name = PREDICATES[rng.below(PREDICATES.len())];
+            let mut args = vec![if rng.chance(3) {
+                gen_indexed(rng)
+            } else {
+                field(&[FIELDS[rng.below(FIELDS.len())]])
+            }];
+            if name == "arrayContains" {
+                args.push(gen_const(rng));
+            }
+            if name == "bitTest" {
+                args.push(Expr::Value(Literal::Int(rng.below(3) as i64)));
+            }
+            Expr::Func(jsonsm_ast::Func {
+                name: name.to_owned(),
+                args,
+            })
+        }
         3 => Expr::False,
+        4 if rng.chance(2) => {
+            let (pattern, escape) = LIKE_PATTERNS[rng.below(LIKE_PATTERNS.len())];
+            Expr::Like {
+                lhs: Box::new(if rng.chance(3) {
+                    gen_indexed(rng)
+                } else {
+                    field(&[FIELDS[rng.below(FIELDS.len())]])
+                }),
+                pattern: Box::new(Expr::Value(Literal::String(pattern.to_owned()))),
+                escape,
+            }
+        }
         4 => Expr::Matches {
             lhs: Box::new(field(&[FIELDS[rng.below(FIELDS.len())]])),
             pattern: Box::new(Expr::Value(Literal::String(
@@ -342,6 +527,10 @@ fn gen_leaf(rng: &mut Rng) -> Expr {
                         args: vec![base],
                     })
                 }
+                2 if rng.chance(3) => gen_case(rng, base),
+                2 if rng.chance(2) => gen_array_func(rng, base),
+                3 if rng.chance(2) => gen_str_func(rng, base),
+                3 => gen_default(rng, base),
                 1 => {
                     // two-field function argument (deferred via slots + after)
                     let g = field(&[FIELDS[rng.below(FIELDS.len())]]);
@@ -369,6 +558,119 @@ fn gen_leaf(rng: &mut Rng) -> Expr {
     }
 }
 
+/// A string, type or conversion function over `base`, its other arguments constants: decoding,
+/// case mapping, codepoint counting, type naming and number text all have to agree between the
+/// engines, escaped strings included.
+fn gen_str_func(rng: &mut Rng, base: Expr) -> Expr {
+    let s = |rng: &mut Rng| Expr::Value(Literal::String(STRINGS[rng.below(STRINGS.len())].into()));
+    let int = |rng: &mut Rng| Expr::Value(Literal::Int(rng.below(5) as i64 - 2));
+    let func = |name: &str, args| {
+        Expr::Func(jsonsm_ast::Func {
+            name: name.to_owned(),
+            args,
+        })
+    };
+    if rng.chance(5) {
+        // The small string pool holds no numbers, so a number is written out to be read back.
+        return match rng.below(3) {
+            0 => func("toString", vec![base]),
+            1 => func("toBoolean", vec![base]),
+            _ => func("toNumber", vec![func("toString", vec![base])]),
+        };
+    }
+    let (name, args) = match rng.below(9) {
+        0 => ("strLower", vec![base]),
+        1 => ("strUpper", vec![base]),
+        2 => ("strLength", vec![base]),
+        3 => ("strTrim", vec![base]),
+        4 => ("strContains", vec![base, s(rng)]),
+        5 => ("strStartsWith", vec![base, s(rng)]),
+        6 => ("strEndsWith", vec![base, s(rng)]),
+        7 => ("type", vec![base]),
+        _ if rng.chance(2) => ("strSubstr", vec![base, int(rng)]),
+        _ => ("strSubstr", vec![base, int(rng), int(rng)]),
+    };
+    Expr::Func(jsonsm_ast::Func {
+        name: name.to_owned(),
+        args,
+    })
+}
+
+/// An array function over `base`, which the document may or may not hold an array in: the
+/// aggregates have to skip and order elements as the collation does. Sometimes the array is
+/// one of `base`'s object lists instead, or the function `objectLength`.
+fn gen_array_func(rng: &mut Rng, base: Expr) -> Expr {
+    let object = |name: &str, base| {
+        Expr::Func(jsonsm_ast::Func {
+            name: name.to_owned(),
+            args: vec![base],
+        })
+    };
+    let base = match rng.below(8) {
+        0 => return object("objectLength", base),
+        1 => object(OBJECT_LISTS[rng.below(OBJECT_LISTS.len())], base),
+        _ => base,
+    };
+    const FUNCS: &[&str] = &[
+        "arrayLength",
+        "arraySum",
+        "arrayAvg",
+        "arrayMin",
+        "arrayMax",
+        "arrayContains",
+    ];
+    let name = FUNCS[rng.below(FUNCS.len())];
+    let mut args = vec![base];
+    if name == "arrayContains" {
+        args.push(gen_const(rng));
+    }
+    Expr::Func(jsonsm_ast::Func {
+        name: name.to_owned(),
+        args,
+    })
+}
+
+/// The object functions that list an object's members, and may be looped over.
+const OBJECT_LISTS: &[&str] = &["objectValues", "objectNames", "objectPairs"];
+
+/// `base` with a default for when it is missing, null or either, taken from a constant or
+/// another field, sometimes through a third argument.
+fn gen_default(rng: &mut Rng, base: Expr) -> Expr {
+    const FUNCS: &[&str] = &["ifMissing", "ifNull", "ifMissingOrNull"];
+    let fallback = |rng: &mut Rng| {
+        if rng.chance(3) {
+            field(&[FIELDS[rng.below(FIELDS.len())]])
+        } else {
+            gen_const(rng)
+        }
+    };
+    let mut args = vec![base, fallback(rng)];
+    if rng.chance(3) {
+        args.push(fallback(rng));
+    }
+    Expr::Func(jsonsm_ast::Func {
+        name: FUNCS[rng.below(FUNCS.len())].to_owned(),
+        args,
+    })
+}
+
+/// A `CASE` choosing between `base`, another field and constants by ordinary leaf predicates,
+/// which may themselves read any field (or hold a `CASE`), sometimes with no `ELSE`.
+fn gen_case(rng: &mut Rng, base: Expr) -> Expr {
+    let operand = |rng: &mut Rng| match rng.below(3) {
+        0 => base.clone(),
+        1 => field(&[FIELDS[rng.below(FIELDS.len())]]),
+        _ => gen_const(rng),
+    };
+    let mut whens = Vec::new();
+    for _ in 0..1 + rng.below(2) {
+        let cond = gen_leaf(rng);
+        whens.push((cond, operand(rng)));
+    }
+    let otherwise = rng.chance(2).then(|| Box::new(operand(rng)));
+    Expr::Case { whens, otherwise }
+}
+
 fn elem_field(path: &[&str]) -> Expr {
     Expr::Field(Field {
         root: 1,
@@ -442,6 +744,34 @@ fn gen_loop(rng: &mut Rng) -> Expr {
             elem_field(&["x"]),
             gen_const(rng),
         ),
+        // a CASE over the element's fields, chosen per element
+        2 if rng.chance(3) => Expr::compare(
+            OPS[rng.below(OPS.len())],
+            Expr::Case {
+                whens: vec![(
+                    Expr::compare(
+                        OPS[rng.below(OPS.len())],
+                        elem_field(&["x"]),
+                        gen_const(rng),
+                    ),
+                    elem_field(&["y"]),
+                )],
+                otherwise: rng.chance(2).then(|| Box::new(elem_field(&["x"]))),
+            },
+            gen_const(rng),
+        ),
+        // whether element.x <op> const has an answer, or element.x a value
+        2 if rng.chance(3) => {
+            if rng.chance(2) {
+                Expr::Valued(Box::new(elem_field(&["x"])))
+            } else {
+                Expr::Known(Box::new(Expr::compare(
+                    OPS[rng.below(OPS.len())],
+                    elem_field(&["x"]),
+                    gen_const(rng),
+                )))
+            }
+        }
         // cross-field within the loop body: element.x <op> element.y
         2 => Expr::compare(
             OPS[rng.below(OPS.len())],
@@ -498,6 +828,39 @@ fn gen_loop(rng: &mut Rng) -> Expr {
     }
 }
 
+/// A loop over an object's members through an object list function. The body compares the
+/// member — a value, a name, or a pair's `name` or `val` — against a constant, a document field
+/// (which defers the loop), or for a pair its other half.
+fn gen_member_loop(rng: &mut Rng) -> Expr {
+    let modes = [LoopType::Any, LoopType::Every, LoopType::AnyEvery];
+    let list = OBJECT_LISTS[rng.below(OBJECT_LISTS.len())];
+    let (elem, other) = match list {
+        "objectPairs" if rng.chance(2) => (elem_field(&["val"]), Some(elem_field(&["name"]))),
+        "objectPairs" => (elem_field(&["name"]), Some(elem_field(&["val"]))),
+        _ if rng.chance(3) => (elem_field(&["x"]), None),
+        _ => (elem_field(&[]), None),
+    };
+    let rhs = match (rng.below(3), other) {
+        (0, _) => field(&[FIELDS[rng.below(FIELDS.len())]]),
+        (1, Some(other)) => other,
+        _ => gen_const(rng),
+    };
+    let target = if rng.chance(4) {
+        field(&["c", "y"])
+    } else {
+        field(&[FIELDS[rng.below(FIELDS.len())]])
+    };
+    Expr::Loop {
+        loop_type: modes[rng.below(modes.len())],
+        var: 1,
+        in_expr: Box::new(Expr::Func(jsonsm_ast::Func {
+            name: list.to_owned(),
+            args: vec![target],
+        })),
+        sub_expr: Box::new(Expr::compare(OPS[rng.below(OPS.len())], elem, rhs)),
+    }
+}
+
 fn gen_expr(rng: &mut Rng, depth: u32) -> Expr {
     if depth == 0 {
         return gen_leaf(rng);
@@ -512,8 +875,11 @@ fn gen_expr(rng: &mut Rng, depth: u32) -> Expr {
             let n = 1 + rng.below(3);
             Expr::Or((0..n).map(|_| gen_expr(rng, depth - 1)).collect())
         }
+        3 if rng.chance(4) => gen_member_loop(rng),
         3 => gen_loop(rng),
         4 if depth >= 2 => gen_nested_loop(rng),
+        // `IS KNOWN` over anything, loops included.
+        5 if rng.chance(3) => Expr::Known(Box::new(gen_expr(rng, depth - 1))),
         _ => gen_leaf(rng),
     }
 }
@@ -634,6 +1000,1092 @@ fn nested_cross_scope_loops_agree_with_oracle() {
     );
 }
 
+const TAGS: &[&str] = &["a", "b", "c"];
+
+/// A small array of [`TAGS`] for [`outer_array_loops_agree_with_oracle`], at times absent or
+/// not an array at all.
+fn maybe_tags(rng: &mut Rng, map: &mut serde_json::Map<String, Value>, key: &str) {
+    let tag = |rng: &mut Rng| match rng.below(6) {
+        0 => json!(1),
+        n => json!(TAGS[n % 3]),
+    };
+    let value = match rng.below(8) {
+        0 => return,
+        1 => tag(rng),
+        2 => {
+            let members = (0..rng.below(3)).map(|i| (format!("k{i}"), tag(rng)));
+            Value::Object(members.collect())
+        }
+        _ => Value::Array((0..rng.below(4)).map(|_| tag(rng)).collect()),
+    };
+    map.insert(key.to_owned(), value);
+}
+
+/// A loop over `orders` holding a loop whose array comes from an enclosing scope: the
+/// document's tags, or the order's own from inside a loop over its `lines`, at times through
+/// `objectValues`, with a body that reads the element against an enclosing scope or a constant.
+fn gen_outer_array_loop(rng: &mut Rng) -> Expr {
+    let modes = [LoopType::Any, LoopType::Every, LoopType::AnyEvery];
+    let mut mode = || modes[rng.below(modes.len())];
+    let (outer_mode, inner_mode, lines_mode) = (mode(), mode(), mode());
+    let via_values = rng.chance(4);
+    let over = |array: Expr| {
+        if via_values {
+            Expr::Func(jsonsm_ast::Func {
+                name: "objectValues".to_owned(),
+                args: vec![array],
+            })
+        } else {
+            array
+        }
+    };
+    let deep = rng.chance(2);
+    // Keys sort, so `alltags` precedes the loops that read it and `tags` follows them.
+    let name = ["alltags", "tags"][rng.below(2)];
+    let array = if deep && rng.chance(2) {
+        var_field(1, &[name])
+    } else {
+        field(&[name])
+    };
+    let reference = match rng.below(3) {
+        0 => var_field(1, &["tag"]),
+        1 if deep => var_field(3, &["tag"]),
+        _ => Expr::Value(Literal::String("a".to_owned())),
+    };
+    let body = Expr::compare(OPS[rng.below(OPS.len())], var_field(2, &[]), reference);
+    let inner = Expr::Loop {
+        loop_type: inner_mode,
+        var: 2,
+        in_expr: Box::new(over(array)),
+        sub_expr: Box::new(body),
+    };
+    let inner = if deep {
+        Expr::Loop {
+            loop_type: lines_mode,
+            var: 3,
+            in_expr: Box::new(var_field(1, &["lines"])),
+            sub_expr: Box::new(inner),
+        }
+    } else {
+        inner
+    };
+    Expr::Loop {
+        loop_type: outer_mode,
+        var: 1,
+        in_expr: Box::new(field(&["orders"])),
+        sub_expr: Box::new(inner),
+    }
+}
+
+/// Loops whose array comes from an enclosing scope: run as after-loops from the array's slot,
+/// with every loop around them deferred until that scope has been read.
+#[test]
+fn outer_array_loops_agree_with_oracle() {
+    let mut rng = Rng(0x5EED_1234_ABCD_000C);
+    let mut matched = 0usize;
+
+    for _ in 0..3_000 {
+        let expr = gen_outer_array_loop(&mut rng);
+        let mut map = serde_json::Map::new();
+        maybe_tags(&mut rng, &mut map, "alltags");
+        maybe_tags(&mut rng, &mut map, "tags");
+        if !rng.chance(8) {
+            let orders = (0..rng.below(4))
+                .map(|_| {
+                    let mut order = serde_json::Map::new();
+                    if !rng.chance(4) {
+                        order.insert("tag".to_owned(), json!(TAGS[rng.below(3)]));
+                    }
+                    maybe_tags(&mut rng, &mut order, "alltags");
+                    maybe_tags(&mut rng, &mut order, "tags");
+                    let lines = (0..rng.below(3))
+                        .map(|_| json!({ "tag": TAGS[rng.below(3)] }))
+                        .collect();
+                    order.insert("lines".to_owned(), Value::Array(lines));
+                    Value::Object(order)
+                })
+                .collect();
+            map.insert("orders".to_owned(), Value::Array(orders));
+        }
+        add_decoys(&mut rng, &mut map);
+        let doc = Value::Object(map);
+        let bytes = serde_json::to_vec(&doc).unwrap();
+
+        matched += assert_agrees("outer array loops", &expr, &doc, &bytes, &Env::default());
+    }
+
+    assert!(
+        matched > 1_000,
+        "expected a meaningful number of matches, got {matched}"
+    );
+}
+
+/// Loops over an object's members get a sweep of their own too: the matcher walks the object
+/// as a loop, both inline and deferred from its stored range, where the oracle builds the lists.
+#[test]
+fn member_loops_agree_with_oracle() {
+    let mut rng = Rng(0x5EED_1234_ABCD_0002);
+    let mut matched = 0usize;
+
+    for _ in 0..5_000 {
+        let expr = gen_member_loop(&mut rng);
+        let doc = gen_doc(&mut rng);
+        let bytes = serde_json::to_vec(&doc).unwrap();
+
+        let def = compile(
+            std::slice::from_ref(&expr),
+            &Projection::new(),
+            &DefaultCollation,
+        )
+        .unwrap_or_else(|e| panic!("member loops must compile: {e}\n  expr: {expr:?}"));
+
+        let mut backends = matchers(&def);
+        let fast = {
+            let mut agreed: Option<bool> = None;
+            for (name, fm) in &mut backends {
+                let got = fm.matches(&bytes).expect("fast match").matched();
+                match agreed {
+                    None => agreed = Some(got),
+                    Some(prev) => assert_eq!(
+                        prev,
+                        got,
+                        "backends disagree ({name}) on doc {}",
+                        String::from_utf8_lossy(&bytes)
+                    ),
+                }
+            }
+            agreed.expect("at least one backend")
+        };
+        let slow = SlowMatcher::new(expr.clone())
+            .matches(&doc)
+            .expect("slow match");
+        assert_eq!(
+            fast, slow,
+            "mismatch\n  expr: {expr:?}\n  doc:  {doc}\n  fast={fast} slow={slow}"
+        );
+        matched += usize::from(fast);
+    }
+
+    // Only some documents hold an object where the loop looks, so matches are rarer here.
+    assert!(
+        matched > 100,
+        "expected a meaningful number of matches, got {matched}"
+    );
+}
+
+/// A loop element for [`gen_element_func_loop`]: usually an object with some of the numbers
+/// `qty` and `price`, the strings `s` and `t`, and an array `z` of such objects, each field at
+/// times absent or of another type; sometimes a scalar.
+fn gen_item(rng: &mut Rng, depth: u32) -> Value {
+    if rng.chance(8) {
+        return gen_scalar(rng);
+    }
+    let mut map = serde_json::Map::new();
+    for key in ["qty", "price"] {
+        match rng.below(6) {
+            0 => {}
+            1 => {
+                map.insert(key.to_owned(), gen_scalar(rng));
+            }
+            2 => {
+                map.insert(key.to_owned(), json!(rng.below(4) as f64 / 2.0));
+            }
+            _ => {
+                map.insert(key.to_owned(), json!(rng.below(5) as i64 - 1));
+            }
+        }
+    }
+    for key in ["s", "t"] {
+        if !rng.chance(4) {
+            map.insert(key.to_owned(), json!(STRINGS[rng.below(STRINGS.len())]));
+        }
+    }
+    if depth > 0 && !rng.chance(3) {
+        let z = (0..rng.below(4))
+            .map(|_| gen_item(rng, depth - 1))
+            .collect();
+        map.insert("z".to_owned(), Value::Array(z));
+    }
+    Value::Object(map)
+}
+
+/// A loop whose body calls a function over two or more of the element's own fields —
+/// `i.qty * i.price > k` — as a comparison, an `IN`, a `BETWEEN` or a predicate, at times
+/// against a document field or inside a loop nested in it.
+fn gen_element_func_loop(rng: &mut Rng) -> Expr {
+    let modes = [LoopType::Any, LoopType::Every, LoopType::AnyEvery];
+    let call = |name: &str, args: Vec<Expr>| {
+        Expr::Func(jsonsm_ast::Func {
+            name: name.to_owned(),
+            args,
+        })
+    };
+    let int = |n: i64| Expr::Value(Literal::Int(n));
+    // `qty * price`, or `price * qty`: the fields arrive in either order relative to the call.
+    let product = |var, swapped: bool| {
+        let (a, b) = (var_field(var, &["qty"]), var_field(var, &["price"]));
+        let args = if swapped { vec![b, a] } else { vec![a, b] };
+        call("mathMultiply", args)
+    };
+    let op = OPS[rng.below(OPS.len())];
+    let k = int(rng.below(5) as i64 - 1);
+    let body = match rng.below(8) {
+        0 => Expr::compare(op, product(1, rng.chance(2)), k),
+        1 => Expr::compare(
+            op,
+            call(
+                "mathAdd",
+                vec![
+                    var_field(1, &["qty"]),
+                    call("mathMultiply", vec![var_field(1, &["price"]), int(2)]),
+                ],
+            ),
+            k,
+        ),
+        2 => Expr::In {
+            lhs: Box::new(call(
+                "mathAdd",
+                vec![var_field(1, &["qty"]), var_field(1, &["price"])],
+            )),
+            list: vec![Literal::Int(0), Literal::Int(1), Literal::Int(2)],
+        },
+        3 => Expr::Between {
+            lhs: Box::new(call(
+                "mathSubract",
+                vec![var_field(1, &["qty"]), var_field(1, &["price"])],
+            )),
+            low: Box::new(int(-1)),
+            high: Box::new(k),
+        },
+        4 => {
+            let test = call(
+                "strContains",
+                vec![var_field(1, &["s"]), var_field(1, &["t"])],
+            );
+            if rng.chance(2) {
+                Expr::Not(Box::new(test))
+            } else {
+                test
+            }
+        }
+        // Against a document field, which also defers the loop itself.
+        5 => Expr::compare(op, product(1, rng.chance(2)), field(&["total"])),
+        6 => Expr::compare(
+            op,
+            call(
+                "ifMissing",
+                vec![var_field(1, &["qty"]), var_field(1, &["price"])],
+            ),
+            k,
+        ),
+        // In a nested loop, against a field of the outer element.
+        _ => Expr::Loop {
+            loop_type: modes[rng.below(modes.len())],
+            var: 2,
+            in_expr: Box::new(var_field(1, &["z"])),
+            sub_expr: Box::new(Expr::compare(
+                op,
+                product(2, rng.chance(2)),
+                var_field(1, &["qty"]),
+            )),
+        },
+    };
+    Expr::Loop {
+        loop_type: modes[rng.below(modes.len())],
+        var: 1,
+        in_expr: Box::new(field(&["items"])),
+        sub_expr: Box::new(body),
+    }
+}
+
+/// Loop bodies that call a function over several fields of the element, deferred to the
+/// element's after-node with each field read from a slot.
+#[test]
+fn element_function_loops_agree_with_oracle() {
+    let mut rng = Rng(0x5EED_1234_ABCD_0009);
+    let mut matched = 0usize;
+
+    for _ in 0..3_000 {
+        let expr = gen_element_func_loop(&mut rng);
+        let mut map = serde_json::Map::new();
+        if !rng.chance(8) {
+            let items = (0..rng.below(5)).map(|_| gen_item(&mut rng, 1)).collect();
+            map.insert("items".to_owned(), Value::Array(items));
+        }
+        if !rng.chance(4) {
+            map.insert("total".to_owned(), json!(rng.below(4) as i64));
+        }
+        add_decoys(&mut rng, &mut map);
+        let doc = Value::Object(map);
+        let bytes = serde_json::to_vec(&doc).unwrap();
+
+        let env = Env::default();
+        matched += assert_agrees("element function loops", &expr, &doc, &bytes, &env);
+    }
+
+    assert!(
+        matched > 1_000,
+        "expected a meaningful number of matches, got {matched}"
+    );
+}
+
+/// `EXISTS`, `IS VALUED`, a regex or a `LIKE` over a function: of one document field, of
+/// two (deferred to the after-node), or inside a loop over an element's fields and, at times,
+/// a document field too.
+fn gen_value_op_func(rng: &mut Rng) -> Expr {
+    let call = |name: &str, args: Vec<Expr>| {
+        Expr::Func(jsonsm_ast::Func {
+            name: name.to_owned(),
+            args,
+        })
+    };
+    let in_loop = rng.chance(3);
+    let (s, t) = if in_loop {
+        let t = if rng.chance(3) {
+            field(&["t"])
+        } else {
+            var_field(1, &["t"])
+        };
+        (var_field(1, &["s"]), t)
+    } else {
+        (field(&["s"]), field(&["t"]))
+    };
+    let operand = match rng.below(6) {
+        0 => call("strUpper", vec![s]),
+        1 => call("toString", vec![s]),
+        2 => call("ifMissing", vec![s, t]),
+        3 => call("ifNull", vec![s, t]),
+        4 => call("ifMissingOrNull", vec![call("strLower", vec![s]), t]),
+        _ => call("strContains", vec![s, t]),
+    };
+    let test = match rng.below(5) {
+        0 => Expr::Exists(Box::new(operand)),
+        1 => Expr::NotExists(Box::new(operand)),
+        2 => Expr::Valued(Box::new(operand)),
+        3 => Expr::Matches {
+            lhs: Box::new(operand),
+            pattern: Box::new(Expr::Value(Literal::String(
+                PATTERNS[rng.below(PATTERNS.len())].to_owned(),
+            ))),
+        },
+        _ => {
+            let (pattern, escape) = LIKE_PATTERNS[rng.below(LIKE_PATTERNS.len())];
+            Expr::Like {
+                lhs: Box::new(operand),
+                pattern: Box::new(Expr::Value(Literal::String(pattern.to_owned()))),
+                escape,
+            }
+        }
+    };
+    let test = if rng.chance(3) {
+        Expr::Not(Box::new(test))
+    } else {
+        test
+    };
+    if !in_loop {
+        return test;
+    }
+    let modes = [LoopType::Any, LoopType::Every, LoopType::AnyEvery];
+    Expr::Loop {
+        loop_type: modes[rng.below(modes.len())],
+        var: 1,
+        in_expr: Box::new(field(&["items"])),
+        sub_expr: Box::new(test),
+    }
+}
+
+/// A string, `null`, another scalar, or nothing, for [`value_op_functions_agree_with_oracle`].
+fn maybe_string(rng: &mut Rng, map: &mut serde_json::Map<String, Value>, key: &str) {
+    match rng.below(6) {
+        0 => {}
+        1 => {
+            map.insert(key.to_owned(), Value::Null);
+        }
+        2 => {
+            map.insert(key.to_owned(), gen_scalar(rng));
+        }
+        _ => {
+            map.insert(key.to_owned(), json!(STRINGS[rng.below(STRINGS.len())]));
+        }
+    }
+}
+
+/// Function operands of the value operators, inline when the call reads one field of the
+/// current scope and deferred otherwise, in and out of loops.
+#[test]
+fn value_op_functions_agree_with_oracle() {
+    let mut rng = Rng(0x5EED_1234_ABCD_000A);
+    let mut matched = 0usize;
+
+    for _ in 0..3_000 {
+        let expr = gen_value_op_func(&mut rng);
+        let mut map = serde_json::Map::new();
+        maybe_string(&mut rng, &mut map, "s");
+        maybe_string(&mut rng, &mut map, "t");
+        if !rng.chance(8) {
+            let items = (0..rng.below(4))
+                .map(|_| {
+                    let mut item = serde_json::Map::new();
+                    maybe_string(&mut rng, &mut item, "s");
+                    maybe_string(&mut rng, &mut item, "t");
+                    Value::Object(item)
+                })
+                .collect();
+            map.insert("items".to_owned(), Value::Array(items));
+        }
+        add_decoys(&mut rng, &mut map);
+        let doc = Value::Object(map);
+        let bytes = serde_json::to_vec(&doc).unwrap();
+
+        matched += assert_agrees("function operands", &expr, &doc, &bytes, &Env::default());
+    }
+
+    assert!(
+        matched > 1_000,
+        "expected a meaningful number of matches, got {matched}"
+    );
+}
+
+/// A regex pattern as a document carries it: one of [`PATTERNS`], one that does not compile,
+/// some other scalar, or nothing.
+fn maybe_pattern(rng: &mut Rng, map: &mut serde_json::Map<String, Value>) {
+    match rng.below(8) {
+        0 => {}
+        1 => {
+            map.insert("rule".to_owned(), json!("("));
+        }
+        2 => {
+            map.insert("rule".to_owned(), gen_scalar(rng));
+        }
+        _ => {
+            map.insert(
+                "rule".to_owned(),
+                json!(PATTERNS[rng.below(PATTERNS.len())]),
+            );
+        }
+    }
+}
+
+/// `value MATCHES rule` with the pattern read from the document: the element's own or the
+/// document's in a loop, over a field or a call, negated or under `IS KNOWN` at times.
+fn gen_dynamic_regex(rng: &mut Rng) -> Expr {
+    let in_loop = rng.chance(3);
+    let (value, rule) = if in_loop {
+        let rule = if rng.chance(3) {
+            field(&["rule"])
+        } else {
+            var_field(1, &["rule"])
+        };
+        (var_field(1, &["s"]), rule)
+    } else {
+        (field(&["s"]), field(&["rule"]))
+    };
+    let value = if rng.chance(4) {
+        Expr::Func(jsonsm_ast::Func {
+            name: "strUpper".to_owned(),
+            args: vec![value],
+        })
+    } else {
+        value
+    };
+    let test = Expr::Matches {
+        lhs: Box::new(value),
+        pattern: Box::new(rule),
+    };
+    let test = match rng.below(4) {
+        0 => Expr::Not(Box::new(test)),
+        1 => Expr::Known(Box::new(test)),
+        _ => test,
+    };
+    if !in_loop {
+        return test;
+    }
+    let modes = [LoopType::Any, LoopType::Every, LoopType::AnyEvery];
+    Expr::Loop {
+        loop_type: modes[rng.below(modes.len())],
+        var: 1,
+        in_expr: Box::new(field(&["items"])),
+        sub_expr: Box::new(test),
+    }
+}
+
+/// Regexes read from the document, compiled and cached while matching. Each expression's
+/// matchers see several documents in turn, so later ones meet patterns already cached.
+#[test]
+fn dynamic_regexes_agree_with_oracle() {
+    let mut rng = Rng(0x5EED_1234_ABCD_000B);
+    let mut matched = 0usize;
+
+    for _ in 0..1_000 {
+        let expr = gen_dynamic_regex(&mut rng);
+        let env = Env::default();
+        let def = compile_sweep("dynamic regexes", &expr, &env);
+        let mut fms = matchers(&def);
+        let slow = oracle(&expr, &env);
+
+        for _ in 0..4 {
+            let mut map = serde_json::Map::new();
+            maybe_string(&mut rng, &mut map, "s");
+            maybe_pattern(&mut rng, &mut map);
+            if !rng.chance(8) {
+                let items = (0..rng.below(4))
+                    .map(|_| {
+                        let mut item = serde_json::Map::new();
+                        maybe_string(&mut rng, &mut item, "s");
+                        maybe_pattern(&mut rng, &mut item);
+                        Value::Object(item)
+                    })
+                    .collect();
+                map.insert("items".to_owned(), Value::Array(items));
+            }
+            add_decoys(&mut rng, &mut map);
+            let doc = Value::Object(map);
+            let bytes = serde_json::to_vec(&doc).unwrap();
+
+            matched += agree_on(&mut fms, &slow, &env, &expr, &doc, &bytes);
+        }
+    }
+
+    assert!(
+        matched > 1_000,
+        "expected a meaningful number of matches, got {matched}"
+    );
+}
+
+/// Integers at the edges of exactness: either side of 2^53, where `f64` starts skipping
+/// integers, and of the `i64` and `u64` limits, where checked arithmetic moves between the
+/// representations or gives up to `f64`.
+const EDGE_INTS: &[i128] = &[
+    0,
+    1,
+    -1,
+    3,
+    (1 << 53) - 1,
+    1 << 53,
+    (1 << 53) + 1,
+    -(1 << 53) - 1,
+    i64::MAX as i128,
+    i64::MIN as i128,
+    (i64::MIN as i128) + 1,
+    (i64::MAX as i128) + 1,
+    u64::MAX as i128,
+    (u64::MAX as i128) - 1,
+];
+
+/// An integer as a document or a constant would hold it: the narrowest integer type it fits.
+fn edge_literal(x: i128) -> Literal {
+    match (i64::try_from(x), u64::try_from(x)) {
+        (Ok(i), _) => Literal::Int(i),
+        (_, Ok(u)) => Literal::Uint(u),
+        _ => Literal::Float(x as f64),
+    }
+}
+
+fn edge_json(x: i128) -> Value {
+    match edge_literal(x) {
+        Literal::Int(i) => json!(i),
+        Literal::Uint(u) => json!(u),
+        _ => unreachable!("every edge integer fits i64 or u64"),
+    }
+}
+
+/// Integer arithmetic and the bit functions get a sweep at the magnitudes where they differ
+/// from `f64`: operands read from the document's bytes and by the oracle from its parsed value,
+/// results compared against constants one either side of the exact answer.
+#[test]
+fn integer_functions_agree_with_oracle() {
+    let mut rng = Rng(0x5EED_1234_ABCD_0003);
+    let mut matched = 0usize;
+    let edge = |rng: &mut Rng| EDGE_INTS[rng.below(EDGE_INTS.len())];
+
+    for _ in 0..5_000 {
+        let (a, b) = (edge(&mut rng), edge(&mut rng));
+        let doc = json!({ "a": edge_json(a), "b": edge_json(b) });
+        let (name, exact) = match rng.below(12) {
+            0 => ("mathAdd", a.checked_add(b)),
+            1 => ("mathSubract", a.checked_sub(b)),
+            2 => ("mathMultiply", a.checked_mul(b)),
+            3 => ("mathModulo", a.checked_rem(b)),
+            4 => ("mathAbs", Some(a.abs())),
+            5 => ("mathNegate", Some(-a)),
+            6 => ("bitAnd", Some(a & b)),
+            7 => ("bitOr", Some(a | b)),
+            8 => ("bitXor", Some(a ^ b)),
+            9 => ("bitNot", Some(!a)),
+            10 => ("bitShift", None),
+            _ => ("bitTest", None),
+        };
+        // Shifts and bit positions are small, either side of the word's edges.
+        let small = |rng: &mut Rng, range: usize| {
+            Expr::Value(Literal::Int(rng.below(2 * range + 1) as i64 - range as i64))
+        };
+        let args = match name {
+            "mathAbs" | "mathNegate" | "bitNot" => vec![field(&["a"])],
+            "bitShift" if rng.chance(3) => vec![
+                field(&["a"]),
+                small(&mut rng, 70),
+                Expr::Value(Literal::Bool(rng.chance(2))),
+            ],
+            "bitShift" => vec![field(&["a"]), small(&mut rng, 70)],
+            "bitTest" => vec![field(&["a"]), small(&mut rng, 66)],
+            _ => vec![field(&["a"]), field(&["b"])],
+        };
+        let k = match name {
+            "bitTest" => Literal::Bool(rng.chance(2)),
+            _ => edge_literal(exact.unwrap_or(0) + rng.below(3) as i128 - 1),
+        };
+        let expr = Expr::compare(
+            OPS[rng.below(OPS.len())],
+            Expr::Func(jsonsm_ast::Func {
+                name: name.to_owned(),
+                args,
+            }),
+            Expr::Value(k),
+        );
+        let bytes = serde_json::to_vec(&doc).unwrap();
+
+        matched += assert_agrees("arithmetic", &expr, &doc, &bytes, &Env::default());
+    }
+
+    assert!(
+        matched > 1_000,
+        "expected a meaningful number of matches, got {matched}"
+    );
+}
+
+/// A date, as a string at one of a few offsets or as epoch milliseconds, around a few year,
+/// month and day boundaries.
+fn gen_date(rng: &mut Rng) -> Value {
+    let (y, m, d) = [(1999, 12, 31), (2000, 2, 28), (2000, 2, 29), (2021, 1, 3)][rng.below(4)];
+    let (hh, mm) = (rng.below(24), [0, 1, 30, 59][rng.below(4)]);
+    let offset = ["Z", "+05:30", "-01:00", ""][rng.below(4)];
+    let text = format!("{y:04}-{m:02}-{d:02}T{hh:02}:{mm:02}:00{offset}");
+    if rng.chance(3) {
+        json!(jsonsm::date::parse(&text).unwrap().millis)
+    } else {
+        json!(text)
+    }
+}
+
+const DATE_UNITS: &[&str] = &[
+    "millennium",
+    "century",
+    "decade",
+    "year",
+    "quarter",
+    "month",
+    "week",
+    "day",
+    "hour",
+    "minute",
+];
+
+/// The date functions get a sweep of their own, over documents of dates: the shared
+/// arithmetic is the same in both engines, the dates' decoding and the strings and
+/// milliseconds passed between the functions are what they must agree on. Both matchers are
+/// given the same clock, set to another such date, or none.
+#[test]
+fn date_functions_agree_with_oracle() {
+    let mut rng = Rng(0x5EED_1234_ABCD_0004);
+    let mut matched = 0usize;
+    let s = |t: &str| Expr::Value(Literal::String(t.to_owned()));
+    let func = |name: &str, args| {
+        Expr::Func(jsonsm_ast::Func {
+            name: name.to_owned(),
+            args,
+        })
+    };
+
+    for _ in 0..3_000 {
+        let doc = json!({ "a": gen_date(&mut rng), "b": gen_date(&mut rng) });
+        let mut bytes = serde_json::to_vec(&doc).unwrap();
+        if rng.chance(3) {
+            // The date strings' `T` escaped, which the functions must read decoded. Nothing
+            // else in the document is a `T`.
+            let text = String::from_utf8(bytes).unwrap();
+            bytes = text.replace('T', "\\u0054").into_bytes();
+        }
+        let unit = |rng: &mut Rng| s(DATE_UNITS[rng.below(DATE_UNITS.len())]);
+        let mut date = field(&["a"]);
+        if rng.chance(3) {
+            let n = Expr::Value(Literal::Int(rng.below(5) as i64 - 2));
+            date = func("dateAdd", vec![date, n, unit(&mut rng)]);
+        }
+        if rng.chance(3) {
+            date = func("dateTrunc", vec![date, unit(&mut rng)]);
+        }
+        let now = match gen_date(&mut rng) {
+            _ if rng.chance(5) => None,
+            Value::String(t) => Some(jsonsm::date::parse(&t).unwrap().millis),
+            ms => ms.as_i64(),
+        };
+        let lhs = match rng.below(7) {
+            0 => func("dateDiff", vec![date, field(&["b"]), unit(&mut rng)]),
+            1 => func(
+                "datePart",
+                vec![
+                    date,
+                    s(["hour", "day", "month", "dow", "iso_week", "timezone_hour"][rng.below(6)]),
+                ],
+            ),
+            2 => func("date", vec![date]),
+            3 => func("date", vec![date, s("%Y-%m-%dT%H:%M:%S%z")]),
+            4 => func("strToMillis", vec![date]),
+            5 => func(
+                "dateDiff",
+                vec![date, func("nowStr", vec![]), unit(&mut rng)],
+            ),
+            _ => func(
+                "dateDiff",
+                vec![func("nowMillis", vec![]), date, unit(&mut rng)],
+            ),
+        };
+        let k = Expr::Value(Literal::Int(rng.below(7) as i64 - 3));
+        let expr = Expr::compare(OPS[rng.below(OPS.len())], lhs, k);
+
+        let env = Env {
+            now,
+            ..Env::default()
+        };
+        matched += assert_agrees("date functions", &expr, &doc, &bytes, &env);
+    }
+
+    assert!(
+        matched > 1_000,
+        "expected a meaningful number of matches, got {matched}"
+    );
+}
+
+/// A point's JSON, its `lat` and `lon` written in either order — the point of deferring a geo
+/// function over them — or with one absent or out of range.
+fn gen_point(rng: &mut Rng) -> String {
+    let coord = |rng: &mut Rng, max: f64| match rng.below(12) {
+        0 => format!("{}", max + 1.0),
+        1 => "\"1\"".to_owned(),
+        n => format!("{}", (n as f64 - 6.5) * max / 6.0),
+    };
+    let lat = format!(r#""lat": {}"#, coord(rng, 90.0));
+    let lon = format!(r#""lon": {}"#, coord(rng, 180.0));
+    match rng.below(8) {
+        0 => format!("{{{lat}}}"),
+        1..=3 => format!("{{{lon}, {lat}}}"),
+        _ => format!("{{{lat}, {lon}}}"),
+    }
+}
+
+/// Geo functions over a document point and over each point in an array: their two fields are
+/// always deferred, at the root or in a loop body, and must be read whatever their order.
+#[test]
+fn geo_functions_agree_with_oracle() {
+    let mut rng = Rng(0x5EED_1234_ABCD_0005);
+    let mut matched = 0usize;
+    let num = |x: f64| Expr::Value(Literal::Float(x));
+    let coords = |rng: &mut Rng, n: usize| -> Vec<Expr> {
+        (0..n)
+            .flat_map(|i| {
+                let spread = [30.0, 90.0, 150.0][i % 3];
+                [
+                    num(rng.below(7) as f64 * 15.0 - 45.0),
+                    num(spread - rng.below(5) as f64 * 60.0),
+                ]
+            })
+            .collect()
+    };
+
+    for _ in 0..3_000 {
+        let stops: Vec<String> = (0..rng.below(4)).map(|_| gen_point(&mut rng)).collect();
+        let text = format!(
+            r#"{{"loc": {}, "stops": [{}]}}"#,
+            gen_point(&mut rng),
+            stops.join(", ")
+        );
+        let doc: Value = serde_json::from_str(&text).unwrap();
+
+        let in_loop = rng.chance(3);
+        let point = if in_loop {
+            vec![var_field(1, &["lat"]), var_field(1, &["lon"])]
+        } else {
+            vec![field(&["loc", "lat"]), field(&["loc", "lon"])]
+        };
+        let call = |name: &str, rest: Vec<Expr>| {
+            Expr::Func(jsonsm_ast::Func {
+                name: name.to_owned(),
+                args: point.iter().cloned().chain(rest).collect(),
+            })
+        };
+        let body = match rng.below(3) {
+            0 => {
+                let km = Expr::Value(Literal::Int(rng.below(20_000) as i64 * 1000));
+                let distance = call("geoDistance", coords(&mut rng, 1));
+                Expr::compare(OPS[rng.below(OPS.len())], distance, km)
+            }
+            1 => call("geoWithinBBox", coords(&mut rng, 2)),
+            _ => {
+                let n = 3 + rng.below(3);
+                call("geoWithinPolygon", coords(&mut rng, n))
+            }
+        };
+        let expr = if in_loop {
+            Expr::Loop {
+                loop_type: [LoopType::Any, LoopType::Every][rng.below(2)],
+                var: 1,
+                in_expr: Box::new(field(&["stops"])),
+                sub_expr: Box::new(body),
+            }
+        } else {
+            body
+        };
+
+        let env = Env::default();
+        matched += assert_agrees("geo functions", &expr, &doc, text.as_bytes(), &env);
+    }
+
+    assert!(
+        matched > 1_000,
+        "expected a meaningful number of matches, got {matched}"
+    );
+}
+
+/// Addresses near the networks [`ip_functions_agree_with_oracle`] tests, in both families and
+/// as mapped IPv4.
+const ADDRS: &[&str] = &[
+    "10.0.0.1",
+    "10.255.255.255",
+    "11.0.0.0",
+    "192.168.1.7",
+    "192.168.1.8",
+    "::ffff:10.1.2.3",
+    "2001:db8::1",
+    "2001:DB8:0:0::ff",
+    "2001:db9::",
+    "::1",
+];
+
+/// Addresses that do not parse, which only a document may carry: as a constant they fail the
+/// compile.
+const BAD_ADDRS: &[&str] = &["10.0.0", "010.0.0.1", "fe80::1%eth0"];
+
+const NETS: &[&str] = &[
+    "10.0.0.0/8",
+    "10.1.2.3/8",
+    "192.168.1.7/32",
+    "0.0.0.0/0",
+    "::ffff:10.0.0.0/104",
+    "2001:db8::/32",
+    "::/0",
+];
+
+const BAD_NETS: &[&str] = &["10.0.0.0/33", "10.0.0.0"];
+
+/// The IP functions, mostly against a constant network, which the fast engine parses once at
+/// compile time and the oracle per document — so the two really do take different routes.
+#[test]
+fn ip_functions_agree_with_oracle() {
+    let mut rng = Rng(0x5EED_1234_ABCD_0006);
+    let mut matched = 0usize;
+    let s = |t: &str| Expr::Value(Literal::String(t.to_owned()));
+    let pick = |rng: &mut Rng, from: &[&'static str]| from[rng.below(from.len())];
+    // A document's value may be malformed too.
+    let pick_any = |rng: &mut Rng, good: &[&'static str], bad: &[&'static str]| {
+        let i = rng.below(good.len() + bad.len());
+        good.get(i).copied().unwrap_or_else(|| bad[i - good.len()])
+    };
+    let func = |name: &str, args| {
+        Expr::Func(jsonsm_ast::Func {
+            name: name.to_owned(),
+            args,
+        })
+    };
+
+    for _ in 0..3_000 {
+        let doc = json!({
+            "a": pick_any(&mut rng, ADDRS, BAD_ADDRS),
+            "b": pick_any(&mut rng, ADDRS, BAD_ADDRS),
+            "net": pick_any(&mut rng, NETS, BAD_NETS),
+        });
+        let mut bytes = serde_json::to_vec(&doc).unwrap();
+        if rng.chance(3) {
+            // The addresses' dots escaped, which the functions must read decoded.
+            let text = String::from_utf8(bytes).unwrap();
+            bytes = text.replace('.', "\\u002e").into_bytes();
+        }
+        let expr = match rng.below(6) {
+            0 | 1 => func("ipInCidr", vec![field(&["a"]), s(pick(&mut rng, NETS))]),
+            2 => func("ipInCidr", vec![field(&["a"]), field(&["net"])]),
+            3 => {
+                let (low, high) = (pick(&mut rng, ADDRS), pick(&mut rng, ADDRS));
+                func("ipInRange", vec![field(&["a"]), s(low), s(high)])
+            }
+            4 => Expr::compare(
+                CompareOp::Equals,
+                func("ipNormalize", vec![field(&["a"])]),
+                func("ipNormalize", vec![field(&["b"])]),
+            ),
+            _ => Expr::compare(
+                CompareOp::Equals,
+                func("ipVersion", vec![field(&["a"])]),
+                Expr::Value(Literal::Int(4)),
+            ),
+        };
+
+        matched += assert_agrees("IP functions", &expr, &doc, &bytes, &Env::default());
+    }
+
+    assert!(
+        matched > 1_000,
+        "expected a meaningful number of matches, got {matched}"
+    );
+}
+
+/// Versions whose precedence turns on each of SemVer's rules, and some that are not versions.
+const VERSIONS: &[&str] = &[
+    "1.9.0",
+    "1.10.0",
+    "1.10.0+build.5",
+    "1.0.0",
+    "1.0.0-alpha",
+    "1.0.0-alpha.1",
+    "1.0.0-alpha.beta",
+    "1.0.0-beta.2",
+    "1.0.0-beta.11",
+    "1.0.0-rc.1",
+    "v1.0.0",
+    "1.0",
+    "01.0.0",
+];
+
+/// The semantic-version functions over two fields, or a field and a constant.
+#[test]
+fn semver_functions_agree_with_oracle() {
+    let mut rng = Rng(0x5EED_1234_ABCD_0007);
+    let mut matched = 0usize;
+    let pick = |rng: &mut Rng| VERSIONS[rng.below(VERSIONS.len())];
+
+    for _ in 0..2_000 {
+        let doc = json!({ "a": pick(&mut rng), "b": pick(&mut rng) });
+        let mut bytes = serde_json::to_vec(&doc).unwrap();
+        if rng.chance(3) {
+            let text = String::from_utf8(bytes).unwrap();
+            bytes = text.replace('.', "\\u002e").into_bytes();
+        }
+        let rhs = if rng.chance(2) {
+            field(&["b"])
+        } else {
+            Expr::Value(Literal::String(pick(&mut rng).to_owned()))
+        };
+        let call = |name: &str| {
+            Expr::Func(jsonsm_ast::Func {
+                name: name.to_owned(),
+                args: vec![field(&["a"]), rhs.clone()],
+            })
+        };
+        let expr = if rng.chance(2) {
+            let k = Expr::Value(Literal::Int(rng.below(3) as i64 - 1));
+            Expr::compare(OPS[rng.below(OPS.len())], call("semverCmp"), k)
+        } else {
+            call("semverLess")
+        };
+
+        matched += assert_agrees("semver functions", &expr, &doc, &bytes, &Env::default());
+    }
+
+    assert!(
+        matched > 1_000,
+        "expected a meaningful number of matches, got {matched}"
+    );
+}
+
+/// Custom functions from a registry the two matchers share: a clamp over numbers and a
+/// prefix test over strings, as operands, as a condition, and under a built-in.
+#[test]
+fn custom_functions_agree_with_oracle() {
+    use jsonsm::value::FastVal;
+    let functions = Arc::new(
+        Functions::new()
+            .function("clamp", 3..=3, |args| {
+                let n: Vec<f64> = args
+                    .iter()
+                    .filter_map(|a| a.as_num())
+                    .map(|n| n.as_f64())
+                    .collect();
+                match n[..] {
+                    [x, lo, hi] if lo <= hi => FastVal::Float(x.clamp(lo, hi)),
+                    _ => FastVal::Missing,
+                }
+            })
+            .predicate("hasPrefix", 2..=2, |args| {
+                match (args[0].as_str(), args[1].as_str()) {
+                    (Some(s), Some(p)) => {
+                        FastVal::Bool(s.to_decoded_bytes().starts_with(&p.to_decoded_bytes()))
+                    }
+                    _ => FastVal::Missing,
+                }
+            }),
+    );
+    let env = Env {
+        now: None,
+        functions: Some(functions),
+    };
+    let mut rng = Rng(0x5EED_1234_ABCD_0008);
+    let mut matched = 0usize;
+
+    for _ in 0..3_000 {
+        let mut map = serde_json::Map::new();
+        for key in ["a", "b"] {
+            if !rng.chance(5) {
+                map.insert(key.to_owned(), gen_scalar(&mut rng));
+            }
+        }
+        let doc = Value::Object(map);
+        let bytes = serde_json::to_vec(&doc).unwrap();
+        let call = |name: &str, args: Vec<Expr>| {
+            Expr::Func(jsonsm_ast::Func {
+                name: name.to_owned(),
+                args,
+            })
+        };
+        let int = |n: i64| Expr::Value(Literal::Int(n));
+        let k = int(rng.below(3) as i64);
+        let expr = match rng.below(4) {
+            0 => Expr::compare(
+                OPS[rng.below(OPS.len())],
+                call("clamp", vec![field(&["a"]), int(0), int(1)]),
+                k,
+            ),
+            // Two fields: deferred to the after-node.
+            1 => Expr::compare(
+                OPS[rng.below(OPS.len())],
+                call("clamp", vec![field(&["a"]), field(&["b"]), int(2)]),
+                k,
+            ),
+            2 => Expr::compare(
+                OPS[rng.below(OPS.len())],
+                call(
+                    "mathAdd",
+                    vec![call("clamp", vec![field(&["b"]), int(-1), int(1)]), int(1)],
+                ),
+                k,
+            ),
+            _ => {
+                let prefix = Expr::Value(Literal::String(STRINGS[rng.below(2)].to_owned()));
+                let test = call("hasPrefix", vec![field(&["a"]), prefix]);
+                if rng.chance(2) {
+                    Expr::Not(Box::new(test))
+                } else {
+                    test
+                }
+            }
+        };
+
+        matched += assert_agrees("custom functions", &expr, &doc, &bytes, &env);
+    }
+
+    assert!(
+        matched > 1_000,
+        "expected a meaningful number of matches, got {matched}"
+    );
+}
+
 // ---- field projection -------------------------------------------------------------------
 
 /// Candidate projection paths: present/absent, nested, array elements (in and out of range),
@@ -0,0 +1,51 @@
+//! The current time, for `now`, `nowMillis` and `nowStr`.
+//!
+//! The engine never reads the system clock on its own: a filter such as
+//! `DATE(created) > NOW() - 86400` would otherwise match differently from one run to the next,
+//! and a test could not pin its answer. A matcher is handed a [`Clock`] instead
+//! ([`FastMatcher::set_clock`](crate::matcher::FastMatcher::set_clock)) and reads it **once per
+//! document**, before the scan, so every call to a `now` function in one match sees the same
+//! instant. A matcher with no clock reads the current time as missing, so a comparison against
+//! it is UNKNOWN.
+//!
+//! [`FixedClock`] pins the time, for tests and for replaying a filter as of a given moment;
+//! [`SystemClock`] reads the system's.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A source of the current time, in milliseconds since the Unix epoch.
+///
+/// `Send + Sync` so one clock can be shared, behind an `Arc`, by a matcher per thread.
+///
+/// ```
+/// use jsonsm::clock::{Clock, FixedClock};
+///
+/// assert_eq!(FixedClock(1_577_836_800_000).now_millis(), 1_577_836_800_000);
+/// ```
+pub trait Clock: Send + Sync {
+    /// The current time, in epoch milliseconds.
+    fn now_millis(&self) -> i64;
+}
+
+/// A clock stopped at a fixed instant, in epoch milliseconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FixedClock(pub i64);
+
+impl Clock for FixedClock {
+    fn now_millis(&self) -> i64 {
+        self.0
+    }
+}
+
+/// The system's wall clock. A time before the epoch reads as negative milliseconds.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_millis(&self) -> i64 {
+        match SystemTime::now().duration_since(UNIX_EPOCH) {
+            Ok(d) => i64::try_from(d.as_millis()).unwrap_or(i64::MAX),
+            Err(e) => i64::try_from(e.duration().as_millis()).map_or(i64::MIN, |ms| -ms),
+        }
+    }
+}
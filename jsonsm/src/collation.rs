@@ -16,13 +16,16 @@
 //! stops `NOT` turning an absent field into a match.
 //!
 //! Pattern matching (`matches` / `LIKE`) is also a collation concern: [`Collation::compile_matcher`]
-//! turns a pattern string into a runtime [`ValueMatcher`]. [`DefaultCollation`] supports
-//! this out of the box using the standard [`regex`] crate (unanchored "contains"
-//! matching against the *decoded* string value). The trait method still defaults to an
-//! error so a minimal custom collation may opt out.
+//! turns a regex pattern string into a runtime [`ValueMatcher`], and [`Collation::compile_like`]
+//! does the same for a SQL `LIKE` pattern. [`DefaultCollation`] supports both out of the box:
+//! regexes with the standard [`regex`] crate (unanchored "contains" matching against the
+//! *decoded* string value), and `LIKE` with [`crate::like`]. The trait methods still default to
+//! an error so a minimal custom collation may opt out.
 
-use crate::value::{FastVal, ValueType};
+use crate::value::{FastVal, Num, ValueType};
 use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::hash::Hasher;
 
 /// The outcome of comparing two values under a [`Collation`].
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -81,14 +84,45 @@ pub trait Collation {
         self.compare(a, b).ordering == Ordering::Equal
     }
 
+    /// Feed `value` into `state` so that values [`Self::equals`] calls equal hash alike, and
+    /// return `true`; or return `false` if this collation has no such hash.
+    ///
+    /// Used to index large [`ValueSet`](crate::params::ValueSet)s (`IN` lists and set-valued
+    /// parameters). The default returns `false`, and a set then falls back to binary search
+    /// under [`Self::compare`], which is correct for any collation.
+    fn hash(&self, value: &FastVal<'_>, state: &mut dyn Hasher) -> bool {
+        let _ = (value, state);
+        false
+    }
+
     /// Compile a pattern string into a runtime matcher for the `matches` operator.
     ///
     /// The default implementation reports [`CollationError::MatcherUnsupported`]; a
     /// regex-backed collation overrides it.
+    ///
+    /// Besides constant patterns at compile time, this is called while matching, with
+    /// patterns read from documents or parameters (`REGEXP_CONTAINS(value, rule)`): it must
+    /// report a pattern it rejects as an error rather than panic, and any error there makes the
+    /// match unknown. A collation reports `MatcherUnsupported` for every pattern or for none;
+    /// the compiler relies on that to reject a non-constant pattern up front.
     fn compile_matcher(&self, pattern: &str) -> Result<Box<dyn ValueMatcher>, CollationError> {
         let _ = pattern;
         Err(CollationError::MatcherUnsupported)
     }
+
+    /// Compile a SQL `LIKE` pattern, with `escape` as its escape character if any, into a
+    /// runtime matcher.
+    ///
+    /// The default implementation reports [`CollationError::MatcherUnsupported`];
+    /// [`crate::like::compile_like`] is there for a collation to return.
+    fn compile_like(
+        &self,
+        pattern: &str,
+        escape: Option<char>,
+    ) -> Result<Box<dyn ValueMatcher>, CollationError> {
+        let _ = (pattern, escape);
+        Err(CollationError::MatcherUnsupported)
+    }
 }
 
 /// Strict-N1QL collation: no cross-type coercion.
@@ -104,7 +138,8 @@ pub trait Collation {
 /// gojsonsm (see the note in [`DefaultCollation::compare`]) — so container equality is
 /// byte-exact and whitespace/key-order sensitive.
 ///
-/// Pattern matching is backed by the standard [`regex`] crate.
+/// Regex pattern matching is backed by the standard [`regex`] crate, and `LIKE` by
+/// [`crate::like`].
 #[derive(Debug, Clone, Copy, Default)]
 pub struct DefaultCollation;
 
@@ -135,6 +170,14 @@ impl Collation for DefaultCollation {
         Ok(Box::new(RegexMatcher { re }))
     }
 
+    fn compile_like(
+        &self,
+        pattern: &str,
+        escape: Option<char>,
+    ) -> Result<Box<dyn ValueMatcher>, CollationError> {
+        crate::like::compile_like(pattern, escape)
+    }
+
     #[inline(always)]
     fn compare(&self, a: &FastVal<'_>, b: &FastVal<'_>) -> Comparison {
         // Two strings, asked directly. The general route below reaches the same answer, but by
@@ -180,6 +223,106 @@ impl Collation for DefaultCollation {
         };
         Comparison::within(ordering)
     }
+
+    fn hash(&self, value: &FastVal<'_>, state: &mut dyn Hasher) -> bool {
+        let ty = value.value_type();
+        state.write_u8(ty as u8);
+        match ty {
+            ValueType::Missing | ValueType::Null => {}
+            ValueType::Boolean => {
+                state.write_u8(value.as_bool().expect("a boolean by value_type") as u8)
+            }
+            // Numbers are equal across `i64`, `u64` and `f64` exactly when their values are, so
+            // an integral value hashes as that integer whatever its representation. Every such
+            // `f64` below 2^127 in magnitude converts to `i128` exactly; the rest (fractions and
+            // the infinities) cannot equal an integer and hash by their bits.
+            ValueType::Number => match value.as_num().expect("numeric by value_type") {
+                Num::I(i) => state.write_i128(i.into()),
+                Num::U(u) => state.write_i128(u.into()),
+                Num::F(f) if f.fract() == 0.0 && f.abs() < 2f64.powi(127) => {
+                    state.write_i128(f as i128)
+                }
+                Num::F(f) => state.write_u64(f.to_bits()),
+            },
+            ValueType::String => state.write(
+                &value
+                    .as_str()
+                    .expect("a string by value_type")
+                    .to_decoded_bytes(),
+            ),
+            ValueType::Array | ValueType::Object => {
+                state.write(value.container_bytes().expect("a container by value_type"))
+            }
+        }
+        true
+    }
+}
+
+/// Regexes compiled while matching, from patterns that are not constants, kept so a pattern
+/// repeated across documents is compiled once per matcher.
+///
+/// Holds at most [`PatternCache::CAPACITY`] patterns and, when full, drops the one least
+/// recently used. A pattern that does not compile is remembered as such, so it is not retried
+/// either.
+#[derive(Debug, Default)]
+pub(crate) struct PatternCache {
+    entries: HashMap<Box<str>, CachedPattern>,
+    /// Counts lookups, to date the entries.
+    clock: u64,
+}
+
+#[derive(Debug)]
+struct CachedPattern {
+    /// `None` if the pattern did not compile.
+    matcher: Option<Box<dyn ValueMatcher>>,
+    /// The lookup that last used it.
+    used: u64,
+}
+
+impl PatternCache {
+    pub(crate) const CAPACITY: usize = 64;
+
+    /// Whether `value` matches the regex `pattern` compiled by `collation`; `None` if the
+    /// pattern is not a string, or does not compile.
+    pub(crate) fn matches<C: Collation + ?Sized>(
+        &mut self,
+        pattern: &FastVal<'_>,
+        value: &FastVal<'_>,
+        collation: &C,
+    ) -> Option<bool> {
+        let bytes = pattern.as_str()?.to_decoded_bytes();
+        let pattern = std::str::from_utf8(&bytes).ok()?;
+        self.clock += 1;
+        if let Some(entry) = self.entries.get_mut(pattern) {
+            entry.used = self.clock;
+            return entry.matcher.as_ref().map(|m| m.matches(value));
+        }
+        if self.entries.len() >= Self::CAPACITY {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.used)
+                .map(|(p, _)| p.clone())
+                .expect("a full cache has entries");
+            self.entries.remove(&oldest);
+        }
+        let matcher = collation.compile_matcher(pattern).ok();
+        let result = matcher.as_ref().map(|m| m.matches(value));
+        let used = self.clock;
+        self.entries
+            .insert(pattern.into(), CachedPattern { matcher, used });
+        result
+    }
+
+    #[cfg(test)]
+    fn contains(&self, pattern: &str) -> bool {
+        self.entries.contains_key(pattern)
+    }
+
+    #[cfg(test)]
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
 }
 
 #[cfg(test)]
@@ -292,6 +435,32 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn pattern_cache_compiles_each_pattern_once_and_evicts_the_least_recent() {
+        let c = DefaultCollation;
+        let mut cache = PatternCache::default();
+        assert_eq!(cache.matches(&s("^a"), &s("abc"), &c), Some(true));
+        assert_eq!(cache.matches(&s("^a"), &s("cba"), &c), Some(false));
+        // Decoded before compiling: `\\d` in the document is the regex `\d`.
+        let digit = FastVal::Str(FastStr::Escaped(br"\\d"));
+        assert_eq!(cache.matches(&digit, &s("a1"), &c), Some(true));
+        assert!(cache.contains(r"\d"));
+        // No answer for a pattern that does not compile, or is not a string; the first is
+        // remembered, the second never reaches the cache.
+        assert_eq!(cache.matches(&s("("), &s("("), &c), None);
+        assert!(cache.contains("("));
+        assert_eq!(cache.matches(&FastVal::Int(1), &s("1"), &c), None);
+        assert_eq!(cache.matches(&FastVal::Missing, &s("1"), &c), None);
+
+        // Filling it drops the least recently used: `^a` was used since `\d` was, so it stays.
+        assert_eq!(cache.matches(&s("^a"), &s("a"), &c), Some(true));
+        for i in 0..PatternCache::CAPACITY - 2 {
+            cache.matches(&s(&format!("x{i}")), &s(""), &c);
+        }
+        assert_eq!(cache.entries.len(), PatternCache::CAPACITY);
+        assert!(cache.contains("^a") && !cache.contains(r"\d"));
+    }
+
     #[test]
     fn comparison_is_total_and_antisymmetric() {
         let c = DefaultCollation;
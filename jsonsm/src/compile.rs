@@ -19,7 +19,11 @@
 //! value, so each referenced field is recorded in a **slot** and the whole comparison is
 //! deferred to the current scope's **after-node**: at the root it runs after the document
 //! (order-independent), and in a loop body it runs after each element. Built-in functions
-//! ([`crate::func`]) are supported as operands with any number of field/constant arguments.
+//! ([`crate::func`]) are supported as operands with any number of field/constant arguments;
+//! a call to a predicate function may also stand as a condition, compiled as `f(…) = TRUE`.
+//! Every call is resolved here, against the built-ins and then the caller's
+//! [`FunctionRegistry`] ([`compile_with`]): an unknown name or a wrong argument count is a
+//! [`CompileError::Call`], and a custom function is carried on its [`FuncRef`].
 //!
 //! A loop whose body reads a field from an **enclosing** scope is handled with an
 //! **after-loop**: the array is stored in a slot and the loop is deferred to that scope's
@@ -27,7 +31,13 @@
 //! available (order-independent). At the document scope that means "after the document"; in a
 //! loop body it means "after each element". Nesting composes to any depth: a body reaching
 //! past its immediately enclosing scope also causes *that* loop to be deferred, so by the
-//! time the innermost loop runs every scope it reads has been parsed.
+//! time the innermost loop runs every scope it reads has been parsed. A loop whose **array**
+//! comes from an enclosing scope is one more such reach: it is always an after-loop, reading
+//! the array back from the slot of the scope that holds it.
+//!
+//! A loop's `in` operand may also be `objectValues`, `objectNames` or `objectPairs` of a field
+//! ([`LoopOver`]): the loop is attached to that field's node all the same, and the matcher walks
+//! the object's members as it would an array's elements, so the list is never built.
 //!
 //! [`compile`] takes *all* the expressions to evaluate at once: they are joined by
 //! non-short-circuiting `Neor` nodes so every one is fully evaluated, and each expression's
@@ -40,14 +50,27 @@
 //! values are read back through
 //! [`MatchOutcome::projected`](crate::matcher::MatchOutcome::projected).
 //!
+//! A named parameter ([`Expr::Param`]) compiles to [`DataRef::Param`] and stays symbolic: the
+//! definition records each distinct name once, and the value is bound per match call (see
+//! [`crate::params`]). To the rest of the compiler a parameter is a constant — it references no
+//! field — so it never decides where an op is attached or whether it is deferred.
+//!
+//! A `CASE` operand ([`Expr::Case`]) compiles to [`DataRef::Case`]: its conditions become
+//! [`Cond`]s over slot-stored fields, evaluated when the operand is read rather than reported
+//! into the logic tree. The conditions may read any field of the scope, so an op over a `CASE`
+//! is always deferred to the after-node, as a multi-field comparison is. So is an op over a
+//! function that sees a missing argument as an input (`ifMissing` and the like): inline on the
+//! field's node it would never run for an absent field, which is exactly the case it handles.
+//!
 //! `exists` and `matches` accept a field from an *enclosing* scope as well as the current one:
 //! the outer field is stored in a slot and the op attached to the current scope's node, which is
-//! visited unconditionally, so by then the slot is filled. `CompileError::CrossContext` is now
-//! raised only for a **loop target** — the array a loop iterates must be a field of the current
-//! scope.
+//! visited unconditionally, so by then the slot is filled.
 
 use crate::collation::{Collation, CollationError, ValueMatcher};
+use crate::func;
 use crate::logic_tree::{LogicTree, NodeIdx, NodeType, TreeError, Tri};
+use crate::params::ValueSet;
+use crate::registry::{self, Builtins, CallError, CustomFunction, FunctionRegistry};
 use crate::value::{FastStr, FastVal};
 use jsonsm_ast::{CompareOp, Expr, Field, Literal, LoopType, PathComponent, VariableId};
 use std::sync::Arc;
@@ -59,6 +82,8 @@ pub(crate) type BucketId = NodeIdx;
 /// A storage slot index: a field's scanned byte range is recorded here for later
 /// reference by a deferred (after-node) op.
 pub(crate) type SlotId = usize;
+/// A named parameter's index within a [`MatchDef`]'s parameter names.
+pub(crate) type ParamId = usize;
 
 /// Maximum nesting depth of an expression accepted by [`compile`].
 ///
@@ -184,7 +209,7 @@ pub(crate) struct ProjectedField {
 /// a quote or a newline is a two- or one-character string here, not the four or two bytes JSON
 /// would spell it with, and `Collation::compare` reaches `cmp_plain_vs_escaped` to compare it
 /// against a document string that *is* escaped.
-fn fastval_from_literal(lit: &Literal) -> FastVal<'static> {
+pub(crate) fn fastval_from_literal(lit: &Literal) -> FastVal<'static> {
     match lit {
         Literal::Null => FastVal::Null,
         Literal::Bool(b) => FastVal::Bool(*b),
@@ -208,13 +233,67 @@ pub(crate) enum DataRef {
     Slot(SlotId),
     /// A built-in function applied to resolved argument values.
     Func(FuncRef),
+    /// A named parameter, by index into [`MatchDef::param_names`], whose value is supplied per
+    /// match call (see [`crate::params`]). Resolves to
+    /// [`FastVal::Missing`](crate::value::FastVal::Missing) when the call leaves it unbound.
+    Param(ParamId),
+    /// A `CASE`: one of several operands, chosen by conditions. Only in deferred ops.
+    Case(Box<CaseRef>),
 }
 
-/// A compiled function application: a name plus the data refs for its arguments.
+/// A compiled `CASE`: each condition in order with the operand it selects, and the operand
+/// selected when none is true.
+#[derive(Debug, Clone)]
+pub(crate) struct CaseRef {
+    pub(crate) whens: Vec<(Cond, DataRef)>,
+    pub(crate) otherwise: DataRef,
+}
+
+/// A `CASE` condition: ops joined by Kleene logic, evaluated directly when the `CASE` is read
+/// rather than reported into the logic tree. Every field an op reads is in a slot.
+#[derive(Debug, Clone)]
+pub(crate) enum Cond {
+    Op(OpKind),
+    Not(Box<Cond>),
+    And(Vec<Cond>),
+    Or(Vec<Cond>),
+}
+
+/// A compiled function application: a name plus the data refs for its arguments, and the
+/// constant ones parsed ahead where the function has a use for that.
 #[derive(Debug, Clone)]
 pub(crate) struct FuncRef {
     pub(crate) name: String,
     pub(crate) params: Vec<DataRef>,
+    /// Set when [`func::prepare`] parsed some constant argument once here, so the matcher
+    /// applies the call with [`func::apply_prepared`] instead of reparsing it per document.
+    /// Shared, like a compiled pattern, so the ops stay small.
+    pub(crate) prepared: Option<Arc<func::Prepared>>,
+    /// The registered function a name that is not built in resolved to; the matcher calls it
+    /// in place of [`func::apply`].
+    pub(crate) custom: Option<Arc<CustomFunction>>,
+}
+
+impl FuncRef {
+    fn new(
+        name: &str,
+        params: Vec<DataRef>,
+        custom: Option<Arc<CustomFunction>>,
+    ) -> Result<FuncRef, CallError> {
+        let consts: Vec<Option<&FastVal<'_>>> = params
+            .iter()
+            .map(|p| match p {
+                DataRef::Const(c) => Some(c),
+                _ => None,
+            })
+            .collect();
+        Ok(FuncRef {
+            prepared: func::prepare(name, &consts)?.map(Arc::new),
+            name: name.to_owned(),
+            params,
+            custom,
+        })
+    }
 }
 
 /// Engine comparison operators (the AST's `NotEquals` is lowered to `NOT (Equals)`).
@@ -261,22 +340,74 @@ pub(crate) enum OpKind {
     /// the answer. For a field from an enclosing scope it is a [`DataRef::Slot`] and the op is
     /// deferred to the scope's after-node, exactly as a cross-field comparison is: the slot is
     /// filled iff the field was present, so "did it resolve" answers the question either way.
+    /// For a call it is a [`DataRef::Func`], placed as a comparison over the call would be, and
+    /// the question is whether the function produced a value.
     Exists { of: DataRef },
-    /// `of` matches a compiled pattern. Same two operand shapes as [`OpKind::Exists`].
+    /// True when `of` resolves to a value other than `NULL`. Same operand shapes as
+    /// [`OpKind::Exists`], and likewise never `Unknown`.
+    Valued { of: DataRef },
+    /// True when the condition evaluates to `True` or `False` — `IS KNOWN` in a `CASE`
+    /// condition, every field it reads in a slot. Anywhere else `IS KNOWN` is a
+    /// [`NodeType::Known`] in the logic tree.
+    Known(Box<Cond>),
+    /// `of` matches a compiled pattern. Same operand shapes as [`OpKind::Exists`].
     Matches {
         matcher: Arc<dyn ValueMatcher>,
         of: DataRef,
     },
+    /// `of` matches the regex `pattern` resolves to — a field, a call or a parameter rather
+    /// than a constant — compiled by the matcher's collation when the op runs, and cached
+    /// there. Both take the shapes of comparison operands, and are placed as a comparison's
+    /// are. `Unknown` when either is missing, or the pattern is not a string or not a regex.
+    MatchesDynamic { pattern: DataRef, of: DataRef },
+    /// `of` equals a member of `set` — an `IN` list, tested as one op instead of an `Or` of
+    /// equality leaves. `of` takes the same shapes as a comparison operand.
+    In { of: DataRef, set: ValueSet },
+    /// `low <= of <= high`, inclusive — a `BETWEEN`, tested as one op instead of an `And` of
+    /// two comparison leaves. All three take the shapes of comparison operands.
+    Between {
+        of: DataRef,
+        low: DataRef,
+        high: DataRef,
+    },
     /// A constant boolean (from `True`/`False` / empty `And`/`Or`).
     Always(bool),
 }
 
-/// A loop over the array at the exec node it is attached to.
+/// What a loop iterates: the elements of an array, or the members of an object through one of
+/// the object list functions (`ANY v IN objectValues(o) …`), which the loop reads in place
+/// instead of building the list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum LoopOver {
+    Elements,
+    /// `objectValues`: each member's value.
+    Values,
+    /// `objectNames`: each member's name, as a string.
+    Names,
+    /// `objectPairs`: each member as `{"name": …, "val": …}`. The body may read only `v.name`
+    /// and `v.val`, since the pair object itself exists nowhere in the document.
+    Pairs,
+}
+
+impl LoopOver {
+    /// The loop a function call in a loop's `in` position stands for, if it is one.
+    fn of_func(name: &str) -> Option<Self> {
+        match name {
+            "objectValues" => Some(Self::Values),
+            "objectNames" => Some(Self::Names),
+            "objectPairs" => Some(Self::Pairs),
+            _ => None,
+        }
+    }
+}
+
+/// A loop over the array (or object) at the exec node it is attached to.
 #[derive(Debug, Clone)]
 pub(crate) struct LoopNode {
     /// The loop *body* bucket (the logic-tree Loop node's child).
     pub(crate) bucket: BucketId,
     pub(crate) mode: LoopType,
+    pub(crate) over: LoopOver,
     /// The exec node evaluated for each array element.
     pub(crate) node: ExecId,
     /// Slots stored by nodes inside the body, cleared before each iteration (see
@@ -286,16 +417,11 @@ pub(crate) struct LoopNode {
 
 /// A loop deferred until its enclosing scope is fully parsed, so its body can reference
 /// outer fields (stored in slots) regardless of document field order. The array itself is
-/// read back from `array_slot`.
+/// read back from `array_slot`, which may belong to that scope or to any scope enclosing it.
 #[derive(Debug, Clone)]
 pub(crate) struct AfterLoopNode {
-    pub(crate) bucket: BucketId,
-    pub(crate) mode: LoopType,
-    pub(crate) node: ExecId,
+    pub(crate) lp: LoopNode,
     pub(crate) array_slot: SlotId,
-    /// Slots stored by nodes inside the body, cleared before each iteration (see
-    /// [`fill_loop_clear_slots`]).
-    pub(crate) clear_slots: Vec<SlotId>,
 }
 
 /// Operations and loops deferred until a scope is fully parsed (so any slots they
@@ -556,8 +682,12 @@ pub(crate) struct ExecNode {
     /// there has no answer. The exception is `Exists`, which asks about presence rather than
     /// about a value and so is answerable precisely when the field is missing — it seals to
     /// `False`. That is what keeps `NOT EXISTS` true on an absent field, where an `Unknown`
-    /// would have made it unmatched.
+    /// would have made it unmatched. `IS VALUED` seals the same way.
     pub(crate) seal_buckets: Vec<(BucketId, Tri)>,
+    /// Whether any entry of `seal_buckets` seals to something other than [`Tri::Unknown`]. A
+    /// loop body's seal after each element treats everything unset as `Unknown`, which is
+    /// only right when this is clear; see [`crate::matcher::FastMatcher`]'s `match_loop`.
+    pub(crate) seal_definite: bool,
 }
 
 /// A compiled expression (or set of expressions): everything the matcher needs to
@@ -578,6 +708,8 @@ pub struct MatchDef {
     /// How many *distinct* slots the projections capture into (two projections of the same
     /// path share one slot). The matcher counts down from this while scanning.
     pub(crate) num_projection_slots: usize,
+    /// The distinct parameter names the expressions reference; [`DataRef::Param`] indexes it.
+    pub(crate) params: Vec<String>,
 }
 
 impl MatchDef {
@@ -610,6 +742,13 @@ impl MatchDef {
     pub fn projection_index(&self, path: &[PathComponent]) -> Option<usize> {
         self.projections.iter().position(|p| p.path == path)
     }
+
+    /// The names of the parameters the expressions reference, each once, in order of first
+    /// appearance. Their values are supplied per call by
+    /// [`FastMatcher::matches_with`](crate::matcher::FastMatcher::matches_with).
+    pub fn param_names(&self) -> &[String] {
+        &self.params
+    }
 }
 
 /// An error encountered while compiling an expression.
@@ -619,28 +758,27 @@ pub enum CompileError {
     NotABoolean,
     #[error("expected an operand expression, found a boolean node")]
     NotAnOperand,
-    #[error("this operator requires a field from the innermost scope")]
-    CrossContext,
     #[error("field references an unknown or out-of-scope variable ({0})")]
     UnknownVariable(VariableId),
-    #[error("a function is not valid here; a plain field reference is required")]
-    Func,
-    #[error("a loop's `in` operand must be a field reference")]
+    #[error("a loop's `in` operand must be a field reference or an object list function")]
     BadLoopTarget,
-    #[error("a match pattern must be a constant string")]
+    #[error("a regex pattern must be a string, and a LIKE pattern a constant one")]
     BadPattern,
     #[error("unsupported: {0}")]
     Unsupported(&'static str),
     #[error("expression is nested deeper than the {MAX_EXPR_DEPTH} level limit")]
     TooDeep,
     #[error(transparent)]
+    Call(#[from] CallError),
+    #[error(transparent)]
     Collation(#[from] CollationError),
     #[error("invalid logic tree: {0}")]
     Tree(#[from] TreeError),
 }
 
 /// Compile expressions and a [`Projection`] into one [`MatchDef`], evaluated in a single
-/// pass. `collation` supplies pattern compilation and the missing-field default.
+/// pass. `collation` supplies pattern compilation and the missing-field default. The
+/// expressions may call built-in functions only; see [`compile_with`] for more.
 ///
 /// The overall match result
 /// ([`MatchOutcome::matched`](crate::matcher::MatchOutcome::matched)) is the OR of all the
@@ -675,11 +813,49 @@ pub fn compile<C: Collation>(
     exprs: &[Expr],
     projection: &Projection,
     collation: &C,
+) -> Result<MatchDef, CompileError> {
+    compile_with(exprs, projection, collation, &Builtins)
+}
+
+/// [`compile`], with calls to functions that are not built in resolved against `functions`
+/// ([`crate::registry`]).
+///
+/// ```
+/// use jsonsm::ast::{CompareOp, Expr, Field, Func, Literal, PathComponent};
+/// use jsonsm::collation::DefaultCollation;
+/// use jsonsm::compile::{compile, compile_with, Projection};
+/// use jsonsm::matcher::FastMatcher;
+/// use jsonsm::registry::Functions;
+/// use jsonsm::value::FastVal;
+///
+/// let functions = Functions::new().function("double", 1..=1, |args| match args[0].as_num() {
+///     Some(n) => FastVal::Float(n.as_f64() * 2.0),
+///     None => FastVal::Missing,
+/// });
+/// let expr = Expr::compare(
+///     CompareOp::Equals,
+///     Expr::Func(Func {
+///         name: "double".into(),
+///         args: vec![Expr::Field(Field::root(vec![PathComponent::Key("n".into())]))],
+///     }),
+///     Expr::Value(Literal::Int(42)),
+/// );
+/// let def = compile_with(&[expr.clone()], &Projection::new(), &DefaultCollation, &functions)?;
+/// assert!(FastMatcher::new(&def).matches(br#"{"n": 21}"#)?.matched());
+/// // Without the registry, the name is unknown.
+/// assert!(compile(&[expr], &Projection::new(), &DefaultCollation).is_err());
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn compile_with<C: Collation>(
+    exprs: &[Expr],
+    projection: &Projection,
+    collation: &C,
+    functions: &dyn FunctionRegistry,
 ) -> Result<MatchDef, CompileError> {
     if exprs.iter().any(|e| e.exceeds_depth(MAX_EXPR_DEPTH)) {
         return Err(CompileError::TooDeep);
     }
-    let mut t = Transformer::new(collation);
+    let mut t = Transformer::new(collation, functions);
     let expr_buckets = match exprs {
         [] => {
             // No expressions: never matches.
@@ -720,6 +896,7 @@ pub fn compile<C: Collation>(
         num_slots: t.slot_idx,
         projections,
         num_projection_slots,
+        params: t.params,
     })
 }
 
@@ -742,6 +919,7 @@ enum Operand {
 
 struct Transformer<'c, C: Collation> {
     collation: &'c C,
+    functions: &'c dyn FunctionRegistry,
     arena: Vec<ExecNode>,
     tree: LogicTree,
     active: BucketId,
@@ -751,12 +929,15 @@ struct Transformer<'c, C: Collation> {
     /// reset — `Some(0)` means "the document root was read". `transform_loop` uses it to
     /// decide whether a loop must be deferred to an after-loop, and how far out.
     min_ref_scope: Option<usize>,
+    /// Parameter names seen so far; a name's position is its [`ParamId`].
+    params: Vec<String>,
 }
 
 impl<'c, C: Collation> Transformer<'c, C> {
-    fn new(collation: &'c C) -> Self {
+    fn new(collation: &'c C, functions: &'c dyn FunctionRegistry) -> Self {
         Transformer {
             collation,
+            functions,
             arena: vec![ExecNode::default()], // root exec node at id 0
             tree: LogicTree::new(),           // root bucket at 0
             active: 0,
@@ -766,9 +947,30 @@ impl<'c, C: Collation> Transformer<'c, C> {
             }],
             slot_idx: 0,
             min_ref_scope: None,
+            params: Vec::new(),
         }
     }
 
+    /// Resolve a call ([`registry::resolve`]): `None` for a built-in, or the custom function.
+    fn resolve_call(
+        &self,
+        call: &jsonsm_ast::Func,
+    ) -> Result<Option<Arc<CustomFunction>>, CompileError> {
+        Ok(registry::resolve(
+            &call.name,
+            call.args.len(),
+            self.functions,
+        )?)
+    }
+
+    /// Whether a call may stand as a condition; an unresolved one is an error.
+    fn is_predicate(&self, call: &jsonsm_ast::Func) -> Result<bool, CompileError> {
+        Ok(match self.resolve_call(call)? {
+            Some(custom) => custom.is_predicate(),
+            None => func::is_predicate(&call.name),
+        })
+    }
+
     fn cur(&self) -> &Ctx {
         self.ctx.last().expect("context stack is never empty")
     }
@@ -778,6 +980,18 @@ impl<'c, C: Collation> Transformer<'c, C> {
         self.arena.len() - 1
     }
 
+    /// The [`ParamId`] for `name`, allocating one on first use so every reference to the same
+    /// name shares a binding.
+    fn param_id(&mut self, name: &str) -> ParamId {
+        match self.params.iter().position(|p| p == name) {
+            Some(id) => id,
+            None => {
+                self.params.push(name.to_owned());
+                self.params.len() - 1
+            }
+        }
+    }
+
     /// Navigate/create the exec chain for `path` starting at exec node `base`.
     fn navigate(&mut self, base: ExecId, path: &[PathComponent]) -> ExecId {
         let mut node = base;
@@ -870,6 +1084,7 @@ impl<'c, C: Collation> Transformer<'c, C> {
     fn make_operand(&mut self, e: &Expr) -> Result<Operand, CompileError> {
         match e {
             Expr::Value(lit) => Ok(Operand::Value(DataRef::Const(fastval_from_literal(lit)))),
+            Expr::Param(name) => Ok(Operand::Value(DataRef::Param(self.param_id(name)))),
             Expr::Field(f) => {
                 let (exec, depth) = self.resolve_field(f)?;
                 if self.is_local(depth) {
@@ -883,12 +1098,17 @@ impl<'c, C: Collation> Transformer<'c, C> {
                 }
             }
             Expr::Func(func) => {
+                let custom = self.resolve_call(func)?;
                 let mut params = Vec::with_capacity(func.args.len());
                 let mut active: Option<ExecId> = None;
                 for arg in &func.args {
                     match self.make_operand(arg)? {
                         Operand::Value(d) => params.push(d),
                         Operand::Field { exec, dref } => {
+                            // A call over several local fields has no single active value;
+                            // every caller sends its op to the after-node instead, through
+                            // `operand_slotref`, where each field is read from a slot.
+                            debug_assert!(active.is_none(), "callers count local fields first");
                             if active.is_some() {
                                 return Err(CompileError::Unsupported(
                                     "function with multiple local field arguments",
@@ -899,10 +1119,7 @@ impl<'c, C: Collation> Transformer<'c, C> {
                         }
                     }
                 }
-                let dref = DataRef::Func(FuncRef {
-                    name: func.name.clone(),
-                    params,
-                });
+                let dref = DataRef::Func(FuncRef::new(&func.name, params, custom)?);
                 Ok(match active {
                     Some(exec) => Operand::Field { exec, dref },
                     None => Operand::Value(dref),
@@ -912,45 +1129,63 @@ impl<'c, C: Collation> Transformer<'c, C> {
         }
     }
 
-    /// Resolve the field operand of an operator that inspects a value directly (`exists`,
-    /// `matches`), returning the exec node to attach the op to and the [`DataRef`] that reads it.
+    /// Add the op of an operator that inspects a value directly (`exists`, `valued`,
+    /// `matches`), built by `kind` from the [`DataRef`] that reads its operand.
     ///
-    /// Mirrors what [`Transformer::make_operand`] does for comparisons. A field in the current
-    /// scope is the actively scanned value, so the op belongs on that field's own node. A field
-    /// from an **enclosing** scope is stored in a slot and the op goes on the current scope's node
+    /// Placed by the rule [`Self::transform_compare`] uses. A field in the current scope is the
+    /// actively scanned value, so the op belongs on that field's own node. A field from an
+    /// **enclosing** scope is stored in a slot and the op goes on the current scope's node
     /// instead — which is visited unconditionally (once per element, in a loop body), and by then
     /// the slot is filled, because `resolve_field` has recorded the scope depth and that is what
-    /// defers the enclosing loop far enough out. This is the same route `name = "a"` inside a loop
-    /// body already takes; only `exists`/`matches` were missing it.
-    fn value_operand(&mut self, e: &Expr) -> Result<(ExecId, DataRef), CompileError> {
+    /// defers the enclosing loop far enough out. A function call goes where the one
+    /// current-scope field it reads does, or on the scope's node if it reads none; one that reads
+    /// several, or sees a missing argument, is deferred to the after-node with every field it
+    /// reads in a slot.
+    ///
+    /// Placed inline, the op never runs when its field is absent, and its leaf is sealed as if
+    /// the operand were missing — right for a call too, since only a function that
+    /// [`func::sees_missing`] answers a missing argument with a value, and that one is deferred.
+    fn add_value_op(
+        &mut self,
+        e: &Expr,
+        kind: impl FnOnce(DataRef) -> OpKind,
+    ) -> Result<(), CompileError> {
         match e {
-            Expr::Field(f) => {
-                let (exec, depth) = self.resolve_field(f)?;
-                if self.is_local(depth) {
-                    Ok((exec, DataRef::Active))
-                } else {
-                    let slot = self.store_field(exec);
-                    Ok((self.cur().exec, DataRef::Slot(slot)))
-                }
+            Expr::Field(_) | Expr::Func(_)
+                if count_local_fields(e, self.cur().var) <= 1 && !must_defer(e) =>
+            {
+                let (exec, of) = match self.make_operand(e)? {
+                    Operand::Field { exec, dref } => (exec, dref),
+                    Operand::Value(dref) => (self.cur().exec, dref),
+                };
+                self.add_op(exec, kind(of));
+                Ok(())
+            }
+            Expr::Func(_) => {
+                let of = self.operand_slotref(e)?;
+                self.add_after_op(kind(of));
+                Ok(())
             }
-            Expr::Func(_) => Err(CompileError::Func),
+            Expr::Param(_) => Err(CompileError::Unsupported(
+                "a parameter as the operand of exists or matches",
+            )),
+            Expr::Case { .. } => Err(CompileError::Unsupported(
+                "a CASE as the operand of exists or matches",
+            )),
             _ => Err(CompileError::NotAnOperand),
         }
     }
 
-    /// Resolve an expression that must be a plain current-context field, returning its exec node.
-    ///
-    /// Only loop targets need this now: the array a loop iterates has to be scanned in the scope
-    /// the loop lives in. Operators that merely *read* a value (`exists`, `matches`) go through
-    /// [`Transformer::value_operand`], which accepts an enclosing scope's field via a slot.
-    fn require_field(&mut self, e: &Expr) -> Result<ExecId, CompileError> {
+    /// Resolve a loop target, which must be a plain field, returning its exec node and whether
+    /// it belongs to the current scope. One that does not is iterated from a slot, once its
+    /// scope has been parsed.
+    fn loop_target(&mut self, e: &Expr) -> Result<(ExecId, bool), CompileError> {
         match e {
-            Expr::Field(f) => match self.resolve_field(f)? {
-                (exec, depth) if self.is_local(depth) => Ok(exec),
-                _ => Err(CompileError::CrossContext),
-            },
-            Expr::Func(_) => Err(CompileError::Func),
-            _ => Err(CompileError::NotAnOperand),
+            Expr::Field(f) => {
+                let (exec, depth) = self.resolve_field(f)?;
+                Ok((exec, self.is_local(depth)))
+            }
+            _ => Err(CompileError::BadLoopTarget),
         }
     }
 
@@ -1030,6 +1265,8 @@ impl<'c, C: Collation> Transformer<'c, C> {
             Expr::Not(sub) => self.transform_not(sub),
             Expr::Exists(sub) => self.transform_exists(sub),
             Expr::NotExists(sub) => self.transform_not(&Expr::Exists(sub.clone())),
+            Expr::Valued(sub) => self.transform_valued(sub),
+            Expr::Known(sub) => self.transform_known(sub),
             Expr::Compare {
                 op: CompareOp::NotEquals,
                 lhs,
@@ -1040,13 +1277,25 @@ impl<'c, C: Collation> Transformer<'c, C> {
             }
             Expr::Compare { op, lhs, rhs } => self.transform_compare(*op, lhs, rhs),
             Expr::Matches { lhs, pattern } => self.transform_matches(lhs, pattern),
+            Expr::Like {
+                lhs,
+                pattern,
+                escape,
+            } => self.transform_like(lhs, pattern, *escape),
+            Expr::In { lhs, list } => self.transform_in(lhs, list),
+            Expr::Between { lhs, low, high } => self.transform_between(lhs, low, high),
             Expr::Loop {
                 loop_type,
                 var,
                 in_expr,
                 sub_expr,
             } => self.transform_loop(*loop_type, *var, in_expr, sub_expr),
-            Expr::Value(_) | Expr::Field(_) | Expr::Func(_) => Err(CompileError::NotABoolean),
+            Expr::Func(f) if self.is_predicate(f)? => self.transform_one(&predicate_compare(f)),
+            Expr::Value(_)
+            | Expr::Field(_)
+            | Expr::Func(_)
+            | Expr::Param(_)
+            | Expr::Case { .. } => Err(CompileError::NotABoolean),
         }
     }
 
@@ -1086,9 +1335,26 @@ impl<'c, C: Collation> Transformer<'c, C> {
     }
 
     fn transform_exists(&mut self, sub: &Expr) -> Result<(), CompileError> {
-        let (exec, of) = self.value_operand(sub)?;
-        self.add_op(exec, OpKind::Exists { of });
-        Ok(())
+        self.add_value_op(sub, |of| OpKind::Exists { of })
+    }
+
+    /// Placed exactly as [`Self::transform_exists`] places `Exists`, and for the same reason: an
+    /// absent field settles the answer, so the leaf is sealed `False` if it never runs.
+    fn transform_valued(&mut self, sub: &Expr) -> Result<(), CompileError> {
+        self.add_value_op(sub, |of| OpKind::Valued { of })
+    }
+
+    /// `(sub) IS KNOWN` is a [`NodeType::Known`] over `sub`, compiled as a `NOT` compiles its
+    /// operand, so `sub` may be anything a condition may be, loops included. A leaf beneath it
+    /// whose field is absent seals `Unknown` as it would anywhere, and the node turns that into
+    /// `False`.
+    fn transform_known(&mut self, sub: &Expr) -> Result<(), CompileError> {
+        let base = self.active;
+        self.tree.set_type(base, NodeType::Known);
+        let left = self.tree.add_child(base);
+        self.tree.set_left(base, left);
+        self.active = left;
+        self.transform_one(sub)
     }
 
     fn transform_compare(
@@ -1098,6 +1364,16 @@ impl<'c, C: Collation> Transformer<'c, C> {
         rhs: &Expr,
     ) -> Result<(), CompileError> {
         let cmp = CmpOp::from_ast(op).expect("NotEquals lowered before here");
+        self.add_binary_op(lhs, rhs, |lhs, rhs| OpKind::Compare { op: cmp, lhs, rhs })
+    }
+
+    /// Place an op over two operands: a comparison, or a regex read from the document.
+    fn add_binary_op(
+        &mut self,
+        lhs: &Expr,
+        rhs: &Expr,
+        kind: impl FnOnce(DataRef, DataRef) -> OpKind,
+    ) -> Result<(), CompileError> {
         let cur_var = self.cur().var;
 
         // When the comparison references at most one *local* (current-context) field, that
@@ -1107,8 +1383,13 @@ impl<'c, C: Collation> Transformer<'c, C> {
         // in a slot and defer the whole comparison to the current scope's after-node —
         // which sees all of them once the scope is fully parsed (at the root: after the
         // document; in a loop body: after each element). This uniformly covers
-        // field-vs-field, `f(a, b) <op> const`, and `f(a) <op> b`, in any context.
-        if count_local_fields(lhs, cur_var) + count_local_fields(rhs, cur_var) <= 1 {
+        // field-vs-field, `f(a, b) <op> const`, and `f(a) <op> b`, in any context. A `CASE`
+        // operand is deferred the same way, whatever it reads: its conditions run when it is.
+        // So is a call to a function such as `ifMissing`, which answers for an absent field.
+        let inline = count_local_fields(lhs, cur_var) + count_local_fields(rhs, cur_var) <= 1
+            && !must_defer(lhs)
+            && !must_defer(rhs);
+        if inline {
             let lhs_ref;
             let rhs_ref;
             let exec = match (self.make_operand(lhs)?, self.make_operand(rhs)?) {
@@ -1129,22 +1410,11 @@ impl<'c, C: Collation> Transformer<'c, C> {
                     unreachable!("at most one field total on this path")
                 }
             };
-            self.add_op(
-                exec,
-                OpKind::Compare {
-                    op: cmp,
-                    lhs: lhs_ref,
-                    rhs: rhs_ref,
-                },
-            );
+            self.add_op(exec, kind(lhs_ref, rhs_ref));
         } else {
             let lhs_ref = self.operand_slotref(lhs)?;
             let rhs_ref = self.operand_slotref(rhs)?;
-            self.add_after_op(OpKind::Compare {
-                op: cmp,
-                lhs: lhs_ref,
-                rhs: rhs_ref,
-            });
+            self.add_after_op(kind(lhs_ref, rhs_ref));
         }
         Ok(())
     }
@@ -1154,41 +1424,231 @@ impl<'c, C: Collation> Transformer<'c, C> {
     fn operand_slotref(&mut self, e: &Expr) -> Result<DataRef, CompileError> {
         match e {
             Expr::Value(lit) => Ok(DataRef::Const(fastval_from_literal(lit))),
+            Expr::Param(name) => Ok(DataRef::Param(self.param_id(name))),
             Expr::Field(f) => {
                 let (exec, _depth) = self.resolve_field(f)?;
                 Ok(DataRef::Slot(self.store_field(exec)))
             }
             Expr::Func(func) => {
+                let custom = self.resolve_call(func)?;
                 let mut params = Vec::with_capacity(func.args.len());
                 for arg in &func.args {
                     params.push(self.operand_slotref(arg)?);
                 }
-                Ok(DataRef::Func(FuncRef {
-                    name: func.name.clone(),
-                    params,
-                }))
+                Ok(DataRef::Func(FuncRef::new(&func.name, params, custom)?))
+            }
+            Expr::Case { whens, otherwise } => {
+                let mut compiled = Vec::with_capacity(whens.len());
+                for (cond, then) in whens {
+                    compiled.push((self.transform_cond(cond)?, self.operand_slotref(then)?));
+                }
+                let otherwise = match otherwise {
+                    Some(e) => self.operand_slotref(e)?,
+                    None => DataRef::Const(FastVal::Null),
+                };
+                Ok(DataRef::Case(Box::new(CaseRef {
+                    whens: compiled,
+                    otherwise,
+                })))
             }
             _ => Err(CompileError::NotAnOperand),
         }
     }
 
+    /// Compile a `CASE` condition. Each leaf becomes the op [`Self::transform_one`] would build
+    /// for it in the after-node — every field in a slot — kept out of the logic tree. A loop
+    /// has no such op, so a condition cannot contain one.
+    fn transform_cond(&mut self, e: &Expr) -> Result<Cond, CompileError> {
+        let op = |kind| Ok(Cond::Op(kind));
+        match e {
+            Expr::True => op(OpKind::Always(true)),
+            Expr::False => op(OpKind::Always(false)),
+            Expr::Not(sub) => Ok(Cond::Not(Box::new(self.transform_cond(sub)?))),
+            Expr::And(subs) => Ok(Cond::And(
+                subs.iter()
+                    .map(|s| self.transform_cond(s))
+                    .collect::<Result<_, _>>()?,
+            )),
+            Expr::Or(subs) => Ok(Cond::Or(
+                subs.iter()
+                    .map(|s| self.transform_cond(s))
+                    .collect::<Result<_, _>>()?,
+            )),
+            Expr::Exists(sub) => op(OpKind::Exists {
+                of: self.operand_slotref(sub)?,
+            }),
+            Expr::NotExists(sub) => Ok(Cond::Not(Box::new(Cond::Op(OpKind::Exists {
+                of: self.operand_slotref(sub)?,
+            })))),
+            Expr::Valued(sub) => op(OpKind::Valued {
+                of: self.operand_slotref(sub)?,
+            }),
+            Expr::Known(sub) => op(OpKind::Known(Box::new(self.transform_cond(sub)?))),
+            // `!=` is lowered to `NOT (=)` here too.
+            Expr::Compare { op: cmp, lhs, rhs } => {
+                let compare = |this: &mut Self, op| {
+                    Ok::<_, CompileError>(Cond::Op(OpKind::Compare {
+                        op,
+                        lhs: this.operand_slotref(lhs)?,
+                        rhs: this.operand_slotref(rhs)?,
+                    }))
+                };
+                match CmpOp::from_ast(*cmp) {
+                    Some(op) => compare(self, op),
+                    None => Ok(Cond::Not(Box::new(compare(self, CmpOp::Eq)?))),
+                }
+            }
+            Expr::Matches { lhs, pattern } => match pattern.as_ref() {
+                Expr::Value(Literal::String(p)) => op(OpKind::Matches {
+                    matcher: Arc::from(self.collation.compile_matcher(p)?),
+                    of: self.operand_slotref(lhs)?,
+                }),
+                Expr::Value(_) => Err(CompileError::BadPattern),
+                _ => {
+                    self.check_dynamic_patterns()?;
+                    op(OpKind::MatchesDynamic {
+                        pattern: self.operand_slotref(pattern)?,
+                        of: self.operand_slotref(lhs)?,
+                    })
+                }
+            },
+            Expr::Like {
+                lhs,
+                pattern,
+                escape,
+            } => {
+                let Expr::Value(Literal::String(p)) = pattern.as_ref() else {
+                    return Err(CompileError::BadPattern);
+                };
+                op(OpKind::Matches {
+                    matcher: Arc::from(self.collation.compile_like(p, *escape)?),
+                    of: self.operand_slotref(lhs)?,
+                })
+            }
+            Expr::In { lhs, list } => op(OpKind::In {
+                of: self.operand_slotref(lhs)?,
+                set: ValueSet::new(list.iter().cloned(), self.collation),
+            }),
+            Expr::Between { lhs, low, high } => op(OpKind::Between {
+                of: self.operand_slotref(lhs)?,
+                low: self.operand_slotref(low)?,
+                high: self.operand_slotref(high)?,
+            }),
+            Expr::Loop { .. } => Err(CompileError::Unsupported("a loop in a CASE condition")),
+            Expr::Func(f) if self.is_predicate(f)? => self.transform_cond(&predicate_compare(f)),
+            Expr::Value(_)
+            | Expr::Field(_)
+            | Expr::Func(_)
+            | Expr::Param(_)
+            | Expr::Case { .. } => Err(CompileError::NotABoolean),
+        }
+    }
+
+    /// A constant pattern is compiled here, once. Any other operand is read when the op runs,
+    /// so the op is placed as a comparison between it and `lhs` would be.
     fn transform_matches(&mut self, lhs: &Expr, pattern: &Expr) -> Result<(), CompileError> {
-        let (exec, of) = self.value_operand(lhs)?;
+        match pattern {
+            Expr::Value(Literal::String(p)) => {
+                let matcher = Arc::from(self.collation.compile_matcher(p)?);
+                self.add_value_op(lhs, |of| OpKind::Matches { matcher, of })
+            }
+            Expr::Value(_) => Err(CompileError::BadPattern),
+            _ => {
+                self.check_dynamic_patterns()?;
+                self.add_binary_op(lhs, pattern, |of, pattern| OpKind::MatchesDynamic {
+                    pattern,
+                    of,
+                })
+            }
+        }
+    }
+
+    /// Fail now, rather than at every match, under a collation that compiles no regex at all.
+    /// Such a collation reports `MatcherUnsupported` whatever the pattern, so one probe tells;
+    /// whether the probe itself compiles is of no interest.
+    fn check_dynamic_patterns(&self) -> Result<(), CompileError> {
+        match self.collation.compile_matcher("") {
+            Err(e @ CollationError::MatcherUnsupported) => Err(e.into()),
+            Ok(_) | Err(CollationError::InvalidPattern(_)) => Ok(()),
+        }
+    }
+
+    /// `LIKE` shares the `matches` op; only the collation method that compiles the pattern
+    /// differs.
+    fn transform_like(
+        &mut self,
+        lhs: &Expr,
+        pattern: &Expr,
+        escape: Option<char>,
+    ) -> Result<(), CompileError> {
         let pattern_str = match pattern {
             Expr::Value(Literal::String(s)) => s.as_str(),
             _ => return Err(CompileError::BadPattern),
         };
-        let matcher = self.collation.compile_matcher(pattern_str)?;
-        self.add_op(
-            exec,
-            OpKind::Matches {
-                matcher: Arc::from(matcher),
-                of,
-            },
-        );
+        let matcher = Arc::from(self.collation.compile_like(pattern_str, escape)?);
+        self.add_value_op(lhs, |of| OpKind::Matches { matcher, of })
+    }
+
+    /// Lower `lhs IN (list)` to a single [`OpKind::In`], placed the way a comparison against a
+    /// constant would be: inline at the field's node when `lhs` reads at most one current-scope
+    /// field, otherwise in the scope's after-node with every field it reads in a slot.
+    fn transform_in(&mut self, lhs: &Expr, list: &[Literal]) -> Result<(), CompileError> {
+        let set = ValueSet::new(list.iter().cloned(), self.collation);
+        if count_local_fields(lhs, self.cur().var) <= 1 && !must_defer(lhs) {
+            let (exec, of) = match self.make_operand(lhs)? {
+                Operand::Field { exec, dref } => (exec, dref),
+                Operand::Value(dref) => (self.cur().exec, dref),
+            };
+            self.add_op(exec, OpKind::In { of, set });
+        } else {
+            let of = self.operand_slotref(lhs)?;
+            self.add_after_op(OpKind::In { of, set });
+        }
         Ok(())
     }
 
+    /// Lower `lhs BETWEEN low AND high` to a single [`OpKind::Between`], placed by the rule
+    /// [`Self::transform_compare`] uses: inline at the one current-scope field the three
+    /// operands read between them, if they read at most one, else in the after-node.
+    fn transform_between(
+        &mut self,
+        lhs: &Expr,
+        low: &Expr,
+        high: &Expr,
+    ) -> Result<(), CompileError> {
+        let cur_var = self.cur().var;
+        let local = [lhs, low, high]
+            .iter()
+            .map(|e| count_local_fields(e, cur_var))
+            .sum::<usize>();
+        if local <= 1 && ![lhs, low, high].into_iter().any(must_defer) {
+            let mut exec = self.cur().exec;
+            let of = self.placed_operand(lhs, &mut exec)?;
+            let low = self.placed_operand(low, &mut exec)?;
+            let high = self.placed_operand(high, &mut exec)?;
+            self.add_op(exec, OpKind::Between { of, low, high });
+        } else {
+            let op = OpKind::Between {
+                of: self.operand_slotref(lhs)?,
+                low: self.operand_slotref(low)?,
+                high: self.operand_slotref(high)?,
+            };
+            self.add_after_op(op);
+        }
+        Ok(())
+    }
+
+    /// [`Self::make_operand`], moving `exec` to the operand's field node if it reads one.
+    fn placed_operand(&mut self, e: &Expr, exec: &mut ExecId) -> Result<DataRef, CompileError> {
+        Ok(match self.make_operand(e)? {
+            Operand::Field { exec: at, dref } => {
+                *exec = at;
+                dref
+            }
+            Operand::Value(dref) => dref,
+        })
+    }
+
     fn transform_loop(
         &mut self,
         mode: LoopType,
@@ -1196,11 +1656,19 @@ impl<'c, C: Collation> Transformer<'c, C> {
         in_expr: &Expr,
         sub_expr: &Expr,
     ) -> Result<(), CompileError> {
-        // The array being looped must be a field in the current context.
-        let in_exec = self.require_field(in_expr).map_err(|e| match e {
-            CompileError::Func | CompileError::NotAnOperand => CompileError::BadLoopTarget,
-            other => other,
-        })?;
+        // The array being looped must be a field, or an object list function of one, which
+        // loops over that object's members where they lie.
+        let (target, over) = match in_expr {
+            // Resolved first, so a misspelled list function is reported as one.
+            Expr::Func(call) => match (self.resolve_call(call)?, LoopOver::of_func(&call.name)) {
+                (None, Some(over)) => (&call.args[0], over),
+                _ => (in_expr, LoopOver::Elements),
+            },
+            _ => (in_expr, LoopOver::Elements),
+        };
+        // Resolved in the enclosing tracking, so a target from further out defers the loops
+        // around this one as a body's reference would.
+        let (in_exec, local) = self.loop_target(target)?;
 
         let base = self.active;
         self.tree.set_type(base, NodeType::Loop);
@@ -1228,36 +1696,51 @@ impl<'c, C: Collation> Transformer<'c, C> {
             (a, b) => a.or(b),
         };
         result?;
+        if over == LoopOver::Pairs {
+            let body = &self.arena[body_exec];
+            let members = [&b"name"[..], b"val"]
+                .into_iter()
+                .filter(|k| body.elems.get(k).is_some())
+                .count();
+            if body.elems.len() > members
+                || !body.ops.is_empty()
+                || body.store.is_some()
+                || !body.loops.is_empty()
+                || !body.indexed.is_empty()
+            {
+                return Err(CompileError::Unsupported(
+                    "an objectPairs loop body may read only the pair's `name` and `val`",
+                ));
+            }
+        }
 
-        if body_min.is_some_and(|m| m <= host_scope) {
+        let lp = LoopNode {
+            bucket: body_bucket,
+            mode,
+            over,
+            node: body_exec,
+            clear_slots: Vec::new(), // filled by `fill_loop_clear_slots`
+        };
+        if !local || body_min.is_some_and(|m| m <= host_scope) {
             // The body reads fields from the scope containing this loop (or shallower), which
             // are only knowable once that scope has been fully parsed. Store the array and
             // defer the loop to that scope's after-node: at the root it runs after the whole
             // document, and inside an enclosing loop body it runs after each element. If the
             // body reached further out still, the enclosing loop was deferred as well (see
             // the propagation above), so by the time this loop runs every scope it reads has
-            // been parsed — regardless of document field order.
+            // been parsed — regardless of document field order. An array from an enclosing
+            // scope is such a reach too: it is not scanned where this loop lives, so the loop
+            // can only run from its slot.
             let array_slot = self.store_field(in_exec);
             let host_exec = self.ctx[host_scope].exec;
             self.arena[host_exec]
                 .after
                 .get_or_insert_with(AfterNode::default)
                 .loops
-                .push(AfterLoopNode {
-                    bucket: body_bucket,
-                    mode,
-                    node: body_exec,
-                    array_slot,
-                    clear_slots: Vec::new(), // filled by `fill_loop_clear_slots`
-                });
+                .push(AfterLoopNode { lp, array_slot });
         } else {
             // Inline loop over the array as it is scanned.
-            self.arena[in_exec].loops.push(LoopNode {
-                bucket: body_bucket,
-                mode,
-                node: body_exec,
-                clear_slots: Vec::new(), // filled by `fill_loop_clear_slots`
-            });
+            self.arena[in_exec].loops.push(lp);
         }
         Ok(())
     }
@@ -1290,7 +1773,7 @@ fn fill_loop_clear_slots(arena: &mut [ExecNode]) {
                     .loops
                     .iter()
                     .enumerate()
-                    .map(|(i, l)| (id, true, i, l.node)),
+                    .map(|(i, l)| (id, true, i, l.lp.node)),
             );
         }
     }
@@ -1303,6 +1786,7 @@ fn fill_loop_clear_slots(arena: &mut [ExecNode]) {
                 .as_mut()
                 .expect("after-node exists")
                 .loops[i]
+                .lp
                 .clear_slots = slots;
         } else {
             arena[owner].loops[i].clear_slots = slots;
@@ -1327,7 +1811,7 @@ fn subtree_slots(arena: &[ExecNode], root: ExecId) -> Vec<SlotId> {
         stack.extend(node.indexed.iter().map(|&(_, child)| child));
         stack.extend(node.loops.iter().map(|l| l.node));
         if let Some(after) = &node.after {
-            stack.extend(after.loops.iter().map(|l| l.node));
+            stack.extend(after.loops.iter().map(|l| l.lp.node));
         }
     }
     out.sort_unstable();
@@ -1339,6 +1823,10 @@ fn subtree_slots(arena: &[ExecNode], root: ExecId) -> Vec<SlotId> {
 fn fill_seal_buckets(arena: &mut [ExecNode]) {
     for id in 0..arena.len() {
         arena[id].seal_buckets = subtree_buckets(arena, id);
+        arena[id].seal_definite = arena[id]
+            .seal_buckets
+            .iter()
+            .any(|&(_, t)| t != Tri::Unknown);
     }
 }
 
@@ -1354,7 +1842,7 @@ fn subtree_buckets(arena: &[ExecNode], root: ExecId) -> Vec<(BucketId, Tri)> {
     fn absent_value(kind: &OpKind) -> Tri {
         match kind {
             // Presence is exactly what this asks, and the field is not present.
-            OpKind::Exists { .. } => Tri::False,
+            OpKind::Exists { .. } | OpKind::Valued { .. } => Tri::False,
             _ => Tri::Unknown,
         }
     }
@@ -1368,13 +1856,13 @@ fn subtree_buckets(arena: &[ExecNode], root: ExecId) -> Vec<(BucketId, Tri)> {
         out.extend(node.loops.iter().map(|l| (l.bucket, Tri::Unknown)));
         if let Some(after) = &node.after {
             out.extend(after.ops.iter().map(|o| (o.bucket, absent_value(&o.kind))));
-            out.extend(after.loops.iter().map(|l| (l.bucket, Tri::Unknown)));
+            out.extend(after.loops.iter().map(|l| (l.lp.bucket, Tri::Unknown)));
         }
         stack.extend(node.elems.values());
         stack.extend(node.indexed.iter().map(|&(_, child)| child));
         stack.extend(node.loops.iter().map(|l| l.node));
         if let Some(after) = &node.after {
-            stack.extend(after.loops.iter().map(|l| l.node));
+            stack.extend(after.loops.iter().map(|l| l.lp.node));
         }
     }
     out.sort_unstable_by_key(|&(b, _)| b);
@@ -1397,10 +1885,31 @@ fn count_local_fields(e: &Expr, cur_var: VariableId) -> usize {
     }
 }
 
+/// A predicate function call standing as a condition, as the comparison it means: `f(…) = TRUE`,
+/// so a missing result is UNKNOWN like any other comparison against one.
+fn predicate_compare(f: &jsonsm_ast::Func) -> Expr {
+    Expr::compare(
+        CompareOp::Equals,
+        Expr::Func(f.clone()),
+        Expr::Value(Literal::Bool(true)),
+    )
+}
+
+/// Whether an op over an operand must be deferred to the after-node, whatever fields it reads:
+/// the operand contains a `CASE`, or a call to a function that takes a missing argument as an
+/// input ([`func::sees_missing`]) and so has to run when its field is absent, too.
+fn must_defer(e: &Expr) -> bool {
+    match e {
+        Expr::Case { .. } => true,
+        Expr::Func(func) => func::sees_missing(&func.name) || func.args.iter().any(must_defer),
+        _ => false,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::collation::DefaultCollation;
+    use crate::collation::{Comparison, DefaultCollation};
 
     fn field(keys: &[&str]) -> Expr {
         Expr::Field(Field::root(
@@ -1513,14 +2022,84 @@ mod tests {
         assert_eq!(d.arena[x].ops[0].bucket, 1);
     }
 
-    /// `exists` and `matches` reach an enclosing scope's field; a **loop target** still may not.
-    ///
-    /// Pins the boundary in both directions, so `CrossContext` narrowing to exactly one case is a
-    /// stated property rather than something the next reader has to rediscover. The array a loop
-    /// iterates has to be scanned in the scope the loop lives in; an operator that merely reads a
-    /// value can take it from a slot, which is what the first two accept.
     #[test]
-    fn cross_scope_is_allowed_for_value_operators_but_not_loop_targets() {
+    fn in_lists_compile_to_one_op() {
+        let d = compile_ok(&Expr::In {
+            lhs: Box::new(field(&["status"])),
+            list: ["a", "b", "c", "b"]
+                .map(|s| Literal::String(s.into()))
+                .to_vec(),
+        });
+        // One leaf bucket, not an `Or` of three equality leaves; duplicates collapse.
+        assert_eq!(d.num_buckets(), 1);
+        let n = d.arena[d.root].elems["status"];
+        assert!(matches!(
+            &d.arena[n].ops[0].kind,
+            OpKind::In { of: DataRef::Active, set } if set.len() == 3
+        ));
+        // Reading two fields of the current scope, it waits for the scope's after-node.
+        let d = compile_ok(&Expr::In {
+            lhs: Box::new(Expr::Func(jsonsm_ast::Func {
+                name: "mathAdd".into(),
+                args: vec![field(&["a"]), field(&["b"])],
+            })),
+            list: vec![Literal::Int(3)],
+        });
+        let after = d.arena[d.root].after.as_ref().expect("after-node");
+        assert!(matches!(after.ops[0].kind, OpKind::In { .. }));
+    }
+
+    #[test]
+    fn between_compiles_to_one_op() {
+        let int = |i| Box::new(Expr::Value(Literal::Int(i)));
+        let d = compile_ok(&Expr::Between {
+            lhs: Box::new(field(&["ts"])),
+            low: int(100),
+            high: int(200),
+        });
+        // One leaf bucket and one op, where `>=` AND `<=` would be three buckets and two ops.
+        assert_eq!(d.num_buckets(), 1);
+        let ts = d.arena[d.root].elems["ts"];
+        assert_eq!(d.arena[ts].ops.len(), 1);
+        assert!(matches!(
+            d.arena[ts].ops[0].kind,
+            OpKind::Between {
+                of: DataRef::Active,
+                low: DataRef::Const(FastVal::Int(100)),
+                high: DataRef::Const(FastVal::Int(200)),
+            }
+        ));
+        // A bound read from another field of the scope defers it to the after-node.
+        let d = compile_ok(&Expr::Between {
+            lhs: Box::new(field(&["ts"])),
+            low: Box::new(field(&["start"])),
+            high: int(200),
+        });
+        let after = d.arena[d.root].after.as_ref().expect("after-node");
+        assert!(matches!(after.ops[0].kind, OpKind::Between { .. }));
+    }
+
+    #[test]
+    fn parameters_are_numbered_once_per_name() {
+        let p = |n: &str| Expr::Param(n.into());
+        let d = compile_ok(&Expr::Or(vec![
+            Expr::compare(CompareOp::Equals, field(&["a"]), p("x")),
+            Expr::compare(CompareOp::LessThan, field(&["b"]), p("y")),
+            Expr::compare(CompareOp::GreaterThan, field(&["c"]), p("x")),
+        ]));
+        assert_eq!(d.param_names(), ["x", "y"]);
+        // A parameter is an operand, not a predicate, and has no field to test or match.
+        assert!(matches!(compile_err(&p("x")), CompileError::NotABoolean));
+        assert!(matches!(
+            compile_err(&Expr::Exists(Box::new(p("x")))),
+            CompileError::Unsupported(_)
+        ));
+    }
+
+    /// `exists`, `matches`, a comparison and a **loop target** all reach an enclosing scope's
+    /// field, each through a slot.
+    #[test]
+    fn cross_scope_is_allowed_for_value_operators_and_loop_targets() {
         // ANY t IN tags SATISFIES <op on the document-scope field `name`> END
         let body_over_outer = |body: Expr| Expr::Loop {
             loop_type: LoopType::Any,
@@ -1542,7 +2121,9 @@ mod tests {
             Expr::Value(Literal::Int(1)),
         )));
 
-        // But a nested loop whose *array* comes from an enclosing scope is still rejected.
+        // So can a nested loop whose *array* comes from an enclosing scope: it runs from the
+        // array's slot after each outer element, and the outer loop after the document, by
+        // which time `ys` has been read wherever it lies.
         let inner_over_outer = Expr::Loop {
             loop_type: LoopType::Any,
             var: 1,
@@ -1562,10 +2143,16 @@ mod tests {
                 )),
             }),
         };
-        assert!(matches!(
-            compile_err(&inner_over_outer),
-            CompileError::CrossContext
-        ));
+        let d = compile_ok(&inner_over_outer);
+        let root = &d.arena[d.root];
+        let ys = root.elems["ys"];
+        assert!(root.loops.is_empty() && d.arena[root.elems["xs"]].loops.is_empty());
+        let outer = &root.after.as_ref().expect("root after-node").loops[0];
+        assert_eq!(Some(outer.array_slot), d.arena[root.elems["xs"]].store);
+        let body = &d.arena[outer.lp.node];
+        let inner = &body.after.as_ref().expect("body after-node").loops[0];
+        assert_eq!(Some(inner.array_slot), d.arena[ys].store);
+        assert!(d.arena[ys].loops.is_empty());
     }
 
     #[test]
@@ -1578,6 +2165,84 @@ mod tests {
         assert_eq!(d.num_buckets(), 2); // Not + Exists leaf
     }
 
+    #[test]
+    fn compiles_valued_inline_and_known_deferred() {
+        // `IS VALUED` sits on its field like `Exists`, and an absent field seals it `False`.
+        let d = compile_ok(&Expr::Valued(Box::new(field(&["maybe"]))));
+        let n = d.arena[d.root].elems["maybe"];
+        let op = &d.arena[n].ops[0];
+        assert!(matches!(op.kind, OpKind::Valued { .. }));
+        assert!(d.arena[n].seal_buckets.contains(&(op.bucket, Tri::False)));
+
+        // `IS KNOWN` is a node over its operand, whose op is placed as it would be alone and
+        // seals as it would alone; the node answers for that.
+        let gt = Expr::compare(
+            CompareOp::GreaterThan,
+            field(&["a"]),
+            Expr::Value(Literal::Int(1)),
+        );
+        for (sub, sealed) in [
+            (gt, Tri::Unknown),
+            (Expr::Exists(Box::new(field(&["a"]))), Tri::False),
+        ] {
+            let d = compile_ok(&Expr::Known(Box::new(sub)));
+            assert_eq!((d.num_slots(), d.num_buckets()), (0, 2));
+            let n = d.arena[d.root].elems["a"];
+            let op = &d.arena[n].ops[0];
+            assert!(d.arena[n].seal_buckets.contains(&(op.bucket, sealed)));
+        }
+        // A loop under it compiles as it would anywhere.
+        let d = compile_ok(&Expr::Known(Box::new(Expr::Loop {
+            loop_type: LoopType::Any,
+            var: 1,
+            in_expr: Box::new(field(&["xs"])),
+            sub_expr: Box::new(Expr::compare(
+                CompareOp::Equals,
+                Expr::Field(Field {
+                    root: 1,
+                    path: vec![],
+                }),
+                Expr::Value(Literal::Int(1)),
+            )),
+        })));
+        assert_eq!(d.arena[d.arena[d.root].elems["xs"]].loops.len(), 1);
+    }
+
+    #[test]
+    fn compiles_bare_predicate_calls_as_compare_to_true() {
+        let call = |name: &str| {
+            Expr::Func(jsonsm_ast::Func {
+                name: name.into(),
+                args: vec![field(&["a"])],
+            })
+        };
+        for cond in [call("isString"), Expr::Not(Box::new(call("isObject")))] {
+            let explicit = match &cond {
+                Expr::Not(f) => Expr::Not(Box::new(Expr::compare(
+                    CompareOp::Equals,
+                    (**f).clone(),
+                    Expr::Value(Literal::Bool(true)),
+                ))),
+                f => Expr::compare(
+                    CompareOp::Equals,
+                    f.clone(),
+                    Expr::Value(Literal::Bool(true)),
+                ),
+            };
+            assert_eq!(
+                format!("{:?}", compile_ok(&cond)),
+                format!("{:?}", compile_ok(&explicit))
+            );
+        }
+        // Only predicates stand as conditions; `type` and the other functions are operands.
+        for name in ["type", "strLower", "mathAbs"] {
+            assert!(matches!(
+                compile_err(&call(name)),
+                CompileError::NotABoolean
+            ));
+        }
+    }
+
     #[test]
     fn compiles_loop_with_body_scope() {
         let d = compile_ok(&Expr::Loop {
@@ -1639,6 +2304,119 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn constant_function_arguments_are_prepared() {
+        // ipInCidr(a, net) = b, deferred for its two fields so the call is easy to find.
+        let prepared = |net: Expr| {
+            let d = compile_ok(&Expr::compare(
+                CompareOp::Equals,
+                Expr::Func(jsonsm_ast::Func {
+                    name: "ipInCidr".into(),
+                    args: vec![field(&["a"]), net],
+                }),
+                field(&["b"]),
+            ));
+            let after = d.arena[d.root].after.as_ref().expect("after node");
+            match &after.ops[0].kind {
+                OpKind::Compare {
+                    lhs: DataRef::Func(f),
+                    ..
+                } => f.prepared.as_deref().cloned(),
+                other => panic!("expected a deferred call, got {other:?}"),
+            }
+        };
+        let s = |t: &str| Expr::Value(Literal::String(t.into()));
+        assert!(matches!(
+            prepared(s("10.0.0.0/8")),
+            Some(func::Prepared::IpInCidr(_))
+        ));
+        // A non-constant network is left to each document, and a malformed one fails the
+        // compile, as a malformed constant pattern does.
+        assert!(prepared(field(&["c"])).is_none());
+        let call = |name: &str, args: Vec<Expr>| {
+            Expr::Func(jsonsm_ast::Func {
+                name: name.into(),
+                args,
+            })
+        };
+        assert!(matches!(
+            compile_err(&call("ipInCidr", vec![field(&["a"]), s("10.0.0.0/33")])),
+            CompileError::Call(CallError::Malformed { arg: 1, .. })
+        ));
+        assert!(matches!(
+            compile_err(&call(
+                "ipInRange",
+                vec![field(&["a"]), s("10.0.0.1"), s("10.0.0.x")]
+            )),
+            CompileError::Call(CallError::Malformed { arg: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn calls_are_resolved_against_builtins_and_the_registry() {
+        use crate::registry::Functions;
+        let call = |name: &str, args: Vec<Expr>| {
+            Expr::Func(jsonsm_ast::Func {
+                name: name.into(),
+                args,
+            })
+        };
+        let is_one = |e: Expr| Expr::compare(CompareOp::Equals, e, Expr::Value(Literal::Int(1)));
+        let unknown = |e: CompileError| matches!(e, CompileError::Call(CallError::Unknown(_)));
+        // Misspelled, as an operand, a condition, a deferred operand and a loop target.
+        assert!(unknown(compile_err(&is_one(call(
+            "mathAbss",
+            vec![field(&["a"])]
+        )))));
+        assert!(unknown(compile_err(&call(
+            "strContain",
+            vec![field(&["a"])]
+        ))));
+        assert!(unknown(compile_err(&is_one(call(
+            "mathAd",
+            vec![field(&["a"]), field(&["b"])]
+        )))));
+        assert!(unknown(compile_err(&Expr::Loop {
+            loop_type: LoopType::Any,
+            var: 1,
+            in_expr: Box::new(call("objectValue", vec![field(&["o"])])),
+            sub_expr: Box::new(Expr::True),
+        })));
+        assert!(matches!(
+            compile_err(&is_one(call("mathAbs", vec![field(&["a"]), field(&["b"])]))),
+            CompileError::Call(CallError::Arity { got: 2, .. })
+        ));
+
+        let functions = Functions::new()
+            .function("twice", 1..=1, |_| FastVal::Null)
+            .predicate("odd", 1..=1, |_| FastVal::Bool(true))
+            .function("strLower", 1..=1, |_| FastVal::Null);
+        let with = |e: Expr| compile_with(&[e], &Projection::new(), &DefaultCollation, &functions);
+        let d = with(is_one(call("twice", vec![field(&["a"])]))).expect("compiles");
+        let n = d.arena[d.root].elems["a"];
+        match &d.arena[n].ops[0].kind {
+            OpKind::Compare {
+                lhs: DataRef::Func(f),
+                ..
+            } => assert!(f.custom.is_some()),
+            other => panic!("expected a call, got {other:?}"),
+        }
+        assert!(with(call("odd", vec![field(&["a"])])).is_ok());
+        // A custom function that is not a predicate is no condition.
+        assert!(matches!(
+            with(call("twice", vec![field(&["a"])])),
+            Err(CompileError::NotABoolean)
+        ));
+        assert!(matches!(
+            with(is_one(call("twice", vec![]))),
+            Err(CompileError::Call(CallError::Arity { got: 0, .. }))
+        ));
+        assert!(matches!(
+            with(is_one(call("strLower", vec![field(&["a"])]))),
+            Err(CompileError::Call(CallError::Redefined(_)))
+        ));
+    }
+
     #[test]
     fn multi_field_comparisons_use_slots_and_after() {
         // Two-field function argument: mathAdd(a, b) == 1 -> both fields stored, deferred.
@@ -1661,7 +2439,39 @@ mod tests {
             }
         ));
 
-        // Cross-field inside a loop body now compiles (body's after runs per element).
+        // Cross-field inside a loop body compiles (body's after runs per element), and so
+        // does a function over two of the element's fields: `i.qty * i.price > 100`.
+        let d = compile_ok(&Expr::Loop {
+            loop_type: LoopType::Any,
+            var: 1,
+            in_expr: Box::new(field(&["items"])),
+            sub_expr: Box::new(Expr::compare(
+                CompareOp::GreaterThan,
+                Expr::Func(jsonsm_ast::Func {
+                    name: "mathMultiply".into(),
+                    args: vec![
+                        Expr::Field(Field {
+                            root: 1,
+                            path: vec![PathComponent::Key("qty".into())],
+                        }),
+                        Expr::Field(Field {
+                            root: 1,
+                            path: vec![PathComponent::Key("price".into())],
+                        }),
+                    ],
+                }),
+                Expr::Value(Literal::Int(100)),
+            )),
+        });
+        let items = d.arena[d.root].elems["items"];
+        let body = d.arena[items].loops[0].node;
+        assert_eq!(d.num_slots(), 2);
+        let after = d.arena[body].after.as_ref().expect("element after node");
+        assert!(matches!(
+            &after.ops[0].kind,
+            OpKind::Compare { lhs: DataRef::Func(f), .. }
+                if matches!(f.params[..], [DataRef::Slot(_), DataRef::Slot(_)])
+        ));
         compile_ok(&Expr::Loop {
             loop_type: LoopType::Any,
             var: 1,
@@ -1680,6 +2490,99 @@ mod tests {
         });
     }
 
+    #[test]
+    fn case_operands_are_deferred_with_their_fields_in_slots() {
+        // CASE WHEN kind = "a" THEN price ELSE 0 END > 1: one field per branch, but still
+        // deferred, with the condition kept out of the logic tree.
+        let case = Expr::Case {
+            whens: vec![(
+                Expr::compare(
+                    CompareOp::NotEquals,
+                    field(&["kind"]),
+                    Expr::Value(Literal::String("a".into())),
+                ),
+                field(&["price"]),
+            )],
+            otherwise: None,
+        };
+        let d = compile_ok(&Expr::compare(
+            CompareOp::GreaterThan,
+            case.clone(),
+            Expr::Value(Literal::Int(1)),
+        ));
+        assert_eq!(d.num_slots(), 2);
+        assert!(d.arena[d.root].ops.is_empty());
+        let after = d.arena[d.root].after.as_ref().expect("after node");
+        assert_eq!(after.ops.len(), 1);
+        let OpKind::Compare {
+            lhs: DataRef::Case(c),
+            ..
+        } = &after.ops[0].kind
+        else {
+            panic!(
+                "expected a deferred comparison of a CASE: {:?}",
+                after.ops[0].kind
+            );
+        };
+        assert!(matches!(&c.whens[..], [(Cond::Not(_), DataRef::Slot(_))]));
+        assert!(matches!(c.otherwise, DataRef::Const(FastVal::Null)));
+
+        // Inside a function argument, an `IN` or a `BETWEEN` too.
+        let in_func = Expr::Func(jsonsm_ast::Func {
+            name: "mathAbs".into(),
+            args: vec![case.clone()],
+        });
+        for e in [
+            Expr::compare(CompareOp::Equals, in_func, Expr::Value(Literal::Int(1))),
+            Expr::In {
+                lhs: Box::new(case.clone()),
+                list: vec![Literal::Int(1)],
+            },
+            Expr::Between {
+                lhs: Box::new(case.clone()),
+                low: Box::new(Expr::Value(Literal::Int(1))),
+                high: Box::new(Expr::Value(Literal::Int(2))),
+            },
+        ] {
+            let d = compile_ok(&e);
+            assert!(d.arena[d.root].ops.is_empty(), "{e:?}");
+            assert_eq!(d.arena[d.root].after.as_ref().unwrap().ops.len(), 1);
+        }
+    }
+
+    #[test]
+    fn missing_aware_functions_are_deferred() {
+        let call = |name: &str| {
+            Expr::Func(jsonsm_ast::Func {
+                name: name.into(),
+                args: vec![field(&["a"]), Expr::Value(Literal::Int(0))],
+            })
+        };
+        // An ordinary one-field function runs inline on its field's node…
+        let d = compile_ok(&Expr::compare(
+            CompareOp::Equals,
+            call("mathAdd"),
+            Expr::Value(Literal::Int(1)),
+        ));
+        assert!(d.arena[d.root].after.is_none());
+        // …but one that answers for an absent field waits for the scope, its field in a slot,
+        // also when nested in another function's argument.
+        let nested = Expr::Func(jsonsm_ast::Func {
+            name: "mathAbs".into(),
+            args: vec![call("ifMissing")],
+        });
+        for lhs in [call("ifMissing"), call("ifNull"), nested] {
+            let d = compile_ok(&Expr::compare(
+                CompareOp::Equals,
+                lhs,
+                Expr::Value(Literal::Int(1)),
+            ));
+            assert!(d.arena[d.root].ops.is_empty());
+            assert_eq!(d.num_slots(), 1);
+            assert_eq!(d.arena[d.root].after.as_ref().unwrap().ops.len(), 1);
+        }
+    }
+
     #[test]
     fn projection_marks_fields_for_storage() {
         let projection = Projection::new().field(["name", "first"]).field(["age"]);
@@ -1761,15 +2664,130 @@ mod tests {
     }
 
     #[test]
-    fn rejects_unsupported_shapes() {
-        // exists on a function is not a field
+    fn value_operators_take_function_operands() {
+        let call = |name: &str, args: Vec<Expr>| {
+            Expr::Func(jsonsm_ast::Func {
+                name: name.into(),
+                args,
+            })
+        };
+        // EXISTS(mathAbs(a)): on `a`'s node, like `mathAbs(a) = 1` would be.
+        let d = compile_ok(&Expr::Exists(Box::new(call(
+            "mathAbs",
+            vec![field(&["a"])],
+        ))));
+        let n = d.arena[d.root].elems["a"];
         assert!(matches!(
-            compile_err(&Expr::Exists(Box::new(Expr::Func(jsonsm_ast::Func {
-                name: "mathAbs".into(),
-                args: vec![field(&["a"])]
-            })))),
-            CompileError::Func
+            &d.arena[n].ops[0].kind,
+            OpKind::Exists { of: DataRef::Func(f) } if matches!(f.params[..], [DataRef::Active])
+        ));
+        // REGEXP_CONTAINS(LOWER(email), "@corp"): likewise.
+        let d = compile_ok(&Expr::Matches {
+            lhs: Box::new(call("strLower", vec![field(&["email"])])),
+            pattern: Box::new(Expr::Value(Literal::String("@corp".into()))),
+        });
+        let n = d.arena[d.root].elems["email"];
+        assert!(matches!(
+            &d.arena[n].ops[0].kind,
+            OpKind::Matches {
+                of: DataRef::Func(_),
+                ..
+            }
+        ));
+        // EXISTS(IFMISSING(a, b)) reads two fields, and answers for absent ones: deferred.
+        let d = compile_ok(&Expr::Exists(Box::new(call(
+            "ifMissing",
+            vec![field(&["a"]), field(&["b"])],
+        ))));
+        assert_eq!(d.num_slots(), 2);
+        let after = d.arena[d.root].after.as_ref().expect("after node");
+        assert!(matches!(
+            &after.ops[0].kind,
+            OpKind::Exists { of: DataRef::Func(f) }
+                if matches!(f.params[..], [DataRef::Slot(_), DataRef::Slot(_)])
+        ));
+        // A call over constants alone, on the scope's node.
+        let d = compile_ok(&Expr::Valued(Box::new(call("mathPi", vec![]))));
+        assert!(matches!(
+            &d.arena[d.root].ops[0].kind,
+            OpKind::Valued {
+                of: DataRef::Func(_)
+            }
+        ));
+    }
+
+    #[test]
+    fn non_constant_patterns_are_placed_as_comparisons() {
+        let matches = |lhs: Expr, pattern: Expr| Expr::Matches {
+            lhs: Box::new(lhs),
+            pattern: Box::new(pattern),
+        };
+        // value MATCHES rule: two fields of the scope, so deferred with both in slots.
+        let d = compile_ok(&matches(field(&["value"]), field(&["rule"])));
+        assert_eq!(d.num_slots(), 2);
+        let after = d.arena[d.root].after.as_ref().expect("after node");
+        assert!(matches!(
+            after.ops[0].kind,
+            OpKind::MatchesDynamic {
+                pattern: DataRef::Slot(_),
+                of: DataRef::Slot(_)
+            }
+        ));
+        // value MATCHES $re: inline on `value`'s node.
+        let d = compile_ok(&matches(field(&["value"]), Expr::Param("re".into())));
+        let n = d.arena[d.root].elems["value"];
+        assert!(matches!(
+            d.arena[n].ops[0].kind,
+            OpKind::MatchesDynamic {
+                pattern: DataRef::Param(_),
+                of: DataRef::Active
+            }
         ));
+        // Under a collation without regexes, a pattern from the document fails the compile as
+        // a constant one does.
+        struct NoPatterns;
+        impl Collation for NoPatterns {
+            fn compare(&self, a: &FastVal<'_>, b: &FastVal<'_>) -> Comparison {
+                DefaultCollation.compare(a, b)
+            }
+        }
+        let err = compile(
+            &[matches(field(&["value"]), field(&["rule"]))],
+            &Projection::new(),
+            &NoPatterns,
+        )
+        .expect_err("should not compile");
+        assert!(matches!(
+            err,
+            CompileError::Collation(CollationError::MatcherUnsupported)
+        ));
+        // Only that variant fails the compile: a collation that rejects the probe as an
+        // invalid pattern still takes patterns from the document.
+        struct NoEmptyPatterns;
+        impl Collation for NoEmptyPatterns {
+            fn compare(&self, a: &FastVal<'_>, b: &FastVal<'_>) -> Comparison {
+                DefaultCollation.compare(a, b)
+            }
+            fn compile_matcher(
+                &self,
+                pattern: &str,
+            ) -> Result<Box<dyn ValueMatcher>, CollationError> {
+                if pattern.is_empty() {
+                    return Err(CollationError::InvalidPattern("empty".into()));
+                }
+                DefaultCollation.compile_matcher(pattern)
+            }
+        }
+        compile(
+            &[matches(field(&["value"]), field(&["rule"]))],
+            &Projection::new(),
+            &NoEmptyPatterns,
+        )
+        .expect("compiles");
+    }
+
+    #[test]
+    fn rejects_unsupported_shapes() {
         // bad pattern (non-string)
         assert!(matches!(
             compile_err(&Expr::Matches {
@@ -1778,6 +2796,105 @@ mod tests {
             }),
             CompileError::BadPattern
         ));
+        // a loop over a function other than an object list, or over a pair as a whole
+        let looped = |list: &str, sub_expr: Expr| Expr::Loop {
+            loop_type: LoopType::Any,
+            var: 1,
+            in_expr: Box::new(Expr::Func(jsonsm_ast::Func {
+                name: list.into(),
+                args: vec![field(&["o"])],
+            })),
+            sub_expr: Box::new(sub_expr),
+        };
+        let var = |path: &[&str]| {
+            Expr::Field(Field {
+                root: 1,
+                path: path.iter().map(|&k| PathComponent::Key(k.into())).collect(),
+            })
+        };
+        let is_one = |e: Expr| Expr::compare(CompareOp::Equals, e, Expr::Value(Literal::Int(1)));
+        assert!(matches!(
+            compile_err(&looped("arrayMax", Expr::True)),
+            CompileError::BadLoopTarget
+        ));
+        assert!(matches!(
+            compile_err(&looped("objectPairs", is_one(var(&[])))),
+            CompileError::Unsupported(_)
+        ));
+        assert!(matches!(
+            compile_err(&looped("objectPairs", Expr::Exists(Box::new(var(&[]))))),
+            CompileError::Unsupported(_)
+        ));
+        assert!(matches!(
+            compile_err(&looped("objectPairs", is_one(var(&["foo"])))),
+            CompileError::Unsupported(_)
+        ));
+        assert!(matches!(
+            compile_err(&looped(
+                "objectPairs",
+                Expr::And(vec![is_one(var(&["name"])), is_one(var(&["foo", "val"]))])
+            )),
+            CompileError::Unsupported(_)
+        ));
+        let ok = looped("objectPairs", is_one(var(&["val", "x"])));
+        assert!(compile(&[ok], &Projection::new(), &DefaultCollation).is_ok());
+        assert!(compile(
+            &[looped("objectNames", is_one(var(&[])))],
+            &Projection::new(),
+            &DefaultCollation
+        )
+        .is_ok());
+        // a CASE whose condition loops, or tested for presence or a pattern
+        let case = |cond: Expr| Expr::Case {
+            whens: vec![(cond, Expr::Value(Literal::Int(1)))],
+            otherwise: None,
+        };
+        let looped = case(Expr::Loop {
+            loop_type: LoopType::Any,
+            var: 1,
+            in_expr: Box::new(field(&["xs"])),
+            sub_expr: Box::new(Expr::True),
+        });
+        assert!(matches!(
+            compile_err(&Expr::compare(
+                CompareOp::Equals,
+                looped,
+                Expr::Value(Literal::Int(1))
+            )),
+            CompileError::Unsupported(_)
+        ));
+        assert!(matches!(
+            compile_err(&Expr::Exists(Box::new(case(Expr::True)))),
+            CompileError::Unsupported(_)
+        ));
+        assert!(matches!(
+            compile_err(&Expr::Known(Box::new(field(&["a"])))),
+            CompileError::NotABoolean
+        ));
+        assert!(matches!(
+            compile_err(&Expr::compare(
+                CompareOp::Equals,
+                case(field(&["a"])),
+                Expr::Value(Literal::Int(1))
+            )),
+            CompileError::NotABoolean
+        ));
+        assert!(matches!(
+            compile_err(&Expr::Like {
+                lhs: Box::new(field(&["a"])),
+                pattern: Box::new(field(&["b"])),
+                escape: None,
+            }),
+            CompileError::BadPattern
+        ));
+        assert!(matches!(
+            compile_err(&Expr::Like {
+                lhs: Box::new(field(&["a"])),
+                pattern: Box::new(Expr::Value(Literal::String("a!".into()))),
+                escape: Some('!'),
+            }),
+            CompileError::Collation(_)
+        ));
     }
 }
 
@@ -1,15 +1,27 @@
-//! ISO-8601 date/time parsing for the `DATE()` function.
+//! ISO-8601 date/time parsing for the `DATE()` function, and the calendar arithmetic behind
+//! the other date functions.
 //!
 //! [`parse_epoch`] converts a date string to seconds since the Unix epoch (UTC). The
 //! `DATE()` function returns this as a [`crate::value::FastVal::Float`], so date
 //! comparisons are ordinary numeric comparisons — no implicit string→time coercion (that
 //! would violate the strict-N1QL collation). Accepts `YYYY`, `YYYY-MM`, `YYYY-MM-DD`, and
-//! `YYYY-MM-DD(T| )HH:MM[:SS[.fff]]` with an optional `Z` or `±HH[:MM]` timezone; missing
-//! components default to the start of the period, matching gojsonsm's date handling.
+//! `YYYY-MM-DD(T| )HH:MM[:SS[.fff]]` with an optional `Z`, `±HH`, `±HHMM` or `±HH:MM`
+//! timezone; missing components default to the start of the period, matching gojsonsm's date
+//! handling. Each component is its exact number of digits, and in range: the day must exist in
+//! its month, and a second of 60 is rejected as Go's parser rejects it.
+//! [`parse_epoch_format`] reads any other layout, described in strftime's directives.
+//!
+//! [`parse`] reads the same forms into a [`DateTime`]: an exact instant in milliseconds, and
+//! the offset the string was written in. [`part`], [`add`], [`diff`] and [`trunc`] work on the
+//! date's wall clock in that offset, and [`format()`] writes one back, or [`format_layout`] in
+//! strftime's directives. There is no time-zone
+//! database: an offset is fixed, so a day is always 24 hours. A `DateTime` lies in the years
+//! 0000 to 9999, which is what four year digits can write, and an operation that would leave
+//! them gives `None`.
 
 /// Days from 1970-01-01 to the given proleptic-Gregorian date (Howard Hinnant's
 /// `days_from_civil`, exact for all dates).
-fn days_from_civil(y: i64, m: i64, d: i64) -> i64 {
+const fn days_from_civil(y: i64, m: i64, d: i64) -> i64 {
     let y = if m <= 2 { y - 1 } else { y };
     let era = if y >= 0 { y } else { y - 399 } / 400;
     let yoe = y - era * 400; // [0, 399]
@@ -35,83 +47,726 @@ fn read_uint(b: &[u8], i: &mut usize) -> Option<i64> {
     }
 }
 
+/// The proleptic-Gregorian date `days` after 1970-01-01, as `(year, month, day)`: the inverse
+/// of [`days_from_civil`], also Hinnant's.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097; // [0, 146096]
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365; // [0, 399]
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100); // [0, 365]
+    let mp = (5 * doy + 2) / 153; // Mar=0 … Feb=11
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = yoe + era * 400;
+    (if m <= 2 { y + 1 } else { y }, m, d)
+}
+
+fn is_leap(y: i64) -> bool {
+    y % 4 == 0 && (y % 100 != 0 || y % 400 == 0)
+}
+
+fn days_in_month(y: i64, m: i64) -> i64 {
+    match m {
+        2 if is_leap(y) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Read exactly `n` ASCII digits at `*i`, advancing past them.
+fn read_fixed(b: &[u8], i: &mut usize, n: usize) -> Option<i64> {
+    let digits = b.get(*i..*i + n)?;
+    if !digits.iter().all(u8::is_ascii_digit) {
+        return None;
+    }
+    *i += n;
+    Some(digits.iter().fold(0, |v, d| v * 10 + i64::from(d - b'0')))
+}
+
+/// Read one to `max` ASCII digits at `*i`, as many as there are, advancing past them.
+fn read_upto(b: &[u8], i: &mut usize, max: usize) -> Option<i64> {
+    let n = b[*i..]
+        .iter()
+        .take(max)
+        .take_while(|d| d.is_ascii_digit())
+        .count();
+    if n == 0 {
+        return None;
+    }
+    read_fixed(b, i, n)
+}
+
+/// Read a timezone at `*i` — `Z`, or `±HH`, `±HHMM` or `±HH:MM` — as its sign, hours and
+/// minutes.
+fn read_zone(b: &[u8], i: &mut usize) -> Option<(i64, i64, i64)> {
+    let sign = match *b.get(*i)? {
+        b'Z' | b'z' => {
+            *i += 1;
+            return Some((1, 0, 0));
+        }
+        b'+' => 1,
+        b'-' => -1,
+        _ => return None,
+    };
+    *i += 1;
+    let hh = read_fixed(b, i, 2)?;
+    let mm = match b.get(*i) {
+        Some(b':') => {
+            *i += 1;
+            read_fixed(b, i, 2)?
+        }
+        Some(d) if d.is_ascii_digit() => read_fixed(b, i, 2)?,
+        _ => 0,
+    };
+    Some((sign, hh, mm))
+}
+
+/// The components of a date string, as written.
+struct Fields {
+    year: i64,
+    month: i64,
+    day: i64,
+    hh: i64,
+    mm: i64,
+    ss: i64,
+    /// The fraction of a second's digits, and how many there are.
+    frac: (i64, u32),
+    /// The timezone's sign, hours and minutes.
+    tz: (i64, i64, i64),
+}
+
+impl Default for Fields {
+    /// The start of 1970 in UTC, which a component the string leaves out defaults to.
+    fn default() -> Self {
+        Fields {
+            year: 1970,
+            month: 1,
+            day: 1,
+            hh: 0,
+            mm: 0,
+            ss: 0,
+            frac: (0, 0),
+            tz: (1, 0, 0),
+        }
+    }
+}
+
+impl Fields {
+    /// Whether every component is in range: the day within its month, the time within a day
+    /// (so no leap second), and the timezone within 23:59 of UTC.
+    fn valid(&self) -> bool {
+        let (_, oh, om) = self.tz;
+        (0..=9999).contains(&self.year)
+            && (1..=12).contains(&self.month)
+            && (1..=days_in_month(self.year, self.month)).contains(&self.day)
+            && self.hh <= 23
+            && self.mm <= 59
+            && self.ss <= 59
+            && oh <= 23
+            && om <= 59
+    }
+
+    /// The timezone, in seconds east of UTC.
+    fn tz_off(&self) -> i64 {
+        let (sign, oh, om) = self.tz;
+        sign * (oh * 3600 + om * 60)
+    }
+
+    /// Seconds from the epoch to the time as written, before the timezone is applied.
+    fn local_secs(&self) -> i64 {
+        days_from_civil(self.year, self.month, self.day) * 86_400
+            + self.hh * 3_600
+            + self.mm * 60
+            + self.ss
+    }
+
+    /// Seconds since the epoch, the fraction included.
+    fn epoch(&self) -> f64 {
+        let (digits, len) = self.frac;
+        (self.local_secs() - self.tz_off()) as f64 + digits as f64 / 10f64.powi(len as i32)
+    }
+}
+
 /// Parse an ISO-8601 date/time into seconds since the Unix epoch (UTC), or `None` if the
 /// string is not a valid date in the accepted forms.
 pub fn parse_epoch(s: &str) -> Option<f64> {
-    let s = s.trim();
-    let b = s.as_bytes();
+    parse_fields(s).map(|f| f.epoch())
+}
+
+/// Read the ISO-8601 forms: each component its exact number of digits, and in range.
+fn parse_fields(s: &str) -> Option<Fields> {
+    let b = s.trim().as_bytes();
     let n = b.len();
     let mut i = 0;
-
-    let year = read_uint(b, &mut i)?;
-    let mut month = 1;
-    let mut day = 1;
-    let (mut hh, mut mm, mut ss) = (0i64, 0i64, 0i64);
-    let mut frac = 0.0f64;
-    let mut tz_off = 0i64; // seconds east of UTC
+    let mut f = Fields {
+        year: read_fixed(b, &mut i, 4)?,
+        ..Fields::default()
+    };
 
     if i < n && b[i] == b'-' {
         i += 1;
-        month = read_uint(b, &mut i)?;
+        f.month = read_fixed(b, &mut i, 2)?;
         if i < n && b[i] == b'-' {
             i += 1;
-            day = read_uint(b, &mut i)?;
+            f.day = read_fixed(b, &mut i, 2)?;
             if i < n && matches!(b[i], b'T' | b't' | b' ') {
                 i += 1;
-                hh = read_uint(b, &mut i)?;
+                f.hh = read_fixed(b, &mut i, 2)?;
                 if i >= n || b[i] != b':' {
                     return None;
                 }
                 i += 1;
-                mm = read_uint(b, &mut i)?;
+                f.mm = read_fixed(b, &mut i, 2)?;
                 if i < n && b[i] == b':' {
                     i += 1;
-                    ss = read_uint(b, &mut i)?;
+                    f.ss = read_fixed(b, &mut i, 2)?;
                     if i < n && b[i] == b'.' {
                         i += 1;
                         let start = i;
                         let digits = read_uint(b, &mut i)?;
-                        let len = (i - start) as u32;
-                        frac = digits as f64 / 10f64.powi(len as i32);
+                        f.frac = (digits, (i - start) as u32);
                     }
                 }
                 // Optional timezone.
                 if i < n {
-                    match b[i] {
-                        b'Z' | b'z' => i += 1,
-                        b'+' | b'-' => {
-                            let sign = if b[i] == b'-' { -1 } else { 1 };
-                            i += 1;
-                            let oh = read_uint(b, &mut i)?;
-                            let om = if i < n && b[i] == b':' {
-                                i += 1;
-                                read_uint(b, &mut i)?
-                            } else {
-                                0
-                            };
-                            tz_off = sign * (oh * 3600 + om * 60);
-                        }
-                        _ => {}
-                    }
+                    f.tz = read_zone(b, &mut i)?;
                 }
             }
         }
     }
 
     // Reject trailing garbage and out-of-range components.
-    if i != n
-        || !(1..=12).contains(&month)
-        || !(1..=31).contains(&day)
-        || hh > 23
-        || mm > 59
-        || ss > 60
-    {
+    (i == n && f.valid()).then_some(f)
+}
+
+/// Parse `s` as laid out by `format`, into seconds since the Unix epoch. `format` takes
+/// strftime's directives:
+///
+/// | Directive | Reads |
+/// | --- | --- |
+/// | `%Y`, `%y` | the year, in up to four digits or in two (`69` to `99` are 19xx, the rest 20xx) |
+/// | `%m`, `%d`, `%e` | the month and the day of the month, in one or two digits |
+/// | `%b`, `%h`, `%B` | the month's English name, abbreviated or in full, in any case |
+/// | `%a`, `%A` | the weekday's name, likewise, which must be the date's |
+/// | `%H`, `%I`, `%p` | the hour of 24, or of 12 with `AM` or `PM` |
+/// | `%M`, `%S`, `%f` | the minute and second, and a fraction of a second's digits |
+/// | `%z`, `%Z` | a timezone as `Z`, `±HH`, `±HHMM` or `±HH:MM`, or named `UTC`, `GMT` or `UT` |
+/// | `%s`, `%Q` | the whole instant, as epoch seconds or milliseconds |
+/// | `%F`, `%T` | `%Y-%m-%d` and `%H:%M:%S` |
+/// | `%%` | a `%` |
+///
+/// Whitespace in `format` matches any run of it, none included; any other character matches
+/// itself. A component `format` has no directive for takes its value at the start of 1970 in
+/// UTC. `None` for an unknown directive, a string that does not fit the layout, a component out
+/// of range, or `%s` or `%Q` alongside a directive for a component.
+pub fn parse_epoch_format(s: &str, format: &str) -> Option<f64> {
+    let format = format.replace("%%", "%\u{0}");
+    let format = format.replace("%F", "%Y-%m-%d").replace("%T", "%H:%M:%S");
+    let (b, fb) = (s.as_bytes(), format.as_bytes());
+    let (mut i, mut j) = (0, 0);
+    let mut f = Fields::default();
+    let mut epoch = None;
+    let mut components = false;
+    let (mut twelve, mut pm, mut weekday) = (false, None, None);
+
+    while j < fb.len() {
+        let c = fb[j];
+        j += 1;
+        if c.is_ascii_whitespace() {
+            while b.get(i).is_some_and(u8::is_ascii_whitespace) {
+                i += 1;
+            }
+            continue;
+        }
+        if c != b'%' {
+            if b.get(i) != Some(&c) {
+                return None;
+            }
+            i += 1;
+            continue;
+        }
+        let d = *fb.get(j)?;
+        j += 1;
+        components |= !matches!(d, b's' | b'Q' | 0);
+        match d {
+            b'Y' => f.year = read_upto(b, &mut i, 4)?,
+            b'y' => {
+                let y = read_fixed(b, &mut i, 2)?;
+                f.year = if y >= 69 { 1900 + y } else { 2000 + y };
+            }
+            b'm' => f.month = read_upto(b, &mut i, 2)?,
+            b'd' => f.day = read_upto(b, &mut i, 2)?,
+            b'e' => {
+                if b.get(i) == Some(&b' ') {
+                    i += 1;
+                }
+                f.day = read_upto(b, &mut i, 2)?;
+            }
+            b'b' | b'h' | b'B' => f.month = read_name(b, &mut i, &MONTHS)? + 1,
+            b'a' | b'A' => weekday = Some(read_name(b, &mut i, &WEEKDAYS)?),
+            b'H' => f.hh = read_upto(b, &mut i, 2)?,
+            b'I' => {
+                f.hh = read_upto(b, &mut i, 2)?;
+                twelve = true;
+            }
+            b'p' => pm = Some(read_name(b, &mut i, &["am", "pm"])? == 1),
+            b'M' => f.mm = read_upto(b, &mut i, 2)?,
+            b'S' => f.ss = read_upto(b, &mut i, 2)?,
+            b'f' => {
+                let start = i;
+                let digits = read_upto(b, &mut i, 18)?;
+                f.frac = (digits, (i - start) as u32);
+            }
+            b'z' => f.tz = read_zone(b, &mut i)?,
+            b'Z' => {
+                read_name(b, &mut i, &["utc", "gmt", "ut", "z"])?;
+                f.tz = (1, 0, 0);
+            }
+            b's' | b'Q' => {
+                let negative = b.get(i) == Some(&b'-');
+                i += usize::from(negative);
+                let n = read_upto(b, &mut i, 18)? as f64;
+                let n = if negative { -n } else { n };
+                epoch = Some(if d == b's' { n } else { n / 1000.0 });
+            }
+            0 => {
+                if b.get(i) != Some(&b'%') {
+                    return None;
+                }
+                i += 1;
+            }
+            _ => return None,
+        }
+    }
+    if i != b.len() {
+        return None;
+    }
+    if let Some(epoch) = epoch {
+        return (!components).then_some(epoch);
+    }
+
+    match (twelve, pm) {
+        (true, Some(pm)) if (1..=12).contains(&f.hh) => f.hh = f.hh % 12 + if pm { 12 } else { 0 },
+        (false, None) => {}
+        _ => return None,
+    }
+    let days = days_from_civil(f.year, f.month, f.day);
+    // 1970-01-01 was a Thursday.
+    if !f.valid() || weekday.is_some_and(|w| w != (days + 4).rem_euclid(7)) {
+        return None;
+    }
+    Some(f.epoch())
+}
+
+const MONTHS: [&str; 12] = [
+    "january",
+    "february",
+    "march",
+    "april",
+    "may",
+    "june",
+    "july",
+    "august",
+    "september",
+    "october",
+    "november",
+    "december",
+];
+const WEEKDAYS: [&str; 7] = [
+    "sunday",
+    "monday",
+    "tuesday",
+    "wednesday",
+    "thursday",
+    "friday",
+    "saturday",
+];
+
+/// Read one of `names` at `*i`, in any case, in full or by its first three letters; its index.
+fn read_name(b: &[u8], i: &mut usize, names: &[&str]) -> Option<i64> {
+    let rest = &b[*i..];
+    let starts = |prefix: &[u8]| {
+        rest.len() >= prefix.len() && rest[..prefix.len()].eq_ignore_ascii_case(prefix)
+    };
+    for (k, name) in names.iter().enumerate() {
+        let name = name.as_bytes();
+        for len in [name.len(), 3.min(name.len())] {
+            if starts(&name[..len]) {
+                *i += len;
+                return Some(k as i64);
+            }
+        }
+    }
+    None
+}
+
+const MS_PER_DAY: i64 = 86_400_000;
+/// The first and last milliseconds of the years a [`DateTime`] may lie in.
+const MIN_MILLIS: i64 = days_from_civil(0, 1, 1) * MS_PER_DAY;
+const MAX_MILLIS: i64 = days_from_civil(10_000, 1, 1) * MS_PER_DAY - 1;
+/// The largest offset from UTC, a minute short of a day.
+const MAX_OFFSET: i64 = 86_399;
+
+/// An instant, and the fixed offset from UTC whose wall clock it is read on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DateTime {
+    /// Milliseconds since the Unix epoch.
+    pub millis: i64,
+    /// Seconds east of UTC.
+    pub offset: i64,
+}
+
+impl DateTime {
+    /// The instant `millis`, read in UTC; `None` outside the years 0000 to 9999.
+    pub fn from_millis(millis: i64) -> Option<DateTime> {
+        DateTime { millis, offset: 0 }.checked()
+    }
+
+    /// The same instant, read at `offset` seconds east of UTC.
+    pub fn at_offset(self, offset: i64) -> DateTime {
+        DateTime { offset, ..self }
+    }
+
+    fn checked(self) -> Option<DateTime> {
+        ((MIN_MILLIS..=MAX_MILLIS).contains(&self.millis) && self.offset.abs() <= MAX_OFFSET)
+            .then_some(self)
+    }
+
+    /// Milliseconds from the epoch to the wall-clock time, as if that were UTC.
+    fn local(self) -> i64 {
+        self.millis + self.offset * 1000
+    }
+
+    /// The instant whose wall-clock time, at this offset, is `local`.
+    fn at_local(self, local: i64) -> Option<DateTime> {
+        DateTime {
+            millis: local.checked_sub(self.offset * 1000)?,
+            offset: self.offset,
+        }
+        .checked()
+    }
+
+    /// The wall clock's date and time of day: `((year, month, day), milliseconds into it)`.
+    fn civil(self) -> ((i64, i64, i64), i64) {
+        let local = self.local();
+        (
+            civil_from_days(local.div_euclid(MS_PER_DAY)),
+            local.rem_euclid(MS_PER_DAY),
+        )
+    }
+}
+
+/// Parse a date string in the forms [`parse_epoch`] accepts, keeping its offset. A fraction of a
+/// second is cut to whole milliseconds. `None` for a string that is not a date.
+pub fn parse(s: &str) -> Option<DateTime> {
+    let f = parse_fields(s)?;
+    let (digits, len) = f.frac;
+    let millis = match len {
+        0..=3 => digits * 10i64.pow(3 - len),
+        // Leading zeros can make the digits outnumber what a power of ten in an `i64` divides.
+        _ => 10i64.checked_pow(len - 3).map_or(0, |p| digits / p),
+    };
+    DateTime {
+        millis: (f.local_secs() - f.tz_off()) * 1000 + millis,
+        offset: f.tz_off(),
+    }
+    .checked()
+}
+
+/// Parse a fixed offset from UTC, `UTC` or a timezone as a date string writes one (`Z`, `±HH`,
+/// `±HHMM` or `±HH:MM`), into seconds east of it.
+pub fn parse_offset(s: &str) -> Option<i64> {
+    let s = s.trim();
+    if s.eq_ignore_ascii_case("utc") {
+        return Some(0);
+    }
+    let (b, mut i) = (s.as_bytes(), 0);
+    let tz = read_zone(b, &mut i)?;
+    let f = Fields {
+        tz,
+        ..Fields::default()
+    };
+    (i == b.len() && f.valid()).then(|| f.tz_off())
+}
+
+/// Write a date as `YYYY-MM-DDTHH:MM:SS`, then `.mmm` if it has milliseconds, then `Z` at
+/// offset zero or `±HH:MM` at any other. `None` if the wall clock's year is outside 0000 to
+/// 9999, as it can be near either end.
+pub fn format(dt: DateTime) -> Option<String> {
+    let ((y, m, d), ms) = dt.civil();
+    if !(0..=9999).contains(&y) {
         return None;
     }
+    let mut out = format!(
+        "{y:04}-{m:02}-{d:02}T{:02}:{:02}:{:02}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60
+    );
+    if ms % 1000 != 0 {
+        out += &format!(".{:03}", ms % 1000);
+    }
+    if dt.offset == 0 {
+        out.push('Z');
+    } else {
+        let sign = if dt.offset < 0 { '-' } else { '+' };
+        let off = dt.offset.abs();
+        out += &format!("{sign}{:02}:{:02}", off / 3600, off / 60 % 60);
+    }
+    Some(out)
+}
+
+/// Write a date as `layout` lays it out, in the directives [`parse_epoch_format`] reads: each
+/// writes its component at full width, zero-padded (`%e` space-padded), `%f` as three digits
+/// of milliseconds, names in title case, `%p` as `AM` or `PM`, and `%z` as `±HHMM`. `%Z` writes
+/// `UTC` at offset zero and the offset as `%z` does at any other. Any other character is
+/// written as it is. `None` for an unknown directive, or a year outside 0000 to 9999.
+pub fn format_layout(dt: DateTime, layout: &str) -> Option<String> {
+    let ((y, m, d), ms) = dt.civil();
+    if !(0..=9999).contains(&y) {
+        return None;
+    }
+    let (hh, mm, ss) = (ms / 3_600_000, ms / 60_000 % 60, ms / 1000 % 60);
+    // 1970-01-01 was a Thursday.
+    let weekday = (dt.local().div_euclid(MS_PER_DAY) + 4).rem_euclid(7) as usize;
+    let title = |name: &str, len: usize| name[..1].to_ascii_uppercase() + &name[1..len];
+    let month = MONTHS[m as usize - 1];
+    let zone = format!(
+        "{}{:02}{:02}",
+        if dt.offset < 0 { '-' } else { '+' },
+        dt.offset.abs() / 3600,
+        dt.offset.abs() / 60 % 60
+    );
+
+    let mut out = String::with_capacity(layout.len() + 16);
+    let mut chars = layout.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        let piece = match chars.next()? {
+            'Y' => format!("{y:04}"),
+            'y' => format!("{:02}", y % 100),
+            'm' => format!("{m:02}"),
+            'd' => format!("{d:02}"),
+            'e' => format!("{d:2}"),
+            'b' | 'h' => title(month, 3),
+            'B' => title(month, month.len()),
+            'a' => title(WEEKDAYS[weekday], 3),
+            'A' => title(WEEKDAYS[weekday], WEEKDAYS[weekday].len()),
+            'H' => format!("{hh:02}"),
+            'I' => format!("{:02}", (hh + 11) % 12 + 1),
+            'p' => (if hh < 12 { "AM" } else { "PM" }).to_owned(),
+            'M' => format!("{mm:02}"),
+            'S' => format!("{ss:02}"),
+            'f' => format!("{:03}", ms % 1000),
+            'z' => zone.clone(),
+            'Z' if dt.offset == 0 => "UTC".to_owned(),
+            'Z' => zone.clone(),
+            's' => dt.millis.div_euclid(1000).to_string(),
+            'Q' => dt.millis.to_string(),
+            'F' => format!("{y:04}-{m:02}-{d:02}"),
+            'T' => format!("{hh:02}:{mm:02}:{ss:02}"),
+            '%' => "%".to_owned(),
+            _ => return None,
+        };
+        out += &piece;
+    }
+    Some(out)
+}
+
+/// A component of a date, by its N1QL name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DatePart {
+    Millennium,
+    Century,
+    Decade,
+    Year,
+    Quarter,
+    Month,
+    /// The week of the year, 1 to 53, its first seven days week 1.
+    Week,
+    Day,
+    Hour,
+    Minute,
+    Second,
+    Millisecond,
+    DayOfYear,
+    /// 0 for Sunday to 6 for Saturday.
+    DayOfWeek,
+    IsoYear,
+    IsoWeek,
+    /// 1 for Monday to 7 for Sunday.
+    IsoDayOfWeek,
+    /// The offset from UTC in seconds, and its hours and minutes.
+    Timezone,
+    TimezoneHour,
+    TimezoneMinute,
+}
+
+impl DatePart {
+    /// The part a name spells, case-insensitively; `None` for an unknown name.
+    pub fn from_name(name: &str) -> Option<DatePart> {
+        use DatePart::*;
+        Some(match name.to_ascii_lowercase().as_str() {
+            "millennium" => Millennium,
+            "century" => Century,
+            "decade" => Decade,
+            "year" => Year,
+            "quarter" => Quarter,
+            "month" => Month,
+            "week" => Week,
+            "day" => Day,
+            "hour" => Hour,
+            "minute" => Minute,
+            "second" => Second,
+            "millisecond" => Millisecond,
+            "day_of_year" | "doy" => DayOfYear,
+            "day_of_week" | "dow" => DayOfWeek,
+            "iso_year" => IsoYear,
+            "iso_week" => IsoWeek,
+            "iso_dow" => IsoDayOfWeek,
+            "timezone" => Timezone,
+            "timezone_hour" => TimezoneHour,
+            "timezone_minute" => TimezoneMinute,
+            _ => return None,
+        })
+    }
 
-    let days = days_from_civil(year, month, day);
-    let secs = days * 86_400 + hh * 3_600 + mm * 60 + ss - tz_off;
-    Some(secs as f64 + frac)
+    /// A unit's fixed length in milliseconds, or `None` for the calendar units (month and
+    /// longer) and for parts that are not units at all.
+    fn millis(self) -> Option<i64> {
+        use DatePart::*;
+        match self {
+            Week => Some(7 * MS_PER_DAY),
+            Day => Some(MS_PER_DAY),
+            Hour => Some(3_600_000),
+            Minute => Some(60_000),
+            Second => Some(1000),
+            Millisecond => Some(1),
+            _ => None,
+        }
+    }
+
+    /// A calendar unit's length in months, or `None` for the rest.
+    fn months(self) -> Option<i64> {
+        use DatePart::*;
+        match self {
+            Millennium => Some(12_000),
+            Century => Some(1200),
+            Decade => Some(120),
+            Year => Some(12),
+            Quarter => Some(3),
+            Month => Some(1),
+            _ => None,
+        }
+    }
+}
+
+/// The value of one component of `dt`, on its wall clock. Centuries and millennia count from
+/// year 1, so 2000 is in the 20th century and 2001 in the 21st.
+pub fn part(dt: DateTime, p: DatePart) -> i64 {
+    use DatePart::*;
+    let ((y, m, d), ms) = dt.civil();
+    let days = dt.local().div_euclid(MS_PER_DAY);
+    let doy = days - days_from_civil(y, 1, 1) + 1;
+    // 1970-01-01 was a Thursday.
+    let dow = (days + 4).rem_euclid(7);
+    let iso_dow = if dow == 0 { 7 } else { dow };
+    // An ISO week belongs to the year its Thursday falls in.
+    let thursday = days - iso_dow + 4;
+    let (iso_year, _, _) = civil_from_days(thursday);
+    match p {
+        Millennium => (y - 1).div_euclid(1000) + 1,
+        Century => (y - 1).div_euclid(100) + 1,
+        Decade => y.div_euclid(10),
+        Year => y,
+        Quarter => (m - 1) / 3 + 1,
+        Month => m,
+        Week => (doy - 1) / 7 + 1,
+        Day => d,
+        Hour => ms / 3_600_000,
+        Minute => ms / 60_000 % 60,
+        Second => ms / 1000 % 60,
+        Millisecond => ms % 1000,
+        DayOfYear => doy,
+        DayOfWeek => dow,
+        IsoYear => iso_year,
+        IsoWeek => (thursday - days_from_civil(iso_year, 1, 1)) / 7 + 1,
+        IsoDayOfWeek => iso_dow,
+        Timezone => dt.offset,
+        TimezoneHour => dt.offset / 3600,
+        TimezoneMinute => dt.offset % 3600 / 60,
+    }
+}
+
+/// `dt` moved by `n` of `unit`. A calendar unit moves the wall clock's month, keeping the day
+/// but for one past the new month's end, which becomes its last day; a fixed unit moves the
+/// instant. `None` for a part that is not a unit, or a result outside the years 0000 to 9999.
+pub fn add(dt: DateTime, n: i64, unit: DatePart) -> Option<DateTime> {
+    if let Some(len) = unit.millis() {
+        return dt.at_local(dt.local().checked_add(n.checked_mul(len)?)?);
+    }
+    let ((y, m, d), ms) = dt.civil();
+    let month = (y * 12 + m - 1).checked_add(n.checked_mul(unit.months()?)?)?;
+    let (y, m) = (month.div_euclid(12), month.rem_euclid(12) + 1);
+    if !(0..=9999).contains(&y) {
+        return None;
+    }
+    let d = d.min(days_in_month(y, m));
+    dt.at_local(days_from_civil(y, m, d) * MS_PER_DAY + ms)
+}
+
+/// Which `unit`-long period of the calendar a wall-clock time falls in, numbered so that
+/// consecutive periods differ by one. Weeks start on Monday.
+fn period(local: i64, unit: DatePart) -> Option<i64> {
+    use DatePart::*;
+    let days = local.div_euclid(MS_PER_DAY);
+    let (y, m, _) = civil_from_days(days);
+    Some(match unit {
+        Millennium => (y - 1).div_euclid(1000),
+        Century => (y - 1).div_euclid(100),
+        Decade => y.div_euclid(10),
+        Year => y,
+        Quarter => y * 4 + (m - 1) / 3,
+        Month => y * 12 + m - 1,
+        // The Monday on or before the epoch, 1969-12-29, begins week 0.
+        Week => (days + 3).div_euclid(7),
+        _ => local.div_euclid(unit.millis()?),
+    })
+}
+
+/// How many `unit` boundaries lie between `b` and `a`: positive if `a` is the later, and
+/// counted on `a`'s wall clock for both, so `diff` of 23:59 and 00:01 the next day is one day.
+/// `None` for a part that is not a unit.
+pub fn diff(a: DateTime, b: DateTime, unit: DatePart) -> Option<i64> {
+    let b = b.at_offset(a.offset);
+    Some(period(a.local(), unit)? - period(b.local(), unit)?)
+}
+
+/// The start of the `unit`-long period `dt` falls in, on its wall clock. `None` for a part that
+/// is not a unit, or a start before the year 0000 (the first century's is year -99).
+pub fn trunc(dt: DateTime, unit: DatePart) -> Option<DateTime> {
+    use DatePart::*;
+    let local = dt.local();
+    let i = period(local, unit)?;
+    let (y, m) = match unit {
+        Millennium => (i * 1000 + 1, 1),
+        Century => (i * 100 + 1, 1),
+        Decade => (i * 10, 1),
+        Year => (i, 1),
+        Quarter => (i.div_euclid(4), i.rem_euclid(4) * 3 + 1),
+        Month => (i.div_euclid(12), i.rem_euclid(12) + 1),
+        Week => return dt.at_local((i * 7 - 3) * MS_PER_DAY),
+        _ => return dt.at_local(local - local.rem_euclid(unit.millis()?)),
+    };
+    if y < 0 {
+        return None;
+    }
+    dt.at_local(days_from_civil(y, m, 1) * MS_PER_DAY)
 }
 
 #[cfg(test)]
@@ -153,6 +808,161 @@ mod tests {
         );
     }
 
+    fn dt(s: &str) -> DateTime {
+        parse(s).unwrap()
+    }
+    fn fmt(dt: Option<DateTime>) -> String {
+        format(dt.unwrap()).unwrap()
+    }
+
+    #[test]
+    fn civil_round_trips() {
+        for days in (-719_528..2_932_897).step_by(997) {
+            let (y, m, d) = civil_from_days(days);
+            assert_eq!(days_from_civil(y, m, d), days);
+        }
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+    }
+
+    #[test]
+    fn parses_to_exact_millis() {
+        assert_eq!(dt("2020-01-01T00:00:00.25Z").millis, 1_577_836_800_250);
+        // Digits past the millisecond are cut, not rounded.
+        assert_eq!(dt("1970-01-01T00:00:00.0019").millis, 1);
+        assert_eq!(dt("1970-01-01T00:00:00.00000000000000000000009").millis, 0);
+        let east = dt("2020-01-01T01:00:00+01:00");
+        assert_eq!((east.millis, east.offset), (1_577_836_800_000, 3600));
+        assert_eq!(parse("2020-01-01T00:00+24:00"), None);
+        assert_eq!(parse("2020-01-01T00:00+01:60"), None);
+        assert_eq!(parse("10000-01-01"), None);
+        assert_eq!(parse("99999999999999999"), None);
+        assert_eq!(parse_epoch("99999999999999999"), None);
+        assert_eq!(DateTime::from_millis(MAX_MILLIS + 1), None);
+    }
+
+    #[test]
+    fn formats_in_its_own_offset() {
+        assert_eq!(fmt(parse("2020-01-01")), "2020-01-01T00:00:00Z");
+        assert_eq!(
+            fmt(parse("2020-01-01T09:05:07.5-05:30")),
+            "2020-01-01T09:05:07.500-05:30"
+        );
+        assert_eq!(
+            fmt(DateTime::from_millis(MIN_MILLIS)),
+            "0000-01-01T00:00:00Z"
+        );
+        // The instant is in range; the wall clock a day east of it is not.
+        assert_eq!(format(dt("9999-12-31T23:00:00Z").at_offset(7200)), None);
+        assert_eq!(parse_offset("UTC"), Some(0));
+        assert_eq!(parse_offset("-05:30"), Some(-19_800));
+        assert_eq!(parse_offset("+0530"), Some(19_800));
+        assert_eq!(parse_offset("+5"), None);
+        assert_eq!(parse_offset("05:00"), None);
+        assert_eq!(parse_offset("+24:00"), None);
+    }
+
+    #[test]
+    fn parts() {
+        use DatePart::*;
+        // A Sunday, in ISO week 53 of 2020.
+        let d = dt("2021-01-03T17:45:30.125+02:00");
+        let got: Vec<i64> = [
+            Millennium,
+            Century,
+            Decade,
+            Year,
+            Quarter,
+            Month,
+            Week,
+            Day,
+            Hour,
+            Minute,
+            Second,
+            Millisecond,
+            DayOfYear,
+            DayOfWeek,
+            IsoYear,
+            IsoWeek,
+            IsoDayOfWeek,
+            Timezone,
+            TimezoneHour,
+            TimezoneMinute,
+        ]
+        .iter()
+        .map(|&p| part(d, p))
+        .collect();
+        assert_eq!(
+            got,
+            [3, 21, 202, 2021, 1, 1, 1, 3, 17, 45, 30, 125, 3, 0, 2020, 53, 7, 7200, 2, 0]
+        );
+        assert_eq!(part(dt("2000-06-01"), Century), 20);
+        assert_eq!(part(dt("2020-12-31"), DayOfYear), 366);
+        assert_eq!(part(dt("2020-12-31"), Week), 53);
+        assert_eq!(part(dt("2024-12-30"), IsoYear), 2025);
+        assert_eq!(part(dt("2024-12-30"), IsoWeek), 1);
+        assert_eq!(part(dt("2020-01-01T00:00-01:30"), TimezoneMinute), -30);
+        assert_eq!(DatePart::from_name("DOW"), Some(DayOfWeek));
+        assert_eq!(DatePart::from_name("fortnight"), None);
+    }
+
+    #[test]
+    fn adds_calendar_and_fixed_units() {
+        use DatePart::*;
+        let d = dt("2020-01-31T12:00:00Z");
+        assert_eq!(fmt(add(d, 1, Month)), "2020-02-29T12:00:00Z");
+        assert_eq!(fmt(add(d, 13, Month)), "2021-02-28T12:00:00Z");
+        assert_eq!(fmt(add(d, -1, Quarter)), "2019-10-31T12:00:00Z");
+        assert_eq!(fmt(add(d, 1, Day)), "2020-02-01T12:00:00Z");
+        assert_eq!(fmt(add(d, -36, Hour)), "2020-01-30T00:00:00Z");
+        assert_eq!(fmt(add(d, 1500, Millisecond)), "2020-01-31T12:00:01.500Z");
+        // The month moves on the wall clock: the last day of January at +02:00.
+        let east = dt("2020-01-31T01:00:00+02:00");
+        assert_eq!(fmt(add(east, 1, Month)), "2020-02-29T01:00:00+02:00");
+        assert_eq!(add(d, 8000, Year), None);
+        assert_eq!(add(d, i64::MAX, Day), None);
+        assert_eq!(add(d, 1, DayOfWeek), None);
+    }
+
+    #[test]
+    fn diffs_count_boundaries() {
+        use DatePart::*;
+        let a = dt("2020-01-02T00:01:00Z");
+        let b = dt("2020-01-01T23:59:00Z");
+        assert_eq!(diff(a, b, Day), Some(1));
+        assert_eq!(diff(b, a, Day), Some(-1));
+        assert_eq!(diff(a, b, Hour), Some(1));
+        assert_eq!(diff(a, b, Minute), Some(2));
+        assert_eq!(diff(a, b, Month), Some(0));
+        assert_eq!(diff(dt("2021-01-01"), dt("2020-12-31"), Year), Some(1));
+        assert_eq!(diff(dt("2020-04-01"), dt("2020-03-31"), Quarter), Some(1));
+        // 2020-01-06 is a Monday: a week boundary.
+        assert_eq!(diff(dt("2020-01-06"), dt("2020-01-05"), Week), Some(1));
+        assert_eq!(diff(dt("2020-01-05"), dt("2019-12-30"), Week), Some(0));
+        assert_eq!(diff(dt("2001-01-01"), dt("2000-12-31"), Century), Some(1));
+        // Both are read on the first's wall clock, +01:00, where 23:00Z is the next day.
+        let c = dt("2020-01-02T00:30:00+01:00");
+        assert_eq!(diff(c, dt("2020-01-01T23:00:00Z"), Day), Some(0));
+        assert_eq!(diff(c, dt("2020-01-01T22:00:00Z"), Day), Some(1));
+        assert_eq!(diff(a, b, Timezone), None);
+    }
+
+    #[test]
+    fn truncates_on_its_wall_clock() {
+        use DatePart::*;
+        let d = dt("2021-05-19T17:45:30.125+05:30");
+        assert_eq!(fmt(trunc(d, Millennium)), "2001-01-01T00:00:00+05:30");
+        assert_eq!(fmt(trunc(d, Decade)), "2020-01-01T00:00:00+05:30");
+        assert_eq!(fmt(trunc(d, Quarter)), "2021-04-01T00:00:00+05:30");
+        assert_eq!(fmt(trunc(d, Week)), "2021-05-17T00:00:00+05:30");
+        assert_eq!(fmt(trunc(d, Day)), "2021-05-19T00:00:00+05:30");
+        assert_eq!(fmt(trunc(d, Hour)), "2021-05-19T17:00:00+05:30");
+        assert_eq!(fmt(trunc(d, Second)), "2021-05-19T17:45:30+05:30");
+        assert_eq!(trunc(dt("0050-01-01"), Century), Some(dt("0001-01-01")));
+        assert_eq!(trunc(dt("0000-06-01"), Century), None);
+        assert_eq!(trunc(d, Timezone), None);
+    }
+
     #[test]
     fn rejects_invalid() {
         assert_eq!(parse_epoch(""), None);
@@ -162,4 +972,136 @@ mod tests {
         assert_eq!(parse_epoch("2020-01-01T25:00:00"), None); // hour out of range
         assert_eq!(parse_epoch("2020-01-01xyz"), None); // trailing garbage
     }
+
+    #[test]
+    fn rejects_out_of_range_components() {
+        for bad in [
+            "2020-00-01",
+            "2020-01-00",
+            "2020-01-40",
+            "2020-02-30",
+            "2021-02-29", // not a leap year
+            "2100-02-29", // nor is a century not divisible by 400
+            "2020-04-31",
+            "2020-01-01T24:00:00",
+            "2020-01-01T23:60:00",
+            "2020-01-01T23:59:60", // no leap seconds
+            "2020-01-01T00:00:00+24:00",
+            "2020-01-01T00:00:00+01:60",
+        ] {
+            assert_eq!(parse_epoch(bad), None, "{bad}");
+            assert_eq!(parse(bad), None, "{bad}");
+        }
+        assert!(parse_epoch("2000-02-29").is_some());
+        assert!(parse_epoch("2020-01-01T23:59:59+23:59").is_some());
+    }
+
+    #[test]
+    fn rejects_wrong_digit_counts() {
+        for bad in [
+            "20-01-01",
+            "20200-01-01",
+            "2020-1-01",
+            "2020-01-1",
+            "2020-01-01T1:00",
+            "2020-01-01T01:0",
+            "2020-01-01T01:00:0",
+            "2020-01-01T01:00+1",
+            "2020-01-01T01:00+01:0",
+        ] {
+            assert_eq!(parse_epoch(bad), None, "{bad}");
+        }
+        // `±HHMM` is a timezone too, not 530 hours.
+        assert_eq!(
+            parse_epoch("2020-01-01T05:30:00+0530"),
+            Some(1_577_836_800.0)
+        );
+    }
+
+    #[test]
+    fn formats() {
+        let at = |s: &str, f: &str| parse_epoch_format(s, f);
+        // The common log format, RFC 2822, and epoch strings.
+        assert_eq!(
+            at("02/Jan/2006:15:04:05 -0700", "%d/%b/%Y:%H:%M:%S %z"),
+            parse_epoch("2006-01-02T15:04:05-07:00")
+        );
+        assert_eq!(
+            at("Mon, 2 Jan 2006 15:04:05 GMT", "%a, %d %b %Y %T %Z"),
+            parse_epoch("2006-01-02T15:04:05Z")
+        );
+        assert_eq!(at("1136214245000", "%Q"), Some(1_136_214_245.0));
+        assert_eq!(at("-1500", "%Q"), Some(-1.5));
+        assert_eq!(at("1136214245", "%s"), Some(1_136_214_245.0));
+        // Names in either length and any case; twelve-hour clocks; two-digit years.
+        assert_eq!(
+            at(
+                "SUNDAY january 1 17 3:04:05.25 pm",
+                "%A %B %e %y %I:%M:%S.%f %p"
+            ),
+            parse_epoch("2017-01-01T15:04:05.25")
+        );
+        assert_eq!(at("12:00 AM", "%I:%M %p"), Some(0.0));
+        assert_eq!(at("68", "%y"), parse_epoch("2068"));
+        assert_eq!(at("69", "%y"), parse_epoch("1969"));
+        assert_eq!(at("2020-03-04 100%", "%F 100%%"), parse_epoch("2020-03-04"));
+        // Whitespace in the format matches any run of it.
+        assert_eq!(at("2020-03-04", "%Y-%m-%d  "), parse_epoch("2020-03-04"));
+        assert_eq!(at("2020 - 03-04", "%Y - %m-%d"), parse_epoch("2020-03-04"));
+        assert_eq!(at("2020- 03-04", "%Y-%m-%d"), None);
+        for (s, f) in [
+            ("Tue, 2 Jan 2006", "%a, %d %b %Y"), // the wrong weekday
+            ("2020-02-30", "%F"),                // out of range
+            ("13:00 PM", "%I:%M %p"),            // a twelve-hour clock goes to 12
+            ("1:00", "%I:%M"),                   // … and needs AM or PM
+            ("1:00 PM", "%H:%M %p"),             // which a 24-hour clock does not take
+            ("2020-01-01", "%F %H"),             // too short
+            ("2020-01-01x", "%F"),               // too long
+            ("2020", "%q"),                      // an unknown directive
+            ("5 2020", "%s %Y"),                 // an epoch alongside a component
+            ("2020 EST", "%Y %Z"),               // no zone database
+        ] {
+            assert_eq!(at(s, f), None, "{s:?} as {f:?}");
+        }
+        // With nothing to read, every component takes its default.
+        assert_eq!(at("", ""), Some(0.0));
+    }
+
+    #[test]
+    fn formats_a_layout() {
+        let d = dt("2021-01-03T17:45:30.125+02:00");
+        let lay = |l: &str| format_layout(d, l);
+        assert_eq!(
+            lay("%F %T.%f %z").as_deref(),
+            Some("2021-01-03 17:45:30.125 +0200")
+        );
+        assert_eq!(
+            lay("%a %A %b %B %e %y %I:%M %p").as_deref(),
+            Some("Sun Sunday Jan January  3 21 05:45 PM")
+        );
+        assert_eq!(
+            lay("%s %Q 100%%").as_deref(),
+            Some("1609688730 1609688730125 100%")
+        );
+        assert_eq!(
+            format_layout(d.at_offset(0), "%H %Z").as_deref(),
+            Some("15 UTC")
+        );
+        assert_eq!(lay("%q"), None);
+        assert_eq!(lay("%"), None);
+        // What it writes, the same layout reads back.
+        for l in [
+            "%F %T.%f %z",
+            "%d/%b/%Y:%H:%M:%S.%f %z",
+            "%A %B %e %Y %I:%M:%S.%f %p %z",
+            "%Q",
+        ] {
+            let written = lay(l).unwrap();
+            assert_eq!(
+                parse_epoch_format(&written, l),
+                Some(1_609_688_730.125),
+                "{l}"
+            );
+        }
+    }
 }
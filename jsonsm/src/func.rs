@@ -6,18 +6,234 @@
 //!
 //! Numeric functions operate in `f64` and return a [`FastVal::Float`] (a deliberate
 //! simplification over gojsonsm's per-type integer handling; the result still compares
-//! exactly against integer constants via the numeric collation). A function given the
-//! wrong arity or a non-numeric argument, or an unknown name, returns
+//! exactly against integer constants via the numeric collation). The exceptions are
+//! `mathAdd`, `mathSubract`, `mathMultiply`, `mathModulo`, `mathAbs` and `mathNegate`: given
+//! integer arguments they compute exactly, with checked arithmetic, and return a
+//! [`FastVal::Int`] or [`FastVal::Uint`] — so `id + 1` is right at the magnitudes where the
+//! collation is careful to be. They fall back to `f64` for a float argument or when the result
+//! does not fit either integer type. A function given a non-numeric argument returns
 //! [`FastVal::Missing`] — so a comparison against it takes the collation's missing result
-//! rather than a spurious ordering. Division/modulo by zero likewise yields `Missing`.
+//! rather than a spurious ordering. Division/modulo by zero likewise yields `Missing`. An
+//! unknown name or a wrong argument count ([`arity`], [`paired`]) fails the compile
+//! ([`crate::registry::resolve`]); [`apply`] handed one anyway returns `Missing` too.
+//!
+//! String functions (`strLower`, `strSubstr`, …) take their arguments decoded — escapes
+//! resolved through [`FastStr::to_decoded_bytes`] — and return an owned [`FastStr`], a
+//! [`FastVal::Int`] or a [`FastVal::Bool`]. Positions and lengths count codepoints, not bytes.
+//! A string that does not decode as UTF-8 is a wrong-type argument, and yields `Missing`.
+//!
+//! `type` names its argument's [`ValueType`] as N1QL spells it, and `isString`, `isNumber`,
+//! `isBoolean`, `isArray` and `isObject` test for one. Like most functions, they return
+//! `Missing` for a missing argument rather than an answer about it.
+//!
+//! `ifMissing`, `ifNull` and `ifMissingOrNull` are the exception: they take a missing
+//! argument as an input ([`sees_missing`]) and return the first of their arguments that is not
+//! missing, not null, or neither, or `Null` when there is none. The compiler defers an op over
+//! one to the after-node, so it runs even when the field it reads is absent.
+//!
+//! Array functions (`arrayLength`, `arrayContains`, `arraySum`, `arrayAvg`, `arrayMin`,
+//! `arrayMax`) walk a [`FastVal::Array`]'s raw bytes with the crate's tokenizer, and compare
+//! elements under the [`Collation`] passed to [`apply`], so `arrayContains` agrees with `=`.
+//! Any argument other than an array gives `Missing`.
+//!
+//! Object functions (`objectLength`, `objectNames`, `objectValues`, `objectPairs`) read a
+//! [`FastVal::Object`]'s members the same way. The three lists are arrays the function builds,
+//! sorted by name, so they come back as a [`FastVal::OwnedArray`]; `objectPairs` lists
+//! `{"name": …, "val": …}` objects. A loop over one of the lists does not build it: the
+//! compiler turns it into a loop over the object's members in the document.
+//!
+//! Conversions (`toNumber`, `toString`, `toBoolean`) cross types on request, since the
+//! collation never does. `toNumber` reads a string as the tokenizer reads a number in a
+//! document, so `"42"` becomes the same value a document's `42` is; `toString` writes a number
+//! as canonical JSON text. A conversion that fails gives `Missing`, as does a null argument.
+//!
+//! `date` (N1QL's `DATE()`) reads an ISO-8601 date, or one laid out as an optional format says,
+//! to epoch seconds, so dates compare as numbers. The other date functions (`datePart`,
+//! `dateAdd`, `dateDiff`, `dateTrunc`, `strToMillis`) take a date either as a string `date`
+//! would read or as a number of epoch milliseconds, and do their arithmetic exactly in
+//! milliseconds through [`crate::date`]. One that returns a date returns it in the form it was
+//! given: a string in the string's own offset, or an [`FastVal::Int`] of milliseconds.
+//! `millisToStr` and `millisToUtc` write epoch milliseconds as a date string in UTC, and
+//! `millisToTz` at the offset its second argument names; each lays it out as an optional last
+//! argument says, in the directives `date` reads a format in.
+//!
+//! `now`, `nowMillis` and `nowStr` read the current time, which [`apply`] is handed rather
+//! than fetching: a matcher reads its [`Clock`](crate::clock::Clock) once per document and
+//! passes the instant to every call, so the calls in one match agree and a test can pin them.
+//! `now` gives epoch seconds, as `date` does; `nowMillis` an [`FastVal::Int`] of milliseconds;
+//! `nowStr` the instant as a UTC string. With no time given they return `Missing`.
+//!
+//! Bit functions (`bitAnd`, `bitOr`, `bitXor`, `bitNot`, `bitShift`, `bitTest`) work on an
+//! integer's exact value in two's complement, and return a [`FastVal::Int`] or
+//! [`FastVal::Uint`] as the arithmetic above does. A float argument gives `Missing`, even an
+//! integral one, as does a result neither integer type holds; an unrotated `bitShift` is
+//! exact like the arithmetic, where N1QL's drops the bits it shifts out.
+//!
+//! Geo functions (`geoDistance`, `geoWithinBBox`, `geoWithinPolygon`) take points as
+//! consecutive latitude and longitude arguments, in degrees: `geoDistance(lat, lon, lat0,
+//! lon0)` is the haversine distance in metres on a sphere of the Earth's mean radius,
+//! `geoWithinBBox(lat, lon, south, west, north, east)` tests a box given by its south-west and
+//! north-east corners, and `geoWithinPolygon(lat, lon, lat1, lon1, …)` a polygon of three
+//! vertices or more, by the even-odd rule on the plane of latitude and longitude. A box whose
+//! west edge is east of its east edge spans the antimeridian; a polygon cannot. A point on
+//! the edge of either is within it. A latitude outside ±90 or a longitude outside ±180 gives
+//! `Missing`. A document's `lat` and `lon` are two fields, so a call on them is deferred to
+//! its scope's after-node like any comparison over more than one field, and they may arrive
+//! in either order.
+//!
+//! IP functions (`ipInCidr`, `ipInRange`, `ipVersion`, `ipNormalize`) read addresses and
+//! networks from decoded strings through [`crate::ip`]; an address or network that does not
+//! parse gives `Missing`. `ipInCidr(a, net)` and `ipInRange(a, low, high)` are predicates, false
+//! for an address of the other family. A constant network is parsed once, when the expression
+//! is compiled, by [`prepare`]; the matcher then applies the call with [`apply_prepared`]. A
+//! constant address or network that does not parse fails the compile there.
+//!
+//! `semverCmp(a, b)` compares two semantic versions by SemVer 2.0.0 precedence through
+//! [`crate::semver`], giving a [`FastVal::Int`] of `-1`, `0` or `1`, and the predicate
+//! `semverLess(a, b)` is whether `a` comes first. A string that is not a version gives
+//! `Missing`, so a comparison against either is UNKNOWN.
+//!
+//! A function that returns a boolean is a *predicate* ([`is_predicate`]): the compiler
+//! accepts a call to one where a condition is expected, as `f(…) = TRUE`.
 //!
 //! Function names are gojsonsm's internal identifiers (e.g. `mathSubract`,
-//! `mathMultiply`); front-ends map their surface syntax (`-`, `*`, `ABS`, …) to these.
+//! `mathMultiply`); front-ends map their surface syntax (`-`, `*`, `ABS`, …) to these. The
+//! string functions have no gojsonsm counterpart and take `str`-prefixed names in the same
+//! style.
+
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::ops::RangeInclusive;
+
+use crate::collation::Collation;
+use crate::date;
+use crate::ip;
+use crate::registry::CallError;
+use crate::semver;
+use crate::tokenizer::{JsonTokenizer, Token, TokenType, Tokenizer};
+use crate::value::{FastStr, FastVal, Num, ValueType};
+
+/// Whether the named function returns a boolean, and so may stand alone as a condition.
+pub fn is_predicate(name: &str) -> bool {
+    matches!(
+        name,
+        "strContains"
+            | "strStartsWith"
+            | "strEndsWith"
+            | "isString"
+            | "isNumber"
+            | "isBoolean"
+            | "isArray"
+            | "isObject"
+            | "arrayContains"
+            | "toBoolean"
+            | "bitTest"
+            | "geoWithinBBox"
+            | "geoWithinPolygon"
+            | "ipInCidr"
+            | "ipInRange"
+            | "semverLess"
+    )
+}
+
+/// Whether the named function takes a missing argument as an input rather than returning
+/// `Missing` for it.
+pub fn sees_missing(name: &str) -> bool {
+    matches!(name, "ifMissing" | "ifNull" | "ifMissingOrNull")
+}
+
+/// How many arguments the named built-in takes, or `None` when there is no built-in by that
+/// name. An open-ended range runs to `usize::MAX`. The compiler rejects a call outside it, so
+/// [`apply`] only sees a wrong count from a caller that skipped the check.
+pub fn arity(name: &str) -> Option<RangeInclusive<usize>> {
+    const MANY: usize = usize::MAX;
+    Some(match name {
+        "now" | "nowMillis" | "nowStr" | "mathPi" | "mathE" => 0..=0,
+        "date" | "millisToStr" | "millisToUtc" | "strTrim" => 1..=2,
+        "strToMillis" | "mathAbs" | "mathAcos" | "mathAsin" | "mathAtan" | "mathCeil"
+        | "mathCos" | "mathDegrees" | "mathExp" | "mathFloor" | "mathLn" | "mathLog"
+        | "mathRadians" | "mathRound" | "mathSin" | "mathSqrt" | "mathTan" | "mathNegate"
+        | "bitNot" | "ipVersion" | "ipNormalize" | "strLower" | "strUpper" | "strLength"
+        | "type" | "isString" | "isNumber" | "isBoolean" | "isArray" | "isObject" | "toNumber"
+        | "toString" | "toBoolean" | "arrayLength" | "arraySum" | "arrayAvg" | "arrayMin"
+        | "arrayMax" | "objectLength" | "objectNames" | "objectValues" | "objectPairs" => 1..=1,
+        "mathAtan2" | "mathPow" | "mathAdd" | "mathSubract" | "mathMultiply" | "mathDivide"
+        | "mathModulo" | "ipInCidr" | "semverCmp" | "semverLess" | "strContains"
+        | "strStartsWith" | "strEndsWith" | "arrayContains" => 2..=2,
+        "datePart" | "dateTrunc" | "millisToTz" | "bitShift" | "bitTest" | "strSubstr" => 2..=3,
+        "dateAdd" | "dateDiff" | "ipInRange" => 3..=3,
+        "bitAnd" | "bitOr" | "bitXor" | "ifMissing" | "ifNull" | "ifMissingOrNull" => 2..=MANY,
+        "geoDistance" => 4..=4,
+        "geoWithinBBox" => 6..=6,
+        // Three vertices or more.
+        "geoWithinPolygon" => 8..=MANY,
+        _ => return None,
+    })
+}
+
+/// Whether the named built-in takes its arguments in pairs — a polygon's latitudes and
+/// longitudes — so that the compiler rejects an odd count as it does one out of [`arity`].
+pub fn paired(name: &str) -> bool {
+    name == "geoWithinPolygon"
+}
+
+/// A call's constant arguments, parsed ahead of matching by [`prepare`].
+#[derive(Debug, Clone)]
+pub enum Prepared {
+    /// `ipInCidr` against a constant network.
+    IpInCidr(ip::Cidr),
+}
+
+/// Parse the constant arguments of a call to the named function once, at compile time, where
+/// the function has a use for that: `consts[i]` is argument `i`'s value when it is a constant.
+/// `Ok(None)` when there is nothing to prepare. A constant address or network that does not
+/// parse is an error rather than a call that is missing for every document, as a malformed
+/// constant pattern is.
+pub fn prepare(name: &str, consts: &[Option<&FastVal<'_>>]) -> Result<Option<Prepared>, CallError> {
+    let malformed = |arg: usize| CallError::Malformed {
+        name: name.to_owned(),
+        arg,
+    };
+    let addrs = match name {
+        "ipInCidr" => consts.get(..1).unwrap_or_default(),
+        "ipInRange" => consts,
+        _ => &[],
+    };
+    if let Some(arg) = addrs
+        .iter()
+        .position(|c| c.is_some_and(|c| ip_arg(c).is_none()))
+    {
+        return Err(malformed(arg));
+    }
+    match (name, consts) {
+        ("ipInCidr", [_, Some(net)]) => match text(net).and_then(|net| ip::Cidr::parse(&net)) {
+            Some(net) => Ok(Some(Prepared::IpInCidr(net))),
+            None => Err(malformed(1)),
+        },
+        _ => Ok(None),
+    }
+}
 
-use crate::value::FastVal;
+/// Apply a [`prepare`]d call to all of its arguments, the constant ones included, with the
+/// result [`apply`] would give.
+pub fn apply_prepared<'a>(prepared: &Prepared, args: &[FastVal<'a>]) -> FastVal<'a> {
+    match (prepared, args) {
+        (Prepared::IpInCidr(net), [addr, _]) => {
+            ip_arg(addr).map_or(FastVal::Missing, |a| FastVal::Bool(net.contains(a)))
+        }
+        _ => FastVal::Missing,
+    }
+}
 
-/// Apply the named function to `args`, returning the result value.
-pub fn apply(name: &str, args: &[FastVal<'_>]) -> FastVal<'static> {
+/// Apply the named function to `args`, returning the result value. `collation` orders and
+/// equates array elements, as it does the operands of a comparison; `now` is the current time
+/// in epoch milliseconds, if the matcher has a clock.
+pub fn apply<'a, C: Collation + ?Sized>(
+    name: &str,
+    args: &[FastVal<'a>],
+    collation: &C,
+    now: Option<i64>,
+) -> FastVal<'a> {
     // Numeric accessor for argument `i`.
     let num = |i: usize| -> Option<f64> { args.get(i)?.as_num().map(|n| n.as_f64()) };
     let one = |f: fn(f64) -> f64| -> FastVal<'static> {
@@ -26,34 +242,153 @@ pub fn apply(name: &str, args: &[FastVal<'_>]) -> FastVal<'static> {
             _ => FastVal::Missing,
         }
     };
+    let is_type = |t: ValueType| -> FastVal<'static> {
+        match args {
+            [FastVal::Missing] => FastVal::Missing,
+            [v] => FastVal::Bool(v.value_type() == t),
+            _ => FastVal::Missing,
+        }
+    };
+    let str_one = |f: fn(&str) -> FastVal<'static>| -> FastVal<'static> {
+        match args {
+            [a] => text(a).map_or(FastVal::Missing, |a| f(&a)),
+            _ => FastVal::Missing,
+        }
+    };
+    let str_two = |f: fn(&str, &str) -> FastVal<'static>| -> FastVal<'static> {
+        match args {
+            [a, b] => match (text(a), text(b)) {
+                (Some(a), Some(b)) => f(&a, &b),
+                _ => FastVal::Missing,
+            },
+            _ => FastVal::Missing,
+        }
+    };
+    // The elements of the one array argument, or `None` for any other arguments.
+    let array_one = || match args {
+        [a] => elements(a),
+        _ => None,
+    };
     let two = |f: fn(f64, f64) -> f64| -> FastVal<'static> {
         match (num(0), num(1)) {
             (Some(a), Some(b)) if args.len() == 2 => finite(f(a, b)),
             _ => FastVal::Missing,
         }
     };
+    // Exact forms of `one` and `two` over integer arguments, `None` where `f64` has to answer
+    // instead: a float argument, or a result (`None` from `f`) that overflows.
+    let int_one = |f: fn(i128) -> Option<i128>| -> Option<FastVal<'static>> {
+        match args {
+            [a] => int_result(f(int_arg(a)?)?),
+            _ => None,
+        }
+    };
+    let int_two = |f: fn(i128, i128) -> Option<i128>| -> Option<FastVal<'static>> {
+        match args {
+            [a, b] => int_result(f(int_arg(a)?, int_arg(b)?)?),
+            _ => None,
+        }
+    };
 
     match name {
-        // DATE(str): parse an ISO-8601 date to epoch seconds so date comparisons are
-        // numeric. A non-string or unparseable argument yields Missing.
+        // DATE(str[, format]): parse an ISO-8601 date, or one laid out as `format` says, to
+        // epoch seconds so date comparisons are numeric. A non-string or unparseable argument
+        // yields Missing.
         "date" => match args {
-            [arg] => arg
-                .as_str()
-                .and_then(|s| {
-                    std::str::from_utf8(&s.to_decoded_bytes())
-                        .ok()
-                        .and_then(crate::date::parse_epoch)
-                })
-                .map_or(FastVal::Missing, FastVal::Float),
+            [arg] => text(arg).and_then(|s| date::parse_epoch(&s)),
+            [arg, format] => text(arg)
+                .zip(text(format))
+                .and_then(|(s, format)| date::parse_epoch_format(&s, &format)),
+            _ => None,
+        }
+        .map_or(FastVal::Missing, FastVal::Float),
+
+        // The instant the matcher's clock read for this document.
+        "now" => match (args, now) {
+            ([], Some(ms)) => FastVal::Float(ms as f64 / 1000.0),
+            _ => FastVal::Missing,
+        },
+        "nowMillis" => match (args, now) {
+            ([], Some(ms)) => FastVal::Int(ms),
+            _ => FastVal::Missing,
+        },
+        "nowStr" => match (args, now) {
+            ([], Some(ms)) => date::DateTime::from_millis(ms)
+                .and_then(date::format)
+                .map_or(FastVal::Missing, owned),
             _ => FastVal::Missing,
         },
 
+        // Dates as strings or epoch milliseconds; a part or unit is named by a string, and an
+        // optional last argument re-reads the date at a fixed offset.
+        "strToMillis" => match args {
+            [s] => text(s)
+                .and_then(|s| date::parse(&s))
+                .map_or(FastVal::Missing, |dt| FastVal::Int(dt.millis)),
+            _ => FastVal::Missing,
+        },
+        // Milliseconds written as a date, in UTC or at an offset, and laid out as an optional
+        // format says. With no time-zone database the local zone `MILLIS_TO_STR` writes in is
+        // UTC too.
+        "millisToStr" | "millisToUtc" => match args {
+            [ms] => date_millis(ms).and_then(date::format),
+            [ms, layout] => date_millis(ms)
+                .zip(text(layout))
+                .and_then(|(dt, layout)| date::format_layout(dt, &layout)),
+            _ => None,
+        }
+        .map_or(FastVal::Missing, owned),
+        "millisToTz" => match args {
+            [ms, tz] => date_millis(ms)
+                .zip(offset_arg(tz))
+                .and_then(|(dt, tz)| date::format(dt.at_offset(tz))),
+            [ms, tz, layout] => date_millis(ms)
+                .zip(offset_arg(tz))
+                .zip(text(layout))
+                .and_then(|((dt, tz), layout)| date::format_layout(dt.at_offset(tz), &layout)),
+            _ => None,
+        }
+        .map_or(FastVal::Missing, owned),
+        "datePart" => match args {
+            [d, p] => date_arg(d, None).zip(date_part(p)),
+            [d, p, tz] => date_arg(d, Some(tz)).zip(date_part(p)),
+            _ => None,
+        }
+        .map_or(FastVal::Missing, |((dt, _), p)| {
+            FastVal::Int(date::part(dt, p))
+        }),
+        "dateAdd" => match args {
+            [d, n, unit] => date_arg(d, None)
+                .zip(int_arg(n).and_then(|n| i64::try_from(n).ok()))
+                .zip(date_part(unit))
+                .and_then(|(((dt, as_text), n), unit)| {
+                    date_result(date::add(dt, n, unit)?, as_text)
+                }),
+            _ => None,
+        }
+        .unwrap_or(FastVal::Missing),
+        "dateDiff" => match args {
+            [a, b, unit] => date_arg(a, None)
+                .zip(date_arg(b, None))
+                .zip(date_part(unit))
+                .and_then(|(((a, _), (b, _)), unit)| date::diff(a, b, unit))
+                .map_or(FastVal::Missing, FastVal::Int),
+            _ => FastVal::Missing,
+        },
+        "dateTrunc" => match args {
+            [d, unit] => date_arg(d, None).zip(date_part(unit)),
+            [d, unit, tz] => date_arg(d, Some(tz)).zip(date_part(unit)),
+            _ => None,
+        }
+        .and_then(|((dt, as_text), unit)| date_result(date::trunc(dt, unit)?, as_text))
+        .unwrap_or(FastVal::Missing),
+
         // Zero-argument constants.
         "mathPi" if args.is_empty() => FastVal::Float(std::f64::consts::PI),
         "mathE" if args.is_empty() => FastVal::Float(std::f64::consts::E),
 
         // One-argument.
-        "mathAbs" => one(f64::abs),
+        "mathAbs" => int_one(|x| Some(x.abs())).unwrap_or_else(|| one(f64::abs)),
         "mathAcos" => one(f64::acos),
         "mathAsin" => one(f64::asin),
         "mathAtan" => one(f64::atan),
@@ -69,21 +404,676 @@ pub fn apply(name: &str, args: &[FastVal<'_>]) -> FastVal<'static> {
         "mathSin" => one(f64::sin),
         "mathSqrt" => one(f64::sqrt),
         "mathTan" => one(f64::tan),
-        "mathNegate" => one(|x| -x),
+        "mathNegate" => int_one(|x| Some(-x)).unwrap_or_else(|| one(|x| -x)),
 
         // Two-argument.
         "mathAtan2" => two(f64::atan2),
         "mathPow" => two(f64::powf),
-        "mathAdd" => two(|a, b| a + b),
-        "mathSubract" => two(|a, b| a - b),
-        "mathMultiply" => two(|a, b| a * b),
+        "mathAdd" => int_two(i128::checked_add).unwrap_or_else(|| two(|a, b| a + b)),
+        "mathSubract" => int_two(i128::checked_sub).unwrap_or_else(|| two(|a, b| a - b)),
+        "mathMultiply" => int_two(i128::checked_mul).unwrap_or_else(|| two(|a, b| a * b)),
         "mathDivide" => two(|a, b| if b == 0.0 { f64::NAN } else { a / b }),
-        "mathModulo" => two(|a, b| if b == 0.0 { f64::NAN } else { a % b }),
+        // By zero, the integer form declines and the `f64` one answers `Missing`.
+        "mathModulo" => int_two(i128::checked_rem)
+            .unwrap_or_else(|| two(|a, b| if b == 0.0 { f64::NAN } else { a % b })),
+
+        // Bits. `bitAnd`, `bitOr` and `bitXor` take two arguments or more.
+        "bitAnd" => bit_fold(args, |a, b| a & b).unwrap_or(FastVal::Missing),
+        "bitOr" => bit_fold(args, |a, b| a | b).unwrap_or(FastVal::Missing),
+        "bitXor" => bit_fold(args, |a, b| a ^ b).unwrap_or(FastVal::Missing),
+        "bitNot" => int_one(|x| Some(!x)).unwrap_or(FastVal::Missing),
+        "bitShift" => match args {
+            [x, n] => bit_shift(x, n, false),
+            [x, n, FastVal::Bool(rotate)] => bit_shift(x, n, *rotate),
+            _ => None,
+        }
+        .unwrap_or(FastVal::Missing),
+        "bitTest" => match args {
+            [x, positions] => bit_test(x, positions, false),
+            [x, positions, FastVal::Bool(all)] => bit_test(x, positions, *all),
+            _ => None,
+        }
+        .unwrap_or(FastVal::Missing),
+
+        // Geo. Every argument is a coordinate, a point's latitude then its longitude.
+        "geoDistance" => match geo_points(args).as_deref() {
+            Some(&[a, b]) => finite(haversine(a, b)),
+            _ => FastVal::Missing,
+        },
+        "geoWithinBBox" => match geo_points(args).as_deref() {
+            Some(&[p, sw, ne]) => within_bbox(p, sw, ne).map_or(FastVal::Missing, FastVal::Bool),
+            _ => FastVal::Missing,
+        },
+        "geoWithinPolygon" => match geo_points(args).as_deref() {
+            Some([p, vertices @ ..]) => {
+                within_polygon(*p, vertices).map_or(FastVal::Missing, FastVal::Bool)
+            }
+            _ => FastVal::Missing,
+        },
+
+        // IP addresses, as text.
+        "ipInCidr" => match args {
+            [addr, net] => ip_arg(addr)
+                .zip(text(net).and_then(|net| ip::Cidr::parse(&net)))
+                .map_or(FastVal::Missing, |(a, net)| FastVal::Bool(net.contains(a))),
+            _ => FastVal::Missing,
+        },
+        "ipInRange" => match args {
+            [addr, low, high] => match (ip_arg(addr), ip_arg(low), ip_arg(high)) {
+                (Some(a), Some(low), Some(high)) if low.v4 == high.v4 => {
+                    FastVal::Bool(low <= a && a <= high)
+                }
+                _ => FastVal::Missing,
+            },
+            _ => FastVal::Missing,
+        },
+        "ipVersion" => match args {
+            [addr] => {
+                ip_arg(addr).map_or(FastVal::Missing, |a| FastVal::Int(if a.v4 { 4 } else { 6 }))
+            }
+            _ => FastVal::Missing,
+        },
+        "ipNormalize" => match args {
+            [addr] => ip_arg(addr).map_or(FastVal::Missing, |a| owned(a.to_ip().to_string())),
+            _ => FastVal::Missing,
+        },
+
+        // Semantic versions, as text.
+        "semverCmp" => match args {
+            [a, b] => semver_cmp(a, b).map_or(FastVal::Missing, |o| FastVal::Int(o as i64)),
+            _ => FastVal::Missing,
+        },
+        "semverLess" => match args {
+            [a, b] => semver_cmp(a, b).map_or(FastVal::Missing, |o| FastVal::Bool(o.is_lt())),
+            _ => FastVal::Missing,
+        },
+
+        // Strings.
+        "strLower" => str_one(|s| owned(s.to_lowercase())),
+        "strUpper" => str_one(|s| owned(s.to_uppercase())),
+        "strLength" => str_one(|s| FastVal::Int(s.chars().count() as i64)),
+        "strContains" => str_two(|s, sub| FastVal::Bool(s.contains(sub))),
+        "strStartsWith" => str_two(|s, pre| FastVal::Bool(s.starts_with(pre))),
+        "strEndsWith" => str_two(|s, suf| FastVal::Bool(s.ends_with(suf))),
+        // TRIM(s) strips whitespace; TRIM(s, chars) strips any of the characters in `chars`.
+        "strTrim" => match args {
+            [_] => str_one(|s| owned(s.trim().to_owned())),
+            [_, _] => str_two(|s, chars| owned(s.trim_matches(|c| chars.contains(c)).to_owned())),
+            _ => FastVal::Missing,
+        },
+        "strSubstr" => match args {
+            [s, pos] => text(s).map_or(FastVal::Missing, |s| substr(&s, pos, None)),
+            [s, pos, len] => text(s).map_or(FastVal::Missing, |s| substr(&s, pos, Some(len))),
+            _ => FastVal::Missing,
+        },
+
+        // Types.
+        "type" => match args {
+            [FastVal::Missing] => FastVal::Missing,
+            [v] => owned(type_name(v.value_type()).to_owned()),
+            _ => FastVal::Missing,
+        },
+        "isString" => is_type(ValueType::String),
+        "isNumber" => is_type(ValueType::Number),
+        "isBoolean" => is_type(ValueType::Boolean),
+        "isArray" => is_type(ValueType::Array),
+        "isObject" => is_type(ValueType::Object),
+
+        // Type conversions.
+        "toNumber" => match args {
+            [v] => to_number(v),
+            _ => FastVal::Missing,
+        },
+        "toString" => match args {
+            [v @ FastVal::Str(_)] => v.clone(),
+            [FastVal::Bool(b)] => owned(b.to_string()),
+            [v] => v
+                .as_num()
+                .and_then(number_text)
+                .map_or(FastVal::Missing, owned),
+            _ => FastVal::Missing,
+        },
+        "toBoolean" => match args {
+            [v] => truthy(v).map_or(FastVal::Missing, FastVal::Bool),
+            _ => FastVal::Missing,
+        },
+
+        // Missing and null handling: the first argument that is not missing, not null, or
+        // neither. N1QL requires two arguments at least.
+        "ifMissing" => first_where(args, |v| !matches!(v, FastVal::Missing)),
+        "ifNull" => first_where(args, |v| !matches!(v, FastVal::Null)),
+        "ifMissingOrNull" => first_where(args, |v| !matches!(v, FastVal::Missing | FastVal::Null)),
+
+        // Arrays. Null elements take no part in the aggregates, nor do non-numbers in the sum
+        // and average; with nothing left, the sum is 0 and the others null.
+        "arrayLength" => array_one().map_or(FastVal::Missing, |e| FastVal::Int(e.len() as i64)),
+        "arrayContains" => match args {
+            [_, FastVal::Missing] => FastVal::Missing,
+            [a, v] => elements(a).map_or(FastVal::Missing, |e| {
+                FastVal::Bool(e.iter().any(|x| collation.equals(x, v)))
+            }),
+            _ => FastVal::Missing,
+        },
+        "arraySum" => array_one().map_or(FastVal::Missing, |e| {
+            finite(
+                e.iter()
+                    .filter_map(|x| x.as_num())
+                    .map(|n| n.as_f64())
+                    .sum(),
+            )
+        }),
+        "arrayAvg" => array_one().map_or(FastVal::Missing, |e| {
+            let nums: Vec<f64> = e
+                .iter()
+                .filter_map(|x| x.as_num())
+                .map(|n| n.as_f64())
+                .collect();
+            if nums.is_empty() {
+                FastVal::Null
+            } else {
+                finite(nums.iter().sum::<f64>() / nums.len() as f64)
+            }
+        }),
+        // The chosen element may come from an array built by another function, which is gone
+        // once this returns, so it is copied out.
+        "arrayMin" => array_one().map_or(FastVal::Missing, |e| {
+            extreme(e, collation, Ordering::Less).into_owned()
+        }),
+        "arrayMax" => array_one().map_or(FastVal::Missing, |e| {
+            extreme(e, collation, Ordering::Greater).into_owned()
+        }),
+
+        // Objects. The lists are sorted by name, as N1QL sorts them.
+        "objectLength" => match args {
+            [o] => members(o).map_or(FastVal::Missing, |m| FastVal::Int(m.len() as i64)),
+            _ => FastVal::Missing,
+        },
+        "objectNames" | "objectValues" | "objectPairs" => match args {
+            [o] => object_list(
+                o,
+                match name {
+                    "objectNames" => |m, out| out.extend_from_slice(m.1),
+                    "objectValues" => |m, out| out.extend_from_slice(m.2),
+                    _ => |m, out| {
+                        out.extend_from_slice(b"{\"name\":");
+                        out.extend_from_slice(m.1);
+                        out.extend_from_slice(b",\"val\":");
+                        out.extend_from_slice(m.2);
+                        out.push(b'}');
+                    },
+                },
+            ),
+            _ => FastVal::Missing,
+        },
 
         _ => FastVal::Missing,
     }
 }
 
+/// The elements of an array value, borrowed from it: scalars in their lazy form, containers as
+/// their raw bytes. `None` for any other value, or for array bytes that do not tokenize (a
+/// container the matcher skipped over is only checked for balance, so its contents may not).
+fn elements<'v>(v: &'v FastVal<'_>) -> Option<Vec<FastVal<'v>>> {
+    let bytes: &[u8] = match v {
+        FastVal::Array(b) => b,
+        FastVal::OwnedArray(b) => b,
+        _ => return None,
+    };
+    let mut tokens = JsonTokenizer::new(bytes);
+    if tokens.step().ok()?.token_type != TokenType::ArrayStart {
+        return None;
+    }
+    let mut out = Vec::new();
+    loop {
+        let tok = tokens.step().ok()?;
+        match tok.token_type {
+            TokenType::ArrayEnd => return Some(out),
+            TokenType::ListDelim => {}
+            _ => out.push(read_value(&mut tokens, tok)?.0),
+        }
+    }
+}
+
+/// An object member: its key, the key's raw token (quotes included), and the value's raw bytes.
+type Member<'b> = (FastStr<'b>, &'b [u8], &'b [u8]);
+
+/// The members of an object value, borrowed from it, in document order. `None` as for
+/// [`elements`].
+fn members<'v>(v: &'v FastVal<'_>) -> Option<Vec<Member<'v>>> {
+    let bytes: &[u8] = match v {
+        FastVal::Object(b) => b,
+        FastVal::OwnedObject(b) => b,
+        _ => return None,
+    };
+    let mut tokens = JsonTokenizer::new(bytes);
+    if tokens.step().ok()?.token_type != TokenType::ObjectStart {
+        return None;
+    }
+    let mut out = Vec::new();
+    loop {
+        let key = tokens.step().ok()?;
+        let escaped = match key.token_type {
+            TokenType::ObjectEnd => return Some(out),
+            TokenType::ListDelim => continue,
+            TokenType::String => false,
+            TokenType::EscString => true,
+            _ => return None,
+        };
+        if tokens.step().ok()?.token_type != TokenType::ObjectKeyDelim {
+            return None;
+        }
+        let value = tokens.step().ok()?;
+        let (_, raw) = read_value(&mut tokens, value)?;
+        let content = &key.value[1..key.value.len() - 1];
+        out.push((FastStr::from_content(content, escaped), key.value, raw));
+    }
+}
+
+/// An object's members as `objectNames`, `objectValues` or `objectPairs` lists them, sorted by
+/// name: an array of the names, of the values, or of `{"name": …, "val": …}` objects.
+fn object_list(v: &FastVal<'_>, item: fn(&Member<'_>, &mut Vec<u8>)) -> FastVal<'static> {
+    let Some(mut members) = members(v) else {
+        return FastVal::Missing;
+    };
+    members.sort_by(|a, b| a.0.cmp_str(&b.0));
+    let mut out = vec![b'['];
+    for (i, m) in members.iter().enumerate() {
+        if i > 0 {
+            out.push(b',');
+        }
+        item(m, &mut out);
+    }
+    out.push(b']');
+    FastVal::OwnedArray(out.into_boxed_slice())
+}
+
+/// The value `tok` opens, read through to its end: a scalar in its lazy form or a container as
+/// its raw bytes, together with the bytes it spans either way.
+fn read_value<'b>(
+    tokens: &mut JsonTokenizer<'b>,
+    tok: Token<'b>,
+) -> Option<(FastVal<'b>, &'b [u8])> {
+    if tok.token_type.is_literal() {
+        return Some((FastVal::from_scalar_token(tok)?, tok.value));
+    }
+    let open = tok.token_type;
+    if !matches!(open, TokenType::ArrayStart | TokenType::ObjectStart) {
+        return None;
+    }
+    let start = tokens.position() - 1;
+    let mut depth = 1usize;
+    while depth > 0 {
+        match tokens.step().ok()?.token_type {
+            TokenType::ArrayStart | TokenType::ObjectStart => depth += 1,
+            TokenType::ArrayEnd | TokenType::ObjectEnd => depth -= 1,
+            TokenType::End => return None,
+            _ => {}
+        }
+    }
+    let raw = &tokens.input()[start..tokens.position()];
+    Some(if open == TokenType::ArrayStart {
+        (FastVal::Array(raw), raw)
+    } else {
+        (FastVal::Object(raw), raw)
+    })
+}
+
+/// The non-null element that `collation` orders `want` of all the others, or `Null` if none.
+fn extreme<'a, C: Collation + ?Sized>(
+    elements: Vec<FastVal<'a>>,
+    collation: &C,
+    want: Ordering,
+) -> FastVal<'a> {
+    elements
+        .into_iter()
+        .filter(|v| !matches!(v, FastVal::Null))
+        .reduce(|best, v| {
+            if collation.compare(&v, &best).ordering == want {
+                v
+            } else {
+                best
+            }
+        })
+        .unwrap_or(FastVal::Null)
+}
+
+/// The first of at least two `args` that `keep` accepts, or `Null` if none is.
+fn first_where<'a>(args: &[FastVal<'a>], keep: fn(&FastVal<'a>) -> bool) -> FastVal<'a> {
+    if args.len() < 2 {
+        return FastVal::Missing;
+    }
+    args.iter()
+        .find(|v| keep(v))
+        .cloned()
+        .unwrap_or(FastVal::Null)
+}
+
+/// `TONUMBER`: a number as itself, a boolean as 0 or 1, and a string holding exactly one JSON
+/// number — no `+`, no surrounding space — as the number a document spelling it would hold.
+fn to_number(v: &FastVal<'_>) -> FastVal<'static> {
+    let n = match v {
+        FastVal::Bool(b) => Some(Num::I(i64::from(*b))),
+        FastVal::Str(s) => {
+            let bytes = s.to_decoded_bytes();
+            let mut tokens = JsonTokenizer::new(&bytes);
+            match tokens.step() {
+                Ok(tok)
+                    if tok.value.len() == bytes.len()
+                        && matches!(tok.token_type, TokenType::Integer | TokenType::Number) =>
+                {
+                    FastVal::from_scalar_token(tok).and_then(|n| n.as_num())
+                }
+                _ => None,
+            }
+        }
+        _ => v.as_num(),
+    };
+    match n {
+        Some(Num::I(i)) => FastVal::Int(i),
+        Some(Num::U(u)) => FastVal::Uint(u),
+        Some(Num::F(x)) => finite(x),
+        None => FastVal::Missing,
+    }
+}
+
+/// The canonical JSON text of a number: an integer exactly, and a float as ECMAScript's
+/// `Number.prototype.toString` writes it, the form RFC 8785 canonicalizes to — the fewest digits
+/// that read back as the same `f64`, no fraction on an integral value, and an exponent only from
+/// `1e21` up or below `1e-6`. `None` for a number that is not finite.
+fn number_text(n: Num) -> Option<String> {
+    let x = match n {
+        Num::I(i) => return Some(i.to_string()),
+        Num::U(u) => return Some(u.to_string()),
+        Num::F(x) if !x.is_finite() => return None,
+        Num::F(0.0) => return Some("0".to_owned()),
+        Num::F(x) => x,
+    };
+    // `{:e}` writes those fewest digits as `d.ddde<exp>`.
+    let sci = format!("{:e}", x.abs());
+    let (mantissa, exp) = sci.split_once('e')?;
+    let digits = mantissa.replace('.', "");
+    let k = digits.len() as i32;
+    // The decimal point falls after `n` digits.
+    let n = exp.parse::<i32>().ok()? + 1;
+    let zeros = |count: i32| "0".repeat(count as usize);
+    let sign = if x < 0.0 { "-" } else { "" };
+    Some(if k <= n && n <= 21 {
+        format!("{sign}{digits}{}", zeros(n - k))
+    } else if 0 < n && n <= 21 {
+        let (int, frac) = digits.split_at(n as usize);
+        format!("{sign}{int}.{frac}")
+    } else if -6 < n && n <= 0 {
+        format!("{sign}0.{}{digits}", zeros(-n))
+    } else {
+        let (first, rest) = digits.split_at(1);
+        let dot = if rest.is_empty() { "" } else { "." };
+        format!("{sign}{first}{dot}{rest}e{:+}", n - 1)
+    })
+}
+
+/// `TOBOOLEAN`, by N1QL's rules: `false`, zero, and an empty string, array or object are false,
+/// and any other value true. `None` for null and missing.
+fn truthy(v: &FastVal<'_>) -> Option<bool> {
+    match v {
+        FastVal::Missing | FastVal::Null => None,
+        FastVal::Bool(b) => Some(*b),
+        FastVal::Str(s) => Some(!s.to_decoded_bytes().is_empty()),
+        FastVal::Array(_) | FastVal::OwnedArray(_) => elements(v).map(|e| !e.is_empty()),
+        FastVal::Object(_) | FastVal::OwnedObject(_) => members(v).map(|m| !m.is_empty()),
+        _ => Some(v.as_num()?.as_f64() != 0.0),
+    }
+}
+
+/// The N1QL name of a type, as `TYPE()` returns it.
+fn type_name(t: ValueType) -> &'static str {
+    match t {
+        ValueType::Missing => "missing",
+        ValueType::Null => "null",
+        ValueType::Boolean => "boolean",
+        ValueType::Number => "number",
+        ValueType::String => "string",
+        ValueType::Array => "array",
+        ValueType::Object => "object",
+    }
+}
+
+/// The decoded text of a string argument; `None` for a non-string or one that is not UTF-8.
+fn text<'v>(v: &'v FastVal<'_>) -> Option<Cow<'v, str>> {
+    match v.as_str()?.to_decoded_bytes() {
+        Cow::Borrowed(b) => std::str::from_utf8(b).ok().map(Cow::Borrowed),
+        Cow::Owned(b) => String::from_utf8(b).ok().map(Cow::Owned),
+    }
+}
+
+#[inline]
+fn owned(s: String) -> FastVal<'static> {
+    FastVal::Str(FastStr::Owned(s))
+}
+
+/// `SUBSTR(s, pos[, len])`: `len` codepoints of `s` from codepoint `pos`, or the rest of `s`
+/// without `len`. Positions are 0-based, and a negative `pos` counts back from the end. A
+/// position outside `s`, a negative length, or a non-integer for either is `Missing`; a length
+/// running past the end stops there.
+fn substr(s: &str, pos: &FastVal<'_>, len: Option<&FastVal<'_>>) -> FastVal<'static> {
+    let int = |v: &FastVal<'_>| {
+        let x = v.as_num()?.as_f64();
+        (x.fract() == 0.0 && x.abs() <= i64::MAX as f64).then_some(x as i64)
+    };
+    let count = s.chars().count() as i64;
+    let Some(pos) = int(pos) else {
+        return FastVal::Missing;
+    };
+    let start = if pos < 0 { count + pos } else { pos };
+    if !(0..=count).contains(&start) {
+        return FastVal::Missing;
+    }
+    let take = match len.map(int) {
+        None => usize::MAX,
+        Some(Some(n)) if n >= 0 => n as usize,
+        Some(_) => return FastVal::Missing,
+    };
+    owned(s.chars().skip(start as usize).take(take).collect())
+}
+
+/// An integer argument, widened so that any `i64` or `u64` fits with room for the result of one
+/// operation on two of them — short of a product, which `checked_mul` catches. `None` for
+/// anything else, a float included even when integral.
+fn int_arg(v: &FastVal<'_>) -> Option<i128> {
+    match v.as_num()? {
+        Num::I(i) => Some(i.into()),
+        Num::U(u) => Some(u.into()),
+        Num::F(_) => None,
+    }
+}
+
+/// An exact integer result as the narrowest type that holds it, as a document's integer would
+/// parse; `None` if neither `i64` nor `u64` does.
+fn int_result(x: i128) -> Option<FastVal<'static>> {
+    match i64::try_from(x) {
+        Ok(i) => Some(FastVal::Int(i)),
+        Err(_) => u64::try_from(x).ok().map(FastVal::Uint),
+    }
+}
+
+/// A date argument, and whether it was given as a string: a string [`date::parse`] reads, or a
+/// number of epoch milliseconds, a fraction of one dropped. `tz`, if given, must name a fixed
+/// offset to read the date at.
+fn date_arg(v: &FastVal<'_>, tz: Option<&FastVal<'_>>) -> Option<(date::DateTime, bool)> {
+    let (dt, as_text) = match v {
+        FastVal::Str(_) => (date::parse(&text(v)?)?, true),
+        _ => (date_millis(v)?, false),
+    };
+    match tz {
+        Some(tz) => Some((dt.at_offset(offset_arg(tz)?), as_text)),
+        None => Some((dt, as_text)),
+    }
+}
+
+/// A number of epoch milliseconds as a date in UTC.
+fn date_millis(v: &FastVal<'_>) -> Option<date::DateTime> {
+    let millis = match v.as_num()? {
+        Num::I(i) => i,
+        Num::U(u) => i64::try_from(u).ok()?,
+        Num::F(f) => {
+            let f = f.floor();
+            // Past the range of dates, just so that it stays inside an `i64`.
+            if f.abs() > 1e18 {
+                return None;
+            }
+            f as i64
+        }
+    };
+    date::DateTime::from_millis(millis)
+}
+
+/// A fixed offset from UTC, named as [`date::parse_offset`] reads one, in seconds east of it.
+fn offset_arg(v: &FastVal<'_>) -> Option<i64> {
+    date::parse_offset(&text(v)?)
+}
+
+fn date_part(v: &FastVal<'_>) -> Option<date::DatePart> {
+    date::DatePart::from_name(&text(v)?)
+}
+
+/// A computed date in the form its argument came in.
+fn date_result(dt: date::DateTime, as_text: bool) -> Option<FastVal<'static>> {
+    if as_text {
+        date::format(dt).map(owned)
+    } else {
+        Some(FastVal::Int(dt.millis))
+    }
+}
+
+/// `f` folded over two or more integer arguments, left to right.
+fn bit_fold(args: &[FastVal<'_>], f: fn(i128, i128) -> i128) -> Option<FastVal<'static>> {
+    let (first, rest) = args.split_first().filter(|(_, rest)| !rest.is_empty())?;
+    int_result(
+        rest.iter()
+            .try_fold(int_arg(first)?, |acc, x| Some(f(acc, int_arg(x)?)))?,
+    )
+}
+
+/// `x` shifted left by `n` bits, or right by `-n`. A right shift floors, and a left shift is
+/// exact, so `None` if the result fits neither integer type. With `rotate`, the bits turn
+/// within the 64-bit word `x` is held in instead, by `n` modulo 64.
+fn bit_shift(x: &FastVal<'_>, n: &FastVal<'_>, rotate: bool) -> Option<FastVal<'static>> {
+    let n = int_arg(n)?;
+    if rotate {
+        let by = n.rem_euclid(64) as u32;
+        return match x.as_num()? {
+            Num::I(i) => Some(FastVal::Int((i as u64).rotate_left(by) as i64)),
+            Num::U(u) => Some(FastVal::Uint(u.rotate_left(by))),
+            Num::F(_) => None,
+        };
+    }
+    let x = int_arg(x)?;
+    int_result(if n >= 0 {
+        // Past 65 bits only zero fits, as it does at 65.
+        x.checked_mul(1 << n.min(65))?
+    } else {
+        x >> (-n).min(127)
+    })
+}
+
+/// Whether `x` has any of the bits at `positions` set — or with `all`, every one. Positions
+/// count from 1 at the least significant bit, and are one integer or an array of them; one
+/// outside the 64-bit word gives `None`.
+fn bit_test(x: &FastVal<'_>, positions: &FastVal<'_>, all: bool) -> Option<FastVal<'static>> {
+    let x = int_arg(x)?;
+    let set = |p: &FastVal<'_>| -> Option<bool> {
+        let p = int_arg(p).filter(|p| (1..=64).contains(p))?;
+        Some((x >> (p - 1)) & 1 == 1)
+    };
+    let bits = match elements(positions) {
+        Some(ps) => ps.iter().map(set).collect::<Option<Vec<bool>>>()?,
+        None => vec![set(positions)?],
+    };
+    Some(FastVal::Bool(if all {
+        bits.iter().all(|&b| b)
+    } else {
+        bits.iter().any(|&b| b)
+    }))
+}
+
+/// A string argument read as an IP address.
+fn ip_arg(v: &FastVal<'_>) -> Option<ip::Addr> {
+    ip::Addr::parse(&text(v)?)
+}
+
+/// Two string arguments compared as semantic versions.
+fn semver_cmp(a: &FastVal<'_>, b: &FastVal<'_>) -> Option<Ordering> {
+    let (a, b) = (text(a)?, text(b)?);
+    Some(semver::Version::parse(&a)?.cmp(&semver::Version::parse(&b)?))
+}
+
+/// The Earth's mean radius in metres, the sphere `geoDistance` measures on.
+const EARTH_RADIUS: f64 = 6_371_008.8;
+
+/// `args` as (latitude, longitude) points in degrees, two arguments each. `None` for an odd
+/// number of arguments, a non-number, or a coordinate out of range.
+fn geo_points(args: &[FastVal<'_>]) -> Option<Vec<(f64, f64)>> {
+    if args.len() % 2 != 0 {
+        return None;
+    }
+    args.chunks_exact(2)
+        .map(|p| {
+            let (lat, lon) = (p[0].as_num()?.as_f64(), p[1].as_num()?.as_f64());
+            ((-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lon)).then_some((lat, lon))
+        })
+        .collect()
+}
+
+/// The great-circle distance in metres between two points, by the haversine formula.
+fn haversine((lat1, lon1): (f64, f64), (lat2, lon2): (f64, f64)) -> f64 {
+    let (phi1, phi2) = (lat1.to_radians(), lat2.to_radians());
+    let half_dphi = (phi2 - phi1) / 2.0;
+    let half_dlambda = (lon2 - lon1).to_radians() / 2.0;
+    let h = half_dphi.sin().powi(2) + phi1.cos() * phi2.cos() * half_dlambda.sin().powi(2);
+    // Rounding can push `h` a hair past 1 for antipodal points.
+    2.0 * EARTH_RADIUS * h.sqrt().min(1.0).asin()
+}
+
+/// Whether `p` lies in the box with corners `(south, west)` and `(north, east)`, edges
+/// included. A west edge east of the east edge spans the antimeridian; a south edge north of
+/// the north edge is no box, and gives `None`.
+fn within_bbox(
+    (lat, lon): (f64, f64),
+    (south, west): (f64, f64),
+    (north, east): (f64, f64),
+) -> Option<bool> {
+    if south > north {
+        return None;
+    }
+    let in_lon = if west <= east {
+        west <= lon && lon <= east
+    } else {
+        west <= lon || lon <= east
+    };
+    Some(south <= lat && lat <= north && in_lon)
+}
+
+/// Whether `p` lies in the polygon through `vertices`, closed back to the first, by the
+/// even-odd rule on the plane of latitude and longitude; a point on an edge is within. Fewer
+/// than three vertices is no polygon, and gives `None`.
+fn within_polygon((y, x): (f64, f64), vertices: &[(f64, f64)]) -> Option<bool> {
+    if vertices.len() < 3 {
+        return None;
+    }
+    let mut inside = false;
+    for (i, &(y1, x1)) in vertices.iter().enumerate() {
+        let (y2, x2) = vertices[(i + 1) % vertices.len()];
+        let on_line = (x2 - x1) * (y - y1) == (y2 - y1) * (x - x1);
+        if on_line && x1.min(x2) <= x && x <= x1.max(x2) && y1.min(y2) <= y && y <= y1.max(y2) {
+            return Some(true);
+        }
+        // A ray due east from `p` crosses this edge.
+        if (y1 > y) != (y2 > y) && x < x1 + (y - y1) * (x2 - x1) / (y2 - y1) {
+            inside = !inside;
+        }
+    }
+    Some(inside)
+}
+
 /// Wrap a computed float as a value, mapping non-finite results (NaN/∞ from domain errors
 /// or division by zero) to `Missing`.
 #[inline]
@@ -100,8 +1090,8 @@ mod tests {
     use super::*;
     use std::cmp::Ordering;
 
-    fn f(name: &str, args: &[FastVal<'_>]) -> FastVal<'static> {
-        apply(name, args)
+    fn f<'a>(name: &str, args: &[FastVal<'a>]) -> FastVal<'a> {
+        apply(name, args, &crate::collation::DefaultCollation, None)
     }
 
     fn approx(v: &FastVal<'_>, expected: f64) -> bool {
@@ -150,6 +1140,100 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn integer_arithmetic_is_exact() {
+        use FastVal::{Float, Int, Uint};
+        // 2^53 + 1 is not an f64; computed in f64 this would give 2^53.
+        let r = f("mathAdd", &[Int(9007199254740992), Int(1)]);
+        assert!(matches!(r, Int(9007199254740993)));
+        assert!(matches!(
+            f("mathAdd", &[Int(i64::MAX), Int(1)]),
+            Uint(9223372036854775808)
+        ));
+        assert!(matches!(
+            f("mathSubract", &[Uint(0), Uint(u64::MAX)]),
+            Float(x) if x == -(u64::MAX as f64)
+        ));
+        assert!(matches!(f("mathAdd", &[Uint(u64::MAX), Int(1)]), Float(_)));
+        assert!(matches!(
+            f("mathMultiply", &[Int(1 << 62), Int(3)]),
+            Uint(13835058055282163712)
+        ));
+        assert!(matches!(
+            f("mathMultiply", &[Int(1 << 62), Int(5)]),
+            Float(_)
+        ));
+        assert!(matches!(f("mathModulo", &[Int(-7), Int(3)]), Int(-1)));
+        assert!(matches!(
+            f("mathModulo", &[Int(7), Int(0)]),
+            FastVal::Missing
+        ));
+        assert!(matches!(
+            f("mathAbs", &[Int(i64::MIN)]),
+            Uint(9223372036854775808)
+        ));
+        assert!(matches!(
+            f("mathNegate", &[Uint(9223372036854775808)]),
+            Int(i64::MIN)
+        ));
+        assert!(matches!(f("mathNegate", &[Uint(u64::MAX)]), Float(_)));
+        // An integral float still computes in f64.
+        assert!(matches!(f("mathAdd", &[Float(2.0), Int(1)]), Float(x) if x == 3.0));
+        assert!(matches!(f("mathAbs", &[Int(1), Int(2)]), FastVal::Missing));
+    }
+
+    #[test]
+    fn bit_functions() {
+        use FastVal::{Bool, Float, Int, Missing, Uint};
+        assert!(matches!(f("bitAnd", &[Int(6), Int(3)]), Int(2)));
+        assert!(matches!(f("bitOr", &[Int(6), Int(3), Int(8)]), Int(15)));
+        assert!(matches!(f("bitXor", &[Int(6), Int(3)]), Int(5)));
+        assert!(matches!(
+            f("bitAnd", &[Int(-1), Uint(u64::MAX)]),
+            Uint(u64::MAX)
+        ));
+        assert!(matches!(f("bitAnd", &[Int(6)]), Missing));
+        assert!(matches!(f("bitNot", &[Int(0)]), Int(-1)));
+        // Two's complement past the i64 range holds in neither type.
+        assert!(matches!(f("bitNot", &[Uint(u64::MAX)]), Missing));
+        assert!(matches!(f("bitShift", &[Int(1), Int(3)]), Int(8)));
+        assert!(matches!(f("bitShift", &[Int(-7), Int(-1)]), Int(-4)));
+        assert!(matches!(
+            f("bitShift", &[Int(1), Int(63)]),
+            Uint(9223372036854775808)
+        ));
+        assert!(matches!(f("bitShift", &[Int(1), Int(64)]), Missing));
+        assert!(matches!(f("bitShift", &[Int(0), Int(200)]), Int(0)));
+        assert!(matches!(f("bitShift", &[Int(-1), Int(-200)]), Int(-1)));
+        assert!(matches!(
+            f("bitShift", &[Int(1), Int(-1), Bool(true)]),
+            Int(i64::MIN)
+        ));
+        assert!(matches!(
+            f("bitShift", &[Uint(1 << 63), Int(1), Bool(true)]),
+            Uint(1)
+        ));
+        assert!(matches!(f("bitTest", &[Int(5), Int(3)]), Bool(true)));
+        assert!(matches!(f("bitTest", &[Int(5), Int(2)]), Bool(false)));
+        assert!(matches!(f("bitTest", &[Int(-1), Int(64)]), Bool(true)));
+        assert!(matches!(f("bitTest", &[Int(5), Int(0)]), Missing));
+        assert!(matches!(f("bitTest", &[Int(5), Int(65)]), Missing));
+        let positions = FastVal::Array(b"[1,2]");
+        assert!(matches!(
+            f("bitTest", &[Int(5), positions.clone()]),
+            Bool(true)
+        ));
+        assert!(matches!(
+            f("bitTest", &[Int(5), positions, Bool(true)]),
+            Bool(false)
+        ));
+        // Floats are not bit patterns, integral or not.
+        assert!(matches!(f("bitAnd", &[Float(6.0), Int(3)]), Missing));
+        assert!(matches!(f("bitTest", &[Int(5), Float(1.0)]), Missing));
+        assert!(matches!(f("bitNot", &[FastVal::Null]), Missing));
+        assert!(is_predicate("bitTest") && !is_predicate("bitAnd"));
+    }
+
     #[test]
     fn zero_arg_constants() {
         assert!(approx(&f("mathPi", &[]), std::f64::consts::PI));
@@ -204,6 +1288,677 @@ mod tests {
             f("date", &[FastVal::Str(FastStr::Unescaped(b"nope"))]),
             FastVal::Missing
         ));
+        assert!(matches!(
+            f("date", &[FastVal::Str(FastStr::Unescaped(b"2020-02-30"))]),
+            FastVal::Missing
+        ));
+        // with a format
+        let s = |t: &'static str| FastVal::Str(FastStr::borrowed_str(t));
+        let d = f(
+            "date",
+            &[s("01/Jan/2020:01:00:00 +0100"), s("%d/%b/%Y:%T %z")],
+        );
+        assert!(approx(&d, 1_577_836_800.0));
+        let d = f("date", &[s("1577836800500"), s("%Q")]);
+        assert!(approx(&d, 1_577_836_800.5));
+        assert!(matches!(
+            f("date", &[s("2020-01-01"), s("%d/%m/%Y")]),
+            FastVal::Missing
+        ));
+        assert!(matches!(
+            f("date", &[s("2020"), FastVal::Int(4)]),
+            FastVal::Missing
+        ));
+    }
+
+    #[test]
+    fn date_functions() {
+        use FastVal::{Float, Int, Missing};
+        let s = |t: &'static str| FastVal::Str(FastStr::borrowed_str(t));
+        let text = |v: FastVal<'_>| match v {
+            FastVal::Str(FastStr::Owned(t)) => t,
+            other => panic!("expected an owned string, got {other:?}"),
+        };
+        let ts = s("2021-01-03T17:45:30.125+02:00");
+        let ms = Int(1_609_688_730_125);
+        assert!(matches!(
+            f("strToMillis", std::slice::from_ref(&ts)),
+            Int(1_609_688_730_125)
+        ));
+        assert_eq!(
+            text(f("millisToStr", std::slice::from_ref(&ms))),
+            "2021-01-03T15:45:30.125Z"
+        );
+        assert_eq!(
+            text(f("millisToTz", &[ms.clone(), s("+02:00")])),
+            "2021-01-03T17:45:30.125+02:00"
+        );
+        // A layout in `date`'s directives, in UTC but for `millisToTz`.
+        assert_eq!(
+            text(f("millisToStr", &[ms.clone(), s("%Y-%m-%d")])),
+            "2021-01-03"
+        );
+        assert_eq!(
+            text(f("millisToUtc", &[ms.clone(), s("%T %Z")])),
+            "15:45:30 UTC"
+        );
+        assert_eq!(
+            text(f("millisToTz", &[ms.clone(), s("-01:00"), s("%H:%M %z")])),
+            "14:45 -0100"
+        );
+        // A part is read on the string's own wall clock, or UTC for milliseconds, or at the
+        // offset the last argument names.
+        assert!(matches!(f("datePart", &[ts.clone(), s("hour")]), Int(17)));
+        assert!(matches!(f("datePart", &[ms.clone(), s("HOUR")]), Int(15)));
+        assert!(matches!(
+            f("datePart", &[ms.clone(), s("hour"), s("-01:00")]),
+            Int(14)
+        ));
+        assert!(matches!(
+            f("datePart", &[Float(1.5), s("millisecond")]),
+            Int(1)
+        ));
+        // A date comes back in the form it went in.
+        assert_eq!(
+            text(f("dateAdd", &[ts.clone(), Int(1), s("month")])),
+            "2021-02-03T17:45:30.125+02:00"
+        );
+        assert!(matches!(
+            f("dateAdd", &[ms.clone(), Int(-1), s("second")]),
+            Int(1_609_688_729_125)
+        ));
+        assert_eq!(
+            text(f("dateTrunc", &[ts.clone(), s("day")])),
+            "2021-01-03T00:00:00+02:00"
+        );
+        assert!(matches!(
+            f("dateTrunc", &[ms.clone(), s("day"), s("+02:00")]),
+            Int(1_609_624_800_000)
+        ));
+        assert!(matches!(
+            f("dateDiff", &[ts.clone(), s("2020-12-31"), s("day")]),
+            Int(3)
+        ));
+        assert!(matches!(
+            f("dateDiff", &[s("2020-12-31"), ms.clone(), s("year")]),
+            Int(-1)
+        ));
+        // Anything that is not a date, a part, a unit, an offset or an integer count.
+        for bad in [
+            f("strToMillis", std::slice::from_ref(&ms)),
+            f("datePart", &[s("nope"), s("hour")]),
+            f("datePart", &[ts.clone(), s("fortnight")]),
+            f("datePart", &[ts.clone(), s("hour"), s("Europe/Paris")]),
+            f("dateAdd", &[ts.clone(), Float(1.0), s("day")]),
+            f("dateAdd", &[ts.clone(), Int(1), s("dow")]),
+            f("dateAdd", &[ts.clone(), Int(10_000), s("year")]),
+            f("dateDiff", &[ts.clone(), Int(0), s("timezone")]),
+            f("dateTrunc", &[Int(i64::MAX), s("day")]),
+            f("millisToStr", &[s("2021-01-03")]),
+            f("millisToStr", &[FastVal::Uint(u64::MAX)]),
+            f("millisToUtc", &[ms.clone(), s("%Y %q")]),
+            f("millisToTz", &[ms.clone(), s("Europe/Paris")]),
+            f("millisToTz", &[ms.clone(), Int(0), s("%F")]),
+            f("datePart", &[FastVal::Null, s("hour")]),
+        ] {
+            assert!(matches!(bad, Missing), "{bad:?}");
+        }
+    }
+
+    #[test]
+    fn geo_functions() {
+        use FastVal::{Bool, Float, Int, Missing};
+        // Paris to London, about 343.9 km.
+        let paris = [Float(48.8566), Float(2.3522)];
+        let d = f(
+            "geoDistance",
+            &[paris.clone(), [Float(51.5074), Float(-0.1278)]].concat(),
+        );
+        assert!(matches!(d.as_num(), Some(n) if (n.as_f64() - 343_900.0).abs() < 500.0));
+        // A quarter of the equator, and the same point twice.
+        let d = f("geoDistance", &[Int(0), Int(0), Int(0), Int(90)]);
+        let quarter = EARTH_RADIUS * std::f64::consts::FRAC_PI_2;
+        assert!(matches!(d.as_num(), Some(n) if (n.as_f64() - quarter).abs() < 1e-3));
+        assert!(approx(
+            &f("geoDistance", &[paris.clone(), paris.clone()].concat()),
+            0.0
+        ));
+        // out of range, non-numeric, or the wrong arity
+        assert!(matches!(
+            f("geoDistance", &[Int(91), Int(0), Int(0), Int(0)]),
+            Missing
+        ));
+        assert!(matches!(
+            f("geoDistance", &[Int(0), Int(181), Int(0), Int(0)]),
+            Missing
+        ));
+        assert!(matches!(
+            f("geoDistance", &[Missing, Int(0), Int(0), Int(0)]),
+            Missing
+        ));
+        assert!(matches!(
+            f("geoDistance", &[Int(0), Int(0), Int(0)]),
+            Missing
+        ));
+
+        let bbox = |lat: i64, lon: i64, sw: (i64, i64), ne: (i64, i64)| {
+            let a = [lat, lon, sw.0, sw.1, ne.0, ne.1].map(Int);
+            f("geoWithinBBox", &a)
+        };
+        assert!(matches!(bbox(5, 5, (0, 0), (10, 10)), Bool(true)));
+        assert!(matches!(bbox(10, 0, (0, 0), (10, 10)), Bool(true)));
+        assert!(matches!(bbox(11, 5, (0, 0), (10, 10)), Bool(false)));
+        // across the antimeridian
+        assert!(matches!(bbox(0, 179, (-5, 170), (5, -170)), Bool(true)));
+        assert!(matches!(bbox(0, -175, (-5, 170), (5, -170)), Bool(true)));
+        assert!(matches!(bbox(0, 0, (-5, 170), (5, -170)), Bool(false)));
+        assert!(matches!(bbox(0, 0, (5, 0), (-5, 10)), Missing));
+
+        // A square with a notch cut into its east side.
+        let notched = [(0, 0), (0, 10), (4, 10), (5, 5), (6, 10), (10, 10), (10, 0)];
+        let poly = |lat: f64, lon: f64| {
+            let mut a = vec![Float(lat), Float(lon)];
+            a.extend(notched.iter().flat_map(|&(y, x)| [Int(y), Int(x)]));
+            f("geoWithinPolygon", &a)
+        };
+        assert!(matches!(poly(2.0, 2.0), Bool(true)));
+        assert!(matches!(poly(5.0, 8.0), Bool(false)));
+        assert!(matches!(poly(5.0, 4.0), Bool(true)));
+        assert!(matches!(poly(0.0, 5.0), Bool(true)));
+        assert!(matches!(poly(10.0, 10.0), Bool(true)));
+        assert!(matches!(poly(-1.0, 5.0), Bool(false)));
+        let two_vertices = [Int(1), Int(1), Int(0), Int(0), Int(2), Int(2)];
+        assert!(matches!(f("geoWithinPolygon", &two_vertices), Missing));
+        assert!(is_predicate("geoWithinPolygon") && !is_predicate("geoDistance"));
+    }
+
+    #[test]
+    fn ip_functions() {
+        use FastVal::{Bool, Int, Missing};
+        let s = |t: &'static str| FastVal::Str(FastStr::borrowed_str(t));
+        assert!(matches!(
+            f("ipInCidr", &[s("10.1.2.3"), s("10.0.0.0/8")]),
+            Bool(true)
+        ));
+        assert!(matches!(
+            f("ipInCidr", &[s("11.1.2.3"), s("10.0.0.0/8")]),
+            Bool(false)
+        ));
+        assert!(matches!(
+            f("ipInCidr", &[s("::1"), s("10.0.0.0/8")]),
+            Bool(false)
+        ));
+        assert!(matches!(
+            f("ipInCidr", &[s("::ffff:10.1.2.3"), s("10.0.0.0/8")]),
+            Bool(true)
+        ));
+        assert!(matches!(
+            f("ipInCidr", &[s("2001:db8::7"), s("2001:db8::/32")]),
+            Bool(true)
+        ));
+        // escaped text is decoded first: `10\u002e1.2.3`
+        let escaped = FastVal::Str(FastStr::Escaped(b"10\\u002e1.2.3"));
+        assert!(matches!(
+            f("ipInCidr", &[escaped, s("10.0.0.0/8")]),
+            Bool(true)
+        ));
+        // malformed addresses or networks, or the wrong types
+        assert!(matches!(
+            f("ipInCidr", &[s("10.1.2"), s("10.0.0.0/8")]),
+            Missing
+        ));
+        assert!(matches!(
+            f("ipInCidr", &[s("10.1.2.3"), s("10.0.0.0/40")]),
+            Missing
+        ));
+        assert!(matches!(
+            f("ipInCidr", &[Int(167_837_955), s("10.0.0.0/8")]),
+            Missing
+        ));
+
+        let range = |a, lo, hi| f("ipInRange", &[s(a), s(lo), s(hi)]);
+        assert!(matches!(
+            range("10.0.0.9", "10.0.0.1", "10.0.0.10"),
+            Bool(true)
+        ));
+        assert!(matches!(
+            range("10.0.0.10", "10.0.0.1", "10.0.0.10"),
+            Bool(true)
+        ));
+        assert!(matches!(
+            range("10.0.0.11", "10.0.0.1", "10.0.0.10"),
+            Bool(false)
+        ));
+        // numerically, where the text would order 10.0.0.9 after 10.0.0.10
+        assert!(matches!(
+            range("10.0.0.9", "10.0.0.10", "10.0.0.20"),
+            Bool(false)
+        ));
+        assert!(matches!(
+            range("fe80::5", "fe80::1", "fe80::ff"),
+            Bool(true)
+        ));
+        assert!(matches!(
+            range("10.0.0.5", "fe80::1", "fe80::ff"),
+            Bool(false)
+        ));
+        assert!(matches!(range("10.0.0.5", "10.0.0.1", "fe80::ff"), Missing));
+
+        assert!(matches!(f("ipVersion", &[s("192.0.2.1")]), Int(4)));
+        assert!(matches!(f("ipVersion", &[s("::ffff:192.0.2.1")]), Int(4)));
+        assert!(matches!(f("ipVersion", &[s("2001:db8::1")]), Int(6)));
+        assert!(matches!(f("ipVersion", &[s("192.0.2.01")]), Missing));
+        assert!(matches!(
+            f("ipNormalize", &[s("2001:DB8:0:0::0001")]),
+            FastVal::Str(FastStr::Owned(t)) if t == "2001:db8::1"
+        ));
+        assert!(is_predicate("ipInRange") && !is_predicate("ipVersion"));
+
+        // A prepared call answers as the unprepared one does.
+        let net = s("10.0.0.0/8");
+        let prepared = prepare("ipInCidr", &[None, Some(&net)])
+            .unwrap()
+            .expect("prepared");
+        for addr in ["10.1.2.3", "11.1.2.3", "::ffff:10.0.0.1", "nope"] {
+            let args = [s(addr), net.clone()];
+            assert_eq!(
+                format!("{:?}", apply_prepared(&prepared, &args)),
+                format!("{:?}", f("ipInCidr", &args))
+            );
+        }
+        assert!(matches!(prepare("ipInCidr", &[None, None]), Ok(None)));
+        assert!(matches!(prepare("strLower", &[Some(&net)]), Ok(None)));
+        // A constant that does not parse is reported, with its position.
+        let bad = |name: &str, consts: &[Option<&FastVal<'_>>]| match prepare(name, consts) {
+            Err(CallError::Malformed { arg, .. }) => Some(arg),
+            _ => None,
+        };
+        assert_eq!(bad("ipInCidr", &[None, Some(&s("10.0.0.0"))]), Some(1));
+        assert_eq!(bad("ipInCidr", &[None, Some(&Int(8))]), Some(1));
+        assert_eq!(bad("ipInCidr", &[Some(&s("10.0.0.256")), None]), Some(0));
+        let low = s("10.0.0.1");
+        assert_eq!(
+            bad("ipInRange", &[None, Some(&low), Some(&s("nope"))]),
+            Some(2)
+        );
+        assert_eq!(bad("ipInRange", &[None, Some(&low), None]), None);
+    }
+
+    #[test]
+    fn semver_functions() {
+        use FastVal::{Bool, Int, Missing};
+        let s = |t: &'static str| FastVal::Str(FastStr::borrowed_str(t));
+        assert!(matches!(
+            f("semverCmp", &[s("1.9.0"), s("1.10.0")]),
+            Int(-1)
+        ));
+        assert!(matches!(f("semverCmp", &[s("1.10.0"), s("1.9.0")]), Int(1)));
+        assert!(matches!(
+            f("semverCmp", &[s("1.0.0+a"), s("1.0.0+b")]),
+            Int(0)
+        ));
+        assert!(matches!(
+            f("semverCmp", &[s("1.0.0-rc.1"), s("1.0.0")]),
+            Int(-1)
+        ));
+        assert!(matches!(
+            f("semverLess", &[s("1.0.0-beta.2"), s("1.0.0-beta.11")]),
+            Bool(true)
+        ));
+        assert!(matches!(
+            f("semverLess", &[s("1.0.0"), s("1.0.0")]),
+            Bool(false)
+        ));
+        // escaped text is decoded first: `1\u002e10.0`
+        let escaped = FastVal::Str(FastStr::Escaped(b"1\\u002e10.0"));
+        assert!(matches!(f("semverCmp", &[escaped, s("1.9.0")]), Int(1)));
+        // not versions
+        assert!(matches!(
+            f("semverCmp", &[s("v1.0.0"), s("1.0.0")]),
+            Missing
+        ));
+        assert!(matches!(f("semverCmp", &[s("1.0.0"), s("1.0")]), Missing));
+        assert!(matches!(f("semverLess", &[Int(1), s("1.0.0")]), Missing));
+        assert!(matches!(f("semverCmp", &[s("1.0.0")]), Missing));
+        assert!(is_predicate("semverLess") && !is_predicate("semverCmp"));
+    }
+
+    #[test]
+    fn now_functions() {
+        let at = |name: &str, now: Option<i64>| {
+            apply(name, &[], &crate::collation::DefaultCollation, now)
+        };
+        let now = Some(1_577_836_800_500);
+        assert!(approx(&at("now", now), 1_577_836_800.5));
+        assert!(matches!(
+            at("nowMillis", now),
+            FastVal::Int(1_577_836_800_500)
+        ));
+        assert!(matches!(
+            at("nowStr", now),
+            FastVal::Str(FastStr::Owned(s)) if s == "2020-01-01T00:00:00.500Z"
+        ));
+        // no clock, or an argument, is missing
+        for name in ["now", "nowMillis", "nowStr"] {
+            assert!(matches!(at(name, None), FastVal::Missing));
+            assert!(matches!(
+                apply(
+                    name,
+                    &[FastVal::Int(0)],
+                    &crate::collation::DefaultCollation,
+                    now
+                ),
+                FastVal::Missing
+            ));
+        }
+    }
+
+    #[test]
+    fn string_functions() {
+        use crate::value::FastStr;
+        let s = |v: &'static str| FastVal::Str(FastStr::Unescaped(v.as_bytes()));
+        let text = |v: FastVal<'_>| match v {
+            FastVal::Str(FastStr::Owned(s)) => s,
+            other => panic!("expected an owned string: {other:?}"),
+        };
+        assert_eq!(text(f("strLower", &[s("ÀdA")])), "àda");
+        assert_eq!(text(f("strUpper", &[s("straße")])), "STRASSE");
+        assert_eq!(text(f("strTrim", &[s("  a b\t")])), "a b");
+        assert_eq!(text(f("strTrim", &[s("xxaxyx"), s("xy")])), "a");
+        assert!(matches!(f("strLength", &[s("héllo")]), FastVal::Int(5)));
+        assert!(matches!(
+            f("strContains", &[s("abc"), s("bc")]),
+            FastVal::Bool(true)
+        ));
+        assert!(matches!(
+            f("strStartsWith", &[s("abc"), s("b")]),
+            FastVal::Bool(false)
+        ));
+        assert!(matches!(
+            f("strEndsWith", &[s("abc"), s("")]),
+            FastVal::Bool(true)
+        ));
+
+        // SUBSTR counts codepoints, from 0 or back from the end.
+        let sub = |args: &[FastVal<'_>]| text(f("strSubstr", args));
+        assert_eq!(sub(&[s("héllo"), FastVal::Int(1), FastVal::Int(3)]), "éll");
+        assert_eq!(sub(&[s("héllo"), FastVal::Int(-2)]), "lo");
+        assert_eq!(sub(&[s("héllo"), FastVal::Int(3), FastVal::Int(99)]), "lo");
+        assert_eq!(sub(&[s("héllo"), FastVal::Float(5.0)]), "");
+
+        // Escaped document strings are decoded first.
+        let esc = FastVal::Str(FastStr::Escaped(br#"A\u00e9\"B"#));
+        assert!(matches!(
+            f("strLength", std::slice::from_ref(&esc)),
+            FastVal::Int(4)
+        ));
+        assert_eq!(text(f("strLower", &[esc])), "aé\"b");
+    }
+
+    #[test]
+    fn string_functions_reject_bad_arguments() {
+        use crate::value::FastStr;
+        let s = FastVal::Str(FastStr::Unescaped(b"abc"));
+        for (name, args) in [
+            ("strLower", vec![]),
+            ("strLower", vec![FastVal::Int(1)]),
+            ("strLength", vec![s.clone(), s.clone()]),
+            ("strContains", vec![s.clone()]),
+            ("strContains", vec![s.clone(), FastVal::Null]),
+            ("strTrim", vec![s.clone(), s.clone(), s.clone()]),
+            ("strSubstr", vec![s.clone()]),
+            ("strSubstr", vec![s.clone(), FastVal::Float(0.5)]),
+            ("strSubstr", vec![s.clone(), FastVal::Int(4)]),
+            ("strSubstr", vec![s.clone(), FastVal::Int(-4)]),
+            (
+                "strSubstr",
+                vec![s.clone(), FastVal::Int(0), FastVal::Int(-1)],
+            ),
+            ("strUpper", vec![FastVal::Str(FastStr::Unescaped(b"\xff"))]),
+        ] {
+            assert!(
+                matches!(f(name, &args), FastVal::Missing),
+                "{name} {args:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn type_functions() {
+        use crate::value::FastStr;
+        let vals = [
+            (FastVal::Null, "null"),
+            (FastVal::Bool(false), "boolean"),
+            (FastVal::IntBytes(b"7"), "number"),
+            (FastVal::Float(0.5), "number"),
+            (FastVal::Str(FastStr::Escaped(br"\n")), "string"),
+            (FastVal::Array(b"[]"), "array"),
+            (FastVal::Object(b"{}"), "object"),
+        ];
+        for (v, name) in vals {
+            assert!(
+                matches!(f("type", std::slice::from_ref(&v)), FastVal::Str(FastStr::Owned(s)) if s == name),
+                "{v:?}"
+            );
+            for (pred, ty) in [
+                ("isString", "string"),
+                ("isNumber", "number"),
+                ("isBoolean", "boolean"),
+                ("isArray", "array"),
+                ("isObject", "object"),
+            ] {
+                assert!(is_predicate(pred));
+                assert!(
+                    matches!(f(pred, std::slice::from_ref(&v)), FastVal::Bool(b) if b == (ty == name))
+                );
+            }
+        }
+        // A missing argument has no type, and the predicates do not answer for it.
+        assert!(matches!(f("type", &[FastVal::Missing]), FastVal::Missing));
+        assert!(matches!(
+            f("isString", &[FastVal::Missing]),
+            FastVal::Missing
+        ));
+        assert!(matches!(f("isString", &[]), FastVal::Missing));
+        assert!(!is_predicate("type"));
+    }
+
+    #[test]
+    fn array_functions() {
+        use FastVal::{Array, Bool, Int, Missing, Null};
+        let arr = Array(br#"[ 3, null, "b", [1, 2], {"k": [0]}, 1.5, "a\u0062" ]"#);
+        assert!(matches!(
+            f("arrayLength", std::slice::from_ref(&arr)),
+            Int(7)
+        ));
+        assert!(matches!(f("arrayLength", &[Array(b"[]")]), Int(0)));
+        assert!(approx(&f("arraySum", std::slice::from_ref(&arr)), 4.5));
+        assert!(approx(&f("arrayAvg", std::slice::from_ref(&arr)), 2.25));
+        // Elements compare as `=` compares them: decoded strings, exact numbers, containers by
+        // their bytes.
+        let contains = |v: FastVal<'static>| f("arrayContains", &[arr.clone(), v]);
+        assert!(matches!(
+            contains(FastVal::Str(FastStr::borrowed_str("ab"))),
+            Bool(true)
+        ));
+        assert!(matches!(contains(FastVal::Float(3.0)), Bool(true)));
+        assert!(matches!(contains(Array(b"[1, 2]")), Bool(true)));
+        assert!(matches!(contains(Array(b"[1,2]")), Bool(false)));
+        assert!(matches!(contains(Null), Bool(true)));
+        assert!(matches!(contains(Missing), Missing));
+        // Under the collation's type order an object is the greatest, a number the least
+        // non-null element.
+        assert!(
+            matches!(f("arrayMax", std::slice::from_ref(&arr)), FastVal::OwnedObject(b) if &b[..] == br#"{"k": [0]}"#)
+        );
+        assert!(approx(&f("arrayMin", std::slice::from_ref(&arr)), 1.5));
+        // With nothing to aggregate: a sum of 0, and null for the rest.
+        let nulls = Array(b"[null, \"x\"]");
+        assert!(approx(&f("arraySum", std::slice::from_ref(&nulls)), 0.0));
+        assert!(matches!(f("arrayAvg", std::slice::from_ref(&nulls)), Null));
+        assert!(matches!(f("arrayMin", &[Array(b"[null]")]), Null));
+        // Anything but an array, or an array that does not tokenize, is missing.
+        for v in [
+            Int(1),
+            Null,
+            Missing,
+            FastVal::Object(b"{}"),
+            Array(b"[1, tru]"),
+        ] {
+            assert!(
+                matches!(f("arrayLength", std::slice::from_ref(&v)), Missing),
+                "{v:?}"
+            );
+            assert!(matches!(f("arrayContains", &[v, Int(1)]), Missing));
+        }
+        assert!(is_predicate("arrayContains"));
+    }
+
+    #[test]
+    fn conversions() {
+        use FastVal::{Bool, Float, Int, Missing, Null, Uint};
+        let s = |t: &'static str| FastVal::Str(FastStr::borrowed_str(t));
+        let esc = |t: &'static str| FastVal::Str(FastStr::Escaped(t.as_bytes()));
+        let num = |v: FastVal<'static>| f("toNumber", &[v]);
+        // Strings read as a document's number would be: integers exact, the rest as `f64`.
+        assert!(matches!(num(s("42")), Int(42)));
+        assert!(matches!(num(s("-0")), Int(0)));
+        assert!(matches!(num(s("18446744073709551615")), Uint(u64::MAX)));
+        assert!(matches!(num(s("4.2e1")), Float(x) if x == 42.0));
+        assert!(matches!(num(esc(r"1.5")), Float(x) if x == 1.5));
+        for bad in [
+            "", " 1", "1 ", "+1", "01", "1.", ".5", "0x10", "1e400", "NaN", "1,2",
+        ] {
+            assert!(matches!(num(s(bad)), Missing), "{bad:?}");
+        }
+        assert!(matches!(num(Bool(true)), Int(1)));
+        assert!(matches!(num(FastVal::FloatBytes(b"2.5")), Float(x) if x == 2.5));
+        for v in [Null, Missing, FastVal::Array(b"[1]")] {
+            assert!(matches!(num(v), Missing));
+        }
+
+        let text = |v: FastVal<'static>| match f("toString", &[v]) {
+            FastVal::Str(s) => String::from_utf8(s.to_decoded_bytes().into_owned()).unwrap(),
+            other => panic!("{other:?}"),
+        };
+        // Canonical number text: integers exact, floats in their shortest round-trip form.
+        assert_eq!(text(FastVal::IntBytes(b"-0")), "0");
+        assert_eq!(text(Uint(u64::MAX)), "18446744073709551615");
+        assert_eq!(
+            text(FastVal::IntBytes(b"9007199254740993")),
+            "9007199254740993"
+        );
+        for (x, want) in [
+            (42.0, "42"),
+            (-0.0, "0"),
+            (0.1, "0.1"),
+            (-1.5, "-1.5"),
+            (123.456, "123.456"),
+            (1e21, "1e+21"),
+            (1.5e300, "1.5e+300"),
+            (1e20, "100000000000000000000"),
+            (0.000001, "0.000001"),
+            (1.25e-7, "1.25e-7"),
+        ] {
+            assert_eq!(text(Float(x)), want);
+        }
+        assert_eq!(text(FastVal::FloatBytes(b"4.20e1")), "42");
+        assert_eq!(text(Bool(false)), "false");
+        assert_eq!(text(esc(r"a\nb")), "a\nb");
+        for v in [
+            Null,
+            Missing,
+            FastVal::FloatBytes(b"1e400"),
+            FastVal::Object(b"{}"),
+        ] {
+            assert!(matches!(f("toString", &[v]), Missing));
+        }
+        // The two round-trip.
+        for t in ["7", "-2.5", "1e+21", "0.000123"] {
+            assert_eq!(text(num(s(t))), t);
+        }
+
+        let truth = |v: FastVal<'static>| f("toBoolean", &[v]);
+        for v in [
+            Int(0),
+            Float(-0.0),
+            s(""),
+            FastVal::Array(b"[ ]"),
+            FastVal::Object(b"{}"),
+        ] {
+            assert!(matches!(truth(v.clone()), Bool(false)), "{v:?}");
+        }
+        for v in [
+            Int(2),
+            s("false"),
+            FastVal::Array(b"[0]"),
+            FastVal::Object(b"{\"a\":0}"),
+        ] {
+            assert!(matches!(truth(v.clone()), Bool(true)), "{v:?}");
+        }
+        assert!(matches!(truth(Null), Missing));
+        assert!(matches!(truth(Missing), Missing));
+        assert!(is_predicate("toBoolean"));
+    }
+
+    #[test]
+    fn object_functions() {
+        use FastVal::{Int, Missing, Object, OwnedArray};
+        let obj = Object(br#"{ "b": [1, 2], "a\u0062": "x", "a": null }"#);
+        let list = |name: &str| match f(name, std::slice::from_ref(&obj)) {
+            OwnedArray(b) => String::from_utf8(b.into_vec()).unwrap(),
+            other => panic!("{name}: {other:?}"),
+        };
+        assert!(matches!(
+            f("objectLength", std::slice::from_ref(&obj)),
+            Int(3)
+        ));
+        // Sorted by decoded name, each name and value copied as the document spelled it.
+        assert_eq!(list("objectNames"), r#"["a","a\u0062","b"]"#);
+        assert_eq!(list("objectValues"), r#"[null,"x",[1, 2]]"#);
+        assert_eq!(
+            list("objectPairs"),
+            r#"[{"name":"a","val":null},{"name":"a\u0062","val":"x"},{"name":"b","val":[1, 2]}]"#
+        );
+        // A built list is an array like any other, to the array functions too.
+        let names = f("objectNames", std::slice::from_ref(&obj));
+        let ab = FastVal::Str(FastStr::borrowed_str("ab"));
+        assert!(matches!(
+            f("arrayContains", &[names.clone(), ab]),
+            FastVal::Bool(true)
+        ));
+        assert!(
+            matches!(f("arrayMax", &[names]), FastVal::Str(s) if s.eq_str(&FastStr::borrowed_str("b")))
+        );
+        assert!(matches!(f("objectLength", &[Object(b"{}")]), Int(0)));
+        for v in [Int(1), Missing, FastVal::Array(b"[]"), Object(b"{1: 2}")] {
+            assert!(
+                matches!(f("objectLength", std::slice::from_ref(&v)), Missing),
+                "{v:?}"
+            );
+            assert!(matches!(f("objectPairs", &[v]), Missing));
+        }
+    }
+
+    #[test]
+    fn missing_and_null_handling() {
+        use FastVal::{Int, Missing, Null};
+        let s = FastVal::Str(FastStr::borrowed_str("x"));
+        assert!(matches!(f("ifMissing", &[Missing, Null, Int(1)]), Null));
+        assert!(matches!(f("ifNull", &[Null, Missing, Int(1)]), Missing));
+        assert!(matches!(
+            f("ifMissingOrNull", &[Missing, Null, Int(1)]),
+            Int(1)
+        ));
+        // The chosen argument comes back as it was, borrowed strings included.
+        assert!(
+            matches!(f("ifMissing", &[Missing, s]), FastVal::Str(v) if v.eq_str(&FastStr::borrowed_str("x")))
+        );
+        // With no argument to choose, the result is null; fewer than two is a wrong arity.
+        assert!(matches!(f("ifMissing", &[Missing, Missing]), Null));
+        assert!(matches!(f("ifMissingOrNull", &[Null, Missing]), Null));
+        assert!(matches!(f("ifNull", &[Int(1)]), Missing));
+        assert!(sees_missing("ifNull") && !sees_missing("type"));
     }
 
     #[test]
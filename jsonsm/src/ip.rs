@@ -0,0 +1,175 @@
+//! IP addresses and CIDR networks, for `ipInCidr`, `ipInRange`, `ipVersion` and `ipNormalize`.
+//!
+//! An address is read from text by the standard library's strict parsers: dotted-quad IPv4
+//! without leading zeros, or IPv6 in any of its RFC 4291 spellings, with no zone and no
+//! brackets. An IPv4-mapped IPv6 address (`::ffff:10.0.0.1`) is the IPv4 address it carries,
+//! so it falls in the same networks and ranges as `10.0.0.1` does. Every address is held as
+//! its family and its value as a 128-bit integer, which orders it numerically.
+//!
+//! A network is written `address/prefix`. Host bits past the prefix are allowed and ignored,
+//! so `10.1.2.3/8` is `10.0.0.0/8`.
+
+use std::net::IpAddr;
+
+/// A parsed IP address: its family and its numeric value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Addr {
+    /// Whether this is an IPv4 address, whose value then fits 32 bits.
+    pub v4: bool,
+    /// The address as an integer, most significant byte first.
+    pub value: u128,
+}
+
+impl Addr {
+    /// Read an address from text; `None` for anything that is not one.
+    pub fn parse(s: &str) -> Option<Addr> {
+        let ip: IpAddr = s.parse().ok()?;
+        Some(Addr::from(ip.to_canonical()))
+    }
+
+    /// The width of the address in bits.
+    pub fn bits(self) -> u32 {
+        if self.v4 {
+            32
+        } else {
+            128
+        }
+    }
+
+    /// The address as a standard `IpAddr`, which displays it canonically: dotted-quad, or
+    /// RFC 5952's compressed lowercase IPv6.
+    pub fn to_ip(self) -> IpAddr {
+        if self.v4 {
+            IpAddr::from((self.value as u32).to_be_bytes())
+        } else {
+            IpAddr::from(self.value.to_be_bytes())
+        }
+    }
+}
+
+impl From<IpAddr> for Addr {
+    fn from(ip: IpAddr) -> Addr {
+        match ip {
+            IpAddr::V4(a) => Addr {
+                v4: true,
+                value: u32::from(a).into(),
+            },
+            IpAddr::V6(a) => Addr {
+                v4: false,
+                value: a.into(),
+            },
+        }
+    }
+}
+
+/// A CIDR network: the addresses that share its first `prefix` bits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cidr {
+    /// The network address, its host bits cleared.
+    pub base: Addr,
+    /// How many leading bits an address must share with `base`.
+    pub prefix: u32,
+}
+
+impl Cidr {
+    /// Read a network written `address/prefix`; `None` for a malformed address, a prefix that
+    /// is not a plain decimal, or one longer than the address.
+    pub fn parse(s: &str) -> Option<Cidr> {
+        let (ip, prefix) = s.split_once('/')?;
+        if prefix.is_empty() || prefix.len() > 3 || !prefix.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        let ip: IpAddr = ip.parse().ok()?;
+        let mut prefix: u32 = prefix.parse().ok()?;
+        let addr = Addr::from(ip.to_canonical());
+        if ip.is_ipv6() && addr.v4 {
+            // A mapped IPv4 network's prefix counts the 96 bits of the mapping too.
+            prefix = prefix.checked_sub(96)?;
+        }
+        if prefix > addr.bits() {
+            return None;
+        }
+        Some(Cidr {
+            base: Addr {
+                value: addr.value & Cidr::mask(addr.bits(), prefix),
+                ..addr
+            },
+            prefix,
+        })
+    }
+
+    /// Whether `addr` is in this network. An address of the other family never is.
+    pub fn contains(&self, addr: Addr) -> bool {
+        addr.v4 == self.base.v4
+            && addr.value & Cidr::mask(addr.bits(), self.prefix) == self.base.value
+    }
+
+    /// The leading `prefix` of `bits` bits set.
+    fn mask(bits: u32, prefix: u32) -> u128 {
+        let all = u128::MAX >> (128 - bits);
+        if prefix == 0 {
+            0
+        } else {
+            all & !(all >> prefix)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn net(s: &str) -> Cidr {
+        Cidr::parse(s).unwrap_or_else(|| panic!("{s} should parse"))
+    }
+    fn addr(s: &str) -> Addr {
+        Addr::parse(s).unwrap_or_else(|| panic!("{s} should parse"))
+    }
+
+    #[test]
+    fn parses_addresses_strictly() {
+        assert_eq!(addr("10.0.0.1").value, 0x0a00_0001);
+        assert!(addr("10.0.0.1").v4 && !addr("::1").v4);
+        assert_eq!(addr("::ffff:10.0.0.1"), addr("10.0.0.1"));
+        assert_eq!(addr("2001:DB8::1"), addr("2001:db8:0:0:0:0:0:1"));
+        for bad in [
+            "",
+            "10.0.0",
+            "10.0.0.256",
+            "010.0.0.1",
+            "10.0.0.1 ",
+            "[::1]",
+            "fe80::1%eth0",
+        ] {
+            assert_eq!(Addr::parse(bad), None, "{bad:?}");
+        }
+        assert_eq!(addr("2001:0db8::0001").to_ip().to_string(), "2001:db8::1");
+    }
+
+    #[test]
+    fn networks_contain_their_prefix() {
+        assert!(net("10.0.0.0/8").contains(addr("10.255.1.2")));
+        assert!(!net("10.0.0.0/8").contains(addr("11.0.0.0")));
+        assert_eq!(net("10.1.2.3/8"), net("10.0.0.0/8"));
+        assert!(net("0.0.0.0/0").contains(addr("203.0.113.9")));
+        assert!(!net("0.0.0.0/0").contains(addr("::1")));
+        assert!(net("192.168.1.7/32").contains(addr("192.168.1.7")));
+        assert!(!net("192.168.1.7/32").contains(addr("192.168.1.8")));
+        assert!(net("2001:db8::/32").contains(addr("2001:db8:ffff::1")));
+        assert!(!net("2001:db8::/32").contains(addr("2001:db9::1")));
+        assert!(net("::/0").contains(addr("::1")));
+        // A mapped network is its IPv4 network, and takes mapped addresses.
+        assert_eq!(net("::ffff:10.0.0.0/104"), net("10.0.0.0/8"));
+        assert!(net("10.0.0.0/8").contains(addr("::ffff:10.9.9.9")));
+        for bad in [
+            "10.0.0.0",
+            "10.0.0.0/33",
+            "10.0.0.0/",
+            "10.0.0.0/+8",
+            "::/129",
+            "x/8",
+        ] {
+            assert_eq!(Cidr::parse(bad), None, "{bad:?}");
+        }
+    }
+}
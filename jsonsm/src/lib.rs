@@ -45,8 +45,11 @@
 //! # Modules
 //!
 //! [`ast`] is the expression tree; [`compile`] turns one or more expressions into a
-//! [`MatchDef`](compile::MatchDef); [`matcher`] evaluates it. [`collation`] is the extension
-//! seam for comparison policy and pattern compilation, [`value`] the runtime value model,
+//! [`MatchDef`](compile::MatchDef); [`matcher`] evaluates it, optionally with [`params`] bound
+//! to the expression's named parameters. [`collation`] is the extension
+//! seam for comparison policy and pattern compilation, and [`registry`] the one for functions
+//! beyond the built-in [`func`]s; [`like`] is the SQL `LIKE` matcher,
+//! [`value`] the runtime value model,
 //! [`tokenizer`] the scanner, and [`logic_tree`] the boolean structure that resolves as
 //! operations report their results.
 
@@ -58,12 +61,18 @@
 
 pub use jsonsm_ast as ast;
 
+pub mod clock;
 pub mod collation;
 pub mod compile;
 pub mod date;
 pub mod func;
+pub mod ip;
+pub mod like;
 pub mod logic_tree;
 pub mod matcher;
+pub mod params;
+pub mod registry;
+pub mod semver;
 #[cfg(feature = "simd")]
 pub mod simd;
 pub mod tokenizer;
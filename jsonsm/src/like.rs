@@ -0,0 +1,221 @@
+//! SQL `LIKE` patterns: `%` matches any run of characters, `_` exactly one, and everything else
+//! itself.
+//!
+//! Unlike the regex behind `REGEXP_CONTAINS`, a `LIKE` pattern is **anchored**: it must match
+//! the whole string, so `'foo%'` is "starts with `foo`", not "contains `foo`". Matching is
+//! against the decoded string and is case-sensitive; a non-string value never matches.
+//!
+//! An escape character, when given, makes the character after it literal, so with escape `\`
+//! the pattern `100\%` matches only the text `100%`.
+//!
+//! [`compile_like`] recognises the shapes filters are mostly written in — an exact string, a
+//! prefix (`'abc%'`), a suffix (`'%abc'`) and a substring (`'%abc%'`) — and matches those with
+//! a slice comparison or a [`memchr::memmem`] search. Anything else, including every pattern
+//! using `_`, is translated to an anchored [`regex::bytes::Regex`].
+
+use crate::collation::{CollationError, ValueMatcher};
+use crate::value::FastVal;
+use memchr::memmem;
+
+/// Compile a `LIKE` pattern into a matcher, with `escape` as its escape character if any.
+///
+/// Fails only for a pattern ending in an unpaired escape character. This is what
+/// [`DefaultCollation`](crate::collation::DefaultCollation) uses for
+/// [`Collation::compile_like`](crate::collation::Collation::compile_like); a custom collation
+/// with byte-exact string equality can return it as well.
+pub fn compile_like(
+    pattern: &str,
+    escape: Option<char>,
+) -> Result<Box<dyn ValueMatcher>, CollationError> {
+    let pieces = parse(pattern, escape)?;
+    Ok(Box::new(LikeMatcher::new(&pieces)?))
+}
+
+/// One element of a parsed pattern.
+#[derive(Debug, Clone, PartialEq)]
+enum Piece {
+    /// A run of characters matched literally.
+    Text(String),
+    /// `_`: any single character.
+    One,
+    /// `%`: any run of characters, including none. Never adjacent to another.
+    Many,
+}
+
+fn parse(pattern: &str, escape: Option<char>) -> Result<Vec<Piece>, CollationError> {
+    let mut pieces = Vec::new();
+    let mut chars = pattern.chars();
+    let text = |pieces: &mut Vec<Piece>, c: char| match pieces.last_mut() {
+        Some(Piece::Text(t)) => t.push(c),
+        _ => pieces.push(Piece::Text(c.into())),
+    };
+    while let Some(c) = chars.next() {
+        if Some(c) == escape {
+            let escaped = chars.next().ok_or_else(|| {
+                CollationError::InvalidPattern(format!(
+                    "LIKE pattern `{pattern}` ends with its escape character"
+                ))
+            })?;
+            text(&mut pieces, escaped);
+        } else if c == '%' {
+            if pieces.last() != Some(&Piece::Many) {
+                pieces.push(Piece::Many);
+            }
+        } else if c == '_' {
+            pieces.push(Piece::One);
+        } else {
+            text(&mut pieces, c);
+        }
+    }
+    Ok(pieces)
+}
+
+/// A compiled `LIKE` pattern.
+#[derive(Debug)]
+enum LikeMatcher {
+    /// `%`: any string at all.
+    Any,
+    Exact(Vec<u8>),
+    Prefix(Vec<u8>),
+    Suffix(Vec<u8>),
+    /// Boxed: a finder is several times the size of the other variants.
+    Contains(Box<memmem::Finder<'static>>),
+    /// Every other shape, as an anchored regex.
+    Regex(regex::bytes::Regex),
+}
+
+impl LikeMatcher {
+    fn new(pieces: &[Piece]) -> Result<Self, CollationError> {
+        use Piece::{Many, Text};
+        let bytes = |t: &String| t.as_bytes().to_vec();
+        Ok(match pieces {
+            [] => LikeMatcher::Exact(Vec::new()),
+            [Many] => LikeMatcher::Any,
+            [Text(t)] => LikeMatcher::Exact(bytes(t)),
+            [Text(t), Many] => LikeMatcher::Prefix(bytes(t)),
+            [Many, Text(t)] => LikeMatcher::Suffix(bytes(t)),
+            [Many, Text(t), Many] => {
+                LikeMatcher::Contains(Box::new(memmem::Finder::new(t).into_owned()))
+            }
+            _ => {
+                // `(?s)` so `_` and `%` cover newlines too; Unicode mode (the default) makes `.`
+                // one character, not one byte.
+                let mut re = String::from("(?s)^");
+                for p in pieces {
+                    match p {
+                        Piece::Text(t) => re.push_str(&regex::escape(t)),
+                        Piece::One => re.push('.'),
+                        Piece::Many => re.push_str(".*"),
+                    }
+                }
+                re.push('$');
+                let re = regex::bytes::Regex::new(&re)
+                    .map_err(|e| CollationError::InvalidPattern(e.to_string()))?;
+                LikeMatcher::Regex(re)
+            }
+        })
+    }
+
+    fn matches_bytes(&self, s: &[u8]) -> bool {
+        match self {
+            LikeMatcher::Any => true,
+            LikeMatcher::Exact(t) => s == t.as_slice(),
+            LikeMatcher::Prefix(t) => s.starts_with(t),
+            LikeMatcher::Suffix(t) => s.ends_with(t),
+            LikeMatcher::Contains(f) => f.find(s).is_some(),
+            LikeMatcher::Regex(re) => re.is_match(s),
+        }
+    }
+}
+
+impl ValueMatcher for LikeMatcher {
+    fn matches(&self, value: &FastVal<'_>) -> bool {
+        value
+            .as_str()
+            .is_some_and(|s| self.matches_bytes(&s.to_decoded_bytes()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value::FastStr;
+
+    fn like(pattern: &str, escape: Option<char>, s: &str) -> bool {
+        compile_like(pattern, escape)
+            .unwrap()
+            .matches(&FastVal::Str(FastStr::borrowed_str(s)))
+    }
+
+    #[test]
+    fn simple_shapes_avoid_the_regex() {
+        for (pattern, shape) in [
+            ("", "Exact"),
+            ("abc", "Exact"),
+            ("%", "Any"),
+            ("%%", "Any"),
+            ("ab%", "Prefix"),
+            ("%ab", "Suffix"),
+            ("%ab%", "Contains"),
+            ("%%ab%%%", "Contains"),
+            ("a%b", "Regex"),
+            ("a_", "Regex"),
+        ] {
+            let m = LikeMatcher::new(&parse(pattern, None).unwrap()).unwrap();
+            assert!(format!("{m:?}").starts_with(shape), "{pattern}: {m:?}");
+        }
+    }
+
+    #[test]
+    fn matching_is_anchored_and_wildcards_behave() {
+        assert!(like("abc", None, "abc"));
+        assert!(!like("abc", None, "abcd"));
+        assert!(!like("abc", None, "ABC"));
+        assert!(like("", None, ""));
+        assert!(!like("", None, "a"));
+        assert!(like("%", None, ""));
+        assert!(like("ab%", None, "ab"));
+        assert!(like("ab%", None, "abxyz"));
+        assert!(!like("ab%", None, "xab"));
+        assert!(like("%yz", None, "xyz"));
+        assert!(!like("%yz", None, "yzx"));
+        assert!(like("%b%", None, "abc"));
+        assert!(!like("%b%", None, "ac"));
+        assert!(like("a%c", None, "abbbc"));
+        assert!(!like("a%c", None, "abbbcd"));
+        // `_` is one character, not one byte, and `%`/`_` cross newlines.
+        assert!(like("caf_", None, "café"));
+        assert!(!like("caf__", None, "café"));
+        assert!(like("a_c", None, "a\nc"));
+        assert!(like("a%", None, "a\nb"));
+        // Regex metacharacters in the text are literal.
+        assert!(like("a.c%", None, "a.cd"));
+        assert!(!like("a.c_", None, "abcd"));
+    }
+
+    #[test]
+    fn escape_makes_wildcards_literal() {
+        assert!(like(r"100\%", Some('\\'), "100%"));
+        assert!(!like(r"100\%", Some('\\'), "1000"));
+        assert!(like(r"a\_%", Some('\\'), "a_b"));
+        assert!(!like(r"a\_%", Some('\\'), "ab"));
+        assert!(like("a!%%", Some('!'), "a%b"));
+        assert!(like("a!!", Some('!'), "a!"));
+        // Without an escape character, a backslash is just a backslash.
+        assert!(like(r"a\%", None, r"a\bc"));
+        assert!(matches!(
+            compile_like("abc!", Some('!')),
+            Err(CollationError::InvalidPattern(_))
+        ));
+    }
+
+    #[test]
+    fn non_strings_never_match_and_strings_match_decoded() {
+        let m = compile_like("%", None).unwrap();
+        assert!(!m.matches(&FastVal::Int(1)));
+        assert!(!m.matches(&FastVal::Null));
+        assert!(!m.matches(&FastVal::Missing));
+        let m = compile_like("a_", None).unwrap();
+        assert!(m.matches(&FastVal::Str(FastStr::from_content(br"a\n", true))));
+    }
+}
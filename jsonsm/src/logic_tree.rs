@@ -46,6 +46,10 @@ pub enum NodeType {
     /// A loop node: its value is the loop's overall result (its single left child is the
     /// per-iteration sub-tree root).
     Loop,
+    /// `IS KNOWN` of the (single, left) child: `True` when the child is `True` or `False`,
+    /// `False` when it is `Unknown`. The one connective that is not Kleene's, and so the only
+    /// way a node can turn an absent field's `Unknown` into a definite answer.
+    Known,
 }
 
 impl NodeType {
@@ -339,8 +343,14 @@ impl Tri {
         }
     }
 
+    /// `IS KNOWN`: whether this is a definite answer, as a definite answer.
     #[inline]
-    fn or(self, other: Self) -> Self {
+    pub(crate) fn known(self) -> Self {
+        Tri::from_bool(self != Tri::Unknown)
+    }
+
+    #[inline]
+    pub(crate) fn or(self, other: Self) -> Self {
         match (self, other) {
             (Tri::True, _) | (_, Tri::True) => Tri::True,
             (Tri::Unknown, _) | (_, Tri::Unknown) => Tri::Unknown,
@@ -349,7 +359,7 @@ impl Tri {
     }
 
     #[inline]
-    fn and(self, other: Self) -> Self {
+    pub(crate) fn and(self, other: Self) -> Self {
         match (self, other) {
             (Tri::False, _) | (_, Tri::False) => Tri::False,
             (Tri::Unknown, _) | (_, Tri::Unknown) => Tri::Unknown,
@@ -634,7 +644,9 @@ impl LogicTreeState<'_> {
     /// yields `True`. That is strictly more than the obvious `And`-only rule catches — it also
     /// covers `NOT (absent OR unseen)`, whose upper bound is `Unknown` because the `Not` swaps a
     /// lower bound of `Unknown` into the upper position, and a disjunction whose every branch is
-    /// separately capped.
+    /// separately capped. `Known` is the exception: it is not an order operation, and it spans
+    /// `[False, True]` until its child is pinned, which keeps the analysis sound beneath it but
+    /// not complete.
     fn root_can_be_true(&mut self) -> bool {
         // Pre-order layout: a node's children always have higher indices, so a single reverse
         // sweep settles every child before its parent.
@@ -661,6 +673,12 @@ impl LogicTreeState<'_> {
                     self.bound_lo[node.left].not(),
                 ),
                 NodeType::Loop => (self.bound_lo[node.left], self.bound_hi[node.left]),
+                // Not monotone, so bounds do not map through: unless the child is pinned to one
+                // value, either answer is still reachable.
+                NodeType::Known => match (self.bound_lo[node.left], self.bound_hi[node.left]) {
+                    (lo, hi) if lo == hi => (lo.known(), lo.known()),
+                    _ => (Tri::False, Tri::True),
+                },
             };
             self.bound_lo[i] = lo;
             self.bound_hi[i] = hi;
@@ -743,6 +761,7 @@ impl LogicTreeState<'_> {
                 }
             }
             NodeType::Not => Some(l?.not()),
+            NodeType::Known => Some(l?.known()),
             NodeType::Loop => l,
             NodeType::Leaf => unreachable!("leaf nodes are not re-checked"),
         }
@@ -840,6 +859,7 @@ impl LogicTreeState<'_> {
         match node.node_type {
             NodeType::Leaf => Tri::Unknown,
             NodeType::Not => left().not(),
+            NodeType::Known => left().known(),
             NodeType::Loop => left(),
             NodeType::Or | NodeType::Neor => left().or(right()),
             NodeType::And => left().and(right()),
@@ -941,6 +961,47 @@ mod tests {
         assert!(s.is_true(0));
     }
 
+    #[test]
+    fn known_answers_whether_its_child_did() {
+        let mut t = LogicTree::new();
+        t.set_type(0, NodeType::Known);
+        let l = t.add_child(0);
+        t.set_left(0, l);
+        t.validate().expect("valid");
+
+        for (child, want) in [
+            (Tri::True, Tri::True),
+            (Tri::False, Tri::True),
+            (Tri::Unknown, Tri::False),
+        ] {
+            let mut s = t.new_state();
+            s.mark_tri(l, child);
+            assert_eq!(s.value(0), Some(want), "{child:?} IS KNOWN");
+        }
+        // Never reached, the child seals `Unknown`, and the answer is a definite `False`.
+        let mut s = t.new_state();
+        s.resolve();
+        assert_eq!(s.value(0), Some(Tri::False));
+
+        // `(l OR r) IS KNOWN` with `l` Unknown: `r` decides whether the `OR` gets an answer,
+        // so the verdict stays open until it does.
+        let mut t = LogicTree::new();
+        t.set_type(0, NodeType::Known);
+        let or = t.add_child(0);
+        t.set_left(0, or);
+        t.set_type(or, NodeType::Or);
+        let l = t.add_child(or);
+        let r = t.add_child(or);
+        t.set_left(or, l);
+        t.set_right(or, r);
+        t.validate().expect("valid");
+        let mut s = t.new_state();
+        s.mark_tri(l, Tri::Unknown);
+        assert!(!s.root_settled());
+        s.mark_tri(r, Tri::Unknown);
+        assert_eq!(s.value(0), Some(Tri::False));
+    }
+
     #[test]
     fn resolve_seals_unset_leaves_to_unknown() {
         // OR of two leaves, neither op ran (both fields absent): Unknown OR Unknown, which is
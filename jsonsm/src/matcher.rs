@@ -17,18 +17,22 @@
 //! Capture is independent of the match result, so the scan short-circuits only once the
 //! logic tree *and* every projected field are settled.
 
-use crate::collation::{Collation, DefaultCollation};
+use crate::clock::Clock;
+use crate::collation::{Collation, DefaultCollation, PatternCache};
 use crate::compile::{
-    AfterNode, BucketId, CmpOp, DataRef, ExecId, ExecNode, KeyMap, head_word, LoopNode, MatchDef,
-    OpKind, OpNode, SlotId,
+    AfterNode, BucketId, CaseRef, CmpOp, Cond, DataRef, ExecId, ExecNode, KeyMap, head_word,
+    LoopNode, LoopOver, MatchDef, OpKind, OpNode, SlotId,
 };
 use crate::logic_tree::{LogicTreeState, Tri};
+use crate::params::{ParamValue, Params, ValueSet};
 use crate::tokenizer::{
     GenericTokenizer, Scan, SkipError, Token, TokenType, Tokenizer, TokenizerError,
 };
 use crate::value::{FastStr, FastVal};
 use jsonsm_ast::{LoopType, PathComponent};
+use std::cell::RefCell;
 use std::cmp::Ordering;
+use std::sync::Arc;
 
 /// A stored value's location in the document: `(start, len)` in bytes.
 type SlotRange = (usize, usize);
@@ -69,6 +73,18 @@ pub struct FastMatcher<'d, C: Collation = DefaultCollation> {
     /// How many projection slots are still unfilled. While non-zero the scan must not
     /// short-circuit, or a projected field appearing later in the document would be missed.
     pending_projections: usize,
+    /// The value bound to each of the definition's parameters for the current call, indexed
+    /// like [`MatchDef::param_names`]; `None` is unbound, which reads as missing.
+    params: Vec<Option<Arc<ParamValue>>>,
+    /// Where `now` and its kin read the time; `None` reads it as missing.
+    clock: Option<Arc<dyn Clock>>,
+    /// The clock's reading for the current document, taken once before the scan so every
+    /// function call in one match sees the same instant.
+    now: Option<i64>,
+    /// Regexes read from documents, compiled by `collation` as they are met. Kept across
+    /// matches, which is the point: rule documents tend to repeat their patterns. Behind a
+    /// `RefCell` because ops are evaluated through `&self`.
+    patterns: RefCell<PatternCache>,
     /// Which scan backend this matcher runs. Resolved **once**, here, by CPU feature
     /// detection; [`FastMatcher::scan`] branches on it a single time per document and
     /// everything below that point is monomorphised for the chosen backend.
@@ -93,6 +109,10 @@ impl<'d, C: Collation> FastMatcher<'d, C> {
             state: def.tree.new_state(),
             slots: vec![None; def.num_slots()],
             pending_projections: def.num_projection_slots,
+            params: vec![None; def.params.len()],
+            clock: None,
+            now: None,
+            patterns: RefCell::default(),
             #[cfg(feature = "simd")]
             backend: crate::simd::Backend::detect(),
         }
@@ -127,6 +147,86 @@ impl<'d, C: Collation> FastMatcher<'d, C> {
     /// # Ok::<(), jsonsm::matcher::MatchError>(())
     /// ```
     pub fn matches<'a>(&mut self, doc: &'a [u8]) -> Result<MatchOutcome<'_, 'a>, MatchError> {
+        self.params.iter_mut().for_each(|p| *p = None);
+        self.match_doc(doc)
+    }
+
+    /// [`Self::matches`], with the definition's named parameters bound from `params`.
+    ///
+    /// Each parameter the definition references is looked up by name once per call; one the
+    /// call does not bind is missing, so a comparison against it is UNKNOWN. Bindings last for
+    /// this call only — a following [`Self::matches`] sees every parameter unbound again.
+    ///
+    /// ```
+    /// use jsonsm::ast::{CompareOp, Expr, Field, Literal, PathComponent};
+    /// use jsonsm::collation::DefaultCollation;
+    /// use jsonsm::compile::{compile, Projection};
+    /// use jsonsm::matcher::FastMatcher;
+    /// use jsonsm::params::Params;
+    ///
+    /// let expr = Expr::compare(
+    ///     CompareOp::GreaterEquals,
+    ///     Expr::Field(Field::root(vec![PathComponent::Key("age".into())])),
+    ///     Expr::Param("min_age".into()),
+    /// );
+    /// let def = compile(&[expr], &Projection::new(), &DefaultCollation).unwrap();
+    /// let mut m = FastMatcher::new(&def);
+    ///
+    /// let adults = Params::new().scalar("min_age", Literal::Int(18));
+    /// let seniors = Params::new().scalar("min_age", Literal::Int(65));
+    /// assert!(m.matches_with(br#"{"age": 41}"#, &adults)?.matched());
+    /// assert!(!m.matches_with(br#"{"age": 41}"#, &seniors)?.matched());
+    /// # Ok::<(), jsonsm::matcher::MatchError>(())
+    /// ```
+    pub fn matches_with<'a>(
+        &mut self,
+        doc: &'a [u8],
+        params: &Params,
+    ) -> Result<MatchOutcome<'_, 'a>, MatchError> {
+        let def = self.def;
+        for (bound, name) in self.params.iter_mut().zip(&def.params) {
+            *bound = params.get_shared(name);
+        }
+        self.match_doc(doc)
+    }
+
+    /// Read the current time for `now`, `nowMillis` and `nowStr` from `clock`, once per
+    /// document. Without a clock they are missing, so a match never depends on when it ran
+    /// unless asked to.
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use jsonsm::ast::{CompareOp, Expr, Field, Func, PathComponent};
+    /// use jsonsm::clock::FixedClock;
+    /// use jsonsm::collation::DefaultCollation;
+    /// use jsonsm::compile::{compile, Projection};
+    /// use jsonsm::matcher::FastMatcher;
+    ///
+    /// // expires > nowMillis()
+    /// let expr = Expr::compare(
+    ///     CompareOp::GreaterThan,
+    ///     Expr::Field(Field::root(vec![PathComponent::Key("expires".into())])),
+    ///     Expr::Func(Func { name: "nowMillis".into(), args: vec![] }),
+    /// );
+    /// let def = compile(&[expr], &Projection::new(), &DefaultCollation).unwrap();
+    /// let mut m = FastMatcher::new(&def);
+    /// let doc = br#"{"expires": 1577836800000}"#;
+    ///
+    /// assert!(!m.matches(doc)?.matched());
+    /// m.set_clock(Arc::new(FixedClock(1_577_836_799_000)));
+    /// assert!(m.matches(doc)?.matched());
+    /// m.set_clock(Arc::new(FixedClock(1_577_836_801_000)));
+    /// assert!(!m.matches(doc)?.matched());
+    /// # Ok::<(), jsonsm::matcher::MatchError>(())
+    /// ```
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = Some(clock);
+    }
+
+    /// The body shared by [`Self::matches`] and [`Self::matches_with`], once parameters are
+    /// bound.
+    fn match_doc<'a>(&mut self, doc: &'a [u8]) -> Result<MatchOutcome<'_, 'a>, MatchError> {
+        self.now = self.clock.as_ref().map(|c| c.now_millis());
         self.state.reset();
         self.slots.iter_mut().for_each(|s| *s = None);
         self.pending_projections = self.def.num_projection_slots;
@@ -271,7 +371,7 @@ impl<'d, C: Collation> FastMatcher<'d, C> {
 
         if token.token_type.is_literal() {
             self.match_literal(tokens, token, exec);
-            return Ok(());
+            return self.run_scalar_after(tokens, &def.arena[exec], depth);
         }
 
         if depth >= MAX_DEPTH {
@@ -285,6 +385,18 @@ impl<'d, C: Collation> FastMatcher<'d, C> {
 
         match token.token_type {
             TokenType::ObjectStart => {
+                // A loop over the object's members (`objectValues` and the like) is a pass of
+                // its own, like a loop over an array, so rewind after each.
+                if node.loops.iter().any(|lp| lp.over != LoopOver::Elements) {
+                    let save = tokens.position();
+                    for lp in node.loops.iter().filter(|lp| lp.over != LoopOver::Elements) {
+                        self.match_loop(tokens, lp, depth)?;
+                        if self.done() {
+                            return Ok(());
+                        }
+                        tokens.seek(save);
+                    }
+                }
                 if node.elems.is_empty() {
                     leave_value(tokens, depth)?;
                 } else {
@@ -312,7 +424,11 @@ impl<'d, C: Collation> FastMatcher<'d, C> {
                 // number of loops. Each is a separate pass over the array, so rewind between
                 // them; every pass consumes through the closing `]`.
                 let indexed = !node.indexed.is_empty();
-                let n_loops = node.loops.len();
+                let n_loops = node
+                    .loops
+                    .iter()
+                    .filter(|lp| lp.over == LoopOver::Elements)
+                    .count();
                 if !indexed && n_loops == 0 {
                     leave_value(tokens, depth)?;
                 } else {
@@ -325,22 +441,15 @@ impl<'d, C: Collation> FastMatcher<'d, C> {
                         }
                         pass += 1;
                     }
-                    for i in 0..n_loops {
+                    // The loop nodes are borrowed from `def` (lifetime 'd), independent of
+                    // `self`, so the &mut self call below is fine.
+                    let loops = node.loops.iter().filter(|lp| lp.over == LoopOver::Elements);
+                    for lp in loops {
                         if pass > 0 {
                             tokens.seek(save);
                         }
                         pass += 1;
-                        // The loop node is borrowed from `def` (lifetime 'd), independent
-                        // of `self`, so the &mut self call below is fine.
-                        let lp: &LoopNode = &node.loops[i];
-                        self.match_loop(
-                            tokens,
-                            lp.bucket,
-                            lp.mode,
-                            lp.node,
-                            &lp.clear_slots,
-                            depth,
-                        )?;
+                        self.match_loop(tokens, lp, depth)?;
                         if self.done() {
                             return Ok(());
                         }
@@ -478,32 +587,24 @@ impl<'d, C: Collation> FastMatcher<'d, C> {
         }
 
         // Deferred loops: seek back to the stored array and iterate now that outer fields
-        // referenced by the body are available. Copy the descriptors out first so the
-        // borrow of `def` does not overlap the `&mut self` loop calls.
-        let loops: Vec<(BucketId, LoopType, ExecId, usize, &[SlotId])> = after
-            .loops
-            .iter()
-            .map(|l| {
-                (
-                    l.bucket,
-                    l.mode,
-                    l.node,
-                    l.array_slot,
-                    l.clear_slots.as_slice(),
-                )
-            })
-            .collect();
-        for (bucket, mode, node, array_slot, clear) in loops {
-            if self.state.is_resolved(bucket) {
+        // referenced by the body are available. `after` is borrowed from `def` (lifetime
+        // 'd), so its loops outlive the `&mut self` loop calls.
+        for l in &after.loops {
+            if self.state.is_resolved(l.lp.bucket) {
                 continue;
             }
-            // If the array field was absent or not an array, the loop does not apply; its
-            // node stays unresolved and `resolve` defaults it to false.
-            if let Some((start, _)) = self.slots[array_slot] {
+            // If the array field was absent or not an array (an object, for a loop over
+            // members), the loop does not apply; its node stays unresolved and `resolve`
+            // defaults it to false.
+            if let Some((start, _)) = self.slots[l.array_slot] {
                 let save = tokens.position();
                 tokens.seek(start);
-                if tokens.step()?.token_type == TokenType::ArrayStart {
-                    self.match_loop(tokens, bucket, mode, node, clear, depth)?;
+                let want = match l.lp.over {
+                    LoopOver::Elements => TokenType::ArrayStart,
+                    _ => TokenType::ObjectStart,
+                };
+                if tokens.step()?.token_type == want {
+                    self.match_loop(tokens, &l.lp, depth)?;
                 }
                 tokens.seek(save);
             }
@@ -514,6 +615,30 @@ impl<'d, C: Collation> FastMatcher<'d, C> {
         Ok(())
     }
 
+    /// Run the after-node of a scope whose value turned out to be a scalar: a loop element, or
+    /// the document itself.
+    ///
+    /// A container runs its after-node when it closes, and a scalar has no close. Most deferred
+    /// ops could skip it anyway — every field they read is absent, so the seal's `Unknown` is
+    /// their answer — but not all: a `CASE` whose conditions read only absent fields still
+    /// selects its `ELSE`, and the comparison over it has a definite answer. Only a scope root
+    /// has an after-node, so a scalar field inside an object never gets here.
+    #[inline(always)]
+    fn run_scalar_after<'a, S: Scan>(
+        &mut self,
+        tokens: &mut GenericTokenizer<'a, S>,
+        node: &'d ExecNode,
+        depth: usize,
+    ) -> Result<(), MatchError>
+    where
+        'd: 'a,
+    {
+        match node.after.as_ref() {
+            Some(after) if !self.done() => self.run_after_node(tokens, after, depth),
+            _ => Ok(()),
+        }
+    }
+
     /// Scan an object, recursing into fields present in `exec.elems` and skipping the rest.
     fn match_object<'a, S: Scan>(
         &mut self,
@@ -752,22 +877,27 @@ impl<'d, C: Collation> FastMatcher<'d, C> {
         }
     }
 
-    /// Run one loop over the array whose opening `[` has just been consumed. Shared by
-    /// inline loops and deferred after-loops. `body` is the loop's body bucket, `node` its
-    /// per-element exec node, and `clear` the slots owned by the body (reset per element so
-    /// one element never reads another's value).
+    /// Run one loop over the array whose opening `[` has just been consumed — or, for a loop
+    /// over an object's members, the object whose `{` has. Shared by inline loops and
+    /// deferred after-loops. The loop's `clear_slots` are the slots owned by the body, reset
+    /// per element so one element never reads another's value.
     fn match_loop<'a, S: Scan>(
         &mut self,
         tokens: &mut GenericTokenizer<'a, S>,
-        body: BucketId,
-        mode: LoopType,
-        node: ExecId,
-        clear: &[SlotId],
+        lp: &'d LoopNode,
         depth: usize,
     ) -> Result<(), MatchError>
     where
         'd: 'a,
     {
+        let LoopNode {
+            bucket: body,
+            mode,
+            over,
+            node,
+            ref clear_slots,
+        } = *lp;
+        let clear = clear_slots.as_slice();
         if self.state.is_resolved(body) {
             leave_value(tokens, depth)?;
             return Ok(());
@@ -795,16 +925,20 @@ impl<'d, C: Collation> FastMatcher<'d, C> {
             LoopType::Every | LoopType::AnyEvery => Tri::False,
         };
         let prev_stall = self.state.set_stall(body);
+        let (close, end, unexpected) = match over {
+            LoopOver::Elements => (b']', TokenType::ArrayEnd, "expected ',' or ']' in array"),
+            _ => (b'}', TokenType::ObjectEnd, "expected ',' or '}' in object"),
+        };
 
         let mut first = true;
         loop {
             if !first {
-                let more = match take_delim(tokens, b']') {
+                let more = match take_delim(tokens, close) {
                     Some(more) => more,
                     None => match tokens.step()?.token_type {
-                        TokenType::ArrayEnd => false,
                         TokenType::ListDelim => true,
-                        _ => return Err(MatchError::Structure("expected ',' or ']' in array")),
+                        t if t == end => false,
+                        _ => return Err(MatchError::Structure(unexpected)),
                     },
                 };
                 if !more {
@@ -813,77 +947,22 @@ impl<'d, C: Collation> FastMatcher<'d, C> {
             }
             first = false;
 
-            // A string element is a scalar, so [`Self::match_exec`] would route it straight
-            // to [`Self::match_literal`]: run the node's ops against it and record its byte
-            // range. Reading it here does exactly that, without `step` building a 24-byte
-            // token and without entering the walker whose frame is sized for the container
-            // cases a scalar cannot take. Nothing about the *body* is assumed — the ops, the
-            // slot and the seal below are the same ones the tokenized arm runs.
-            //
-            // The reset and the slot clear are written out in each arm rather than hoisted
-            // above the `match`. Sharing them would put the fused read's branch on the
-            // tokenized arm's path, which an element that can never settle here would pay
-            // once per element for nothing.
-            match take_str_value(tokens) {
-                Some(bytes) => {
-                    let end = tokens.position();
-                    self.state.reset_node(body);
-                    for &slot in clear {
-                        self.slots[slot] = None;
-                    }
-                    let val = FastVal::Str(FastStr::Unescaped(bytes));
-                    self.run_op_list(tokens, &body_node.ops, &val);
-                    // The element spans its two quotes as well as its content, which is what
-                    // `match_literal` derives from the token's length.
-                    self.store_range(
-                        body_node.store,
-                        body_node.store_projected,
-                        end - bytes.len() - 2,
-                        end,
-                    );
-                }
-                None => {
-                    // Not a plain string, so it is a container, the end of the array, or
-                    // something only the tokenizer can name. The first two are decided by the
-                    // byte itself; `match_exec` wants the token `step` would have returned, so
-                    // it is rebuilt from the cursor the probe just moved.
-                    let elem = match take_structural_head(tokens) {
-                        Some(TokenType::ArrayEnd) => break,
-                        Some(token_type) => {
-                            let at = tokens.position() - 1;
-                            Token {
-                                token_type,
-                                value: &tokens.input()[at..at + 1],
-                            }
-                        }
-                        None => {
-                            let elem = tokens.step()?;
-                            if elem.token_type == TokenType::ArrayEnd {
-                                break;
-                            }
-                            elem
-                        }
-                    };
-                    self.state.reset_node(body);
-                    // This element starts with none of the body's stored fields known.
-                    for &slot in clear {
-                        self.slots[slot] = None;
-                    }
-                    // A scalar the fused read declined — a number, a boolean, `null`, an
-                    // escaped string — contains nothing, so none of the walker's machinery can
-                    // apply and `match_exec` would route it straight back out to
-                    // `match_literal`. Going there directly skips the frame `match_literal`
-                    // exists to avoid, which an array of numbers would otherwise pay once per
-                    // element — only strings and containers have a probe that names them.
-                    if elem.token_type.is_literal() {
-                        self.match_literal(tokens, elem, node);
-                    } else {
-                        self.match_exec(tokens, elem, node, depth + 1)?;
-                    }
+            let more = match over {
+                LoopOver::Elements => {
+                    self.loop_element(tokens, body, node, body_node, clear, depth)?
                 }
+                _ => self.loop_member(tokens, lp, body_node, depth)?,
+            };
+            if !more {
+                break;
             }
             // Seal this element's body: anything still unset names a field this element did
-            // not have, which is unanswerable for this element.
+            // not have, which is unanswerable for this element — unless the body asks about
+            // presence, whose leaves have a definite answer for an absent field and take it
+            // from the node's bucket list first.
+            if body_node.seal_definite && !self.state.is_resolved(body) {
+                self.seal_absent_buckets(node);
+            }
             let matched = self.state.seal_and_value(body);
 
             match matched {
@@ -918,6 +997,163 @@ impl<'d, C: Collation> FastMatcher<'d, C> {
         Ok(())
     }
 
+    /// Read one array element for [`Self::match_loop`] and run the loop body over it, or
+    /// return `false` at the closing `]`.
+    #[inline(always)]
+    fn loop_element<'a, S: Scan>(
+        &mut self,
+        tokens: &mut GenericTokenizer<'a, S>,
+        body: BucketId,
+        node: ExecId,
+        body_node: &'d ExecNode,
+        clear: &[SlotId],
+        depth: usize,
+    ) -> Result<bool, MatchError>
+    where
+        'd: 'a,
+    {
+        // A string element is a scalar, so [`Self::match_exec`] would route it straight
+        // to [`Self::match_literal`]: run the node's ops against it and record its byte
+        // range. Reading it here does exactly that, without `step` building a 24-byte
+        // token and without entering the walker whose frame is sized for the container
+        // cases a scalar cannot take. Nothing about the *body* is assumed — the ops, the
+        // slot and the seal below are the same ones the tokenized arm runs.
+        //
+        // The reset and the slot clear are written out in each arm rather than hoisted
+        // above the `match`. Sharing them would put the fused read's branch on the
+        // tokenized arm's path, which an element that can never settle here would pay
+        // once per element for nothing.
+        match take_str_value(tokens) {
+            Some(bytes) => {
+                let end = tokens.position();
+                self.state.reset_node(body);
+                for &slot in clear {
+                    self.slots[slot] = None;
+                }
+                let val = FastVal::Str(FastStr::Unescaped(bytes));
+                self.run_op_list(tokens, &body_node.ops, &val);
+                // The element spans its two quotes as well as its content, which is what
+                // `match_literal` derives from the token's length.
+                self.store_range(
+                    body_node.store,
+                    body_node.store_projected,
+                    end - bytes.len() - 2,
+                    end,
+                );
+                self.run_scalar_after(tokens, body_node, depth + 1)?;
+            }
+            None => {
+                // Not a plain string, so it is a container, the end of the array, or
+                // something only the tokenizer can name. The first two are decided by the
+                // byte itself; `match_exec` wants the token `step` would have returned, so
+                // it is rebuilt from the cursor the probe just moved.
+                let elem = match take_structural_head(tokens) {
+                    Some(TokenType::ArrayEnd) => return Ok(false),
+                    Some(token_type) => {
+                        let at = tokens.position() - 1;
+                        Token {
+                            token_type,
+                            value: &tokens.input()[at..at + 1],
+                        }
+                    }
+                    None => {
+                        let elem = tokens.step()?;
+                        if elem.token_type == TokenType::ArrayEnd {
+                            return Ok(false);
+                        }
+                        elem
+                    }
+                };
+                self.state.reset_node(body);
+                // This element starts with none of the body's stored fields known.
+                for &slot in clear {
+                    self.slots[slot] = None;
+                }
+                // A scalar the fused read declined — a number, a boolean, `null`, an
+                // escaped string — contains nothing, so none of the walker's machinery can
+                // apply and `match_exec` would route it straight back out to
+                // `match_literal`. Going there directly skips the frame `match_literal`
+                // exists to avoid, which an array of numbers would otherwise pay once per
+                // element — only strings and containers have a probe that names them.
+                if elem.token_type.is_literal() {
+                    self.match_literal(tokens, elem, node);
+                    self.run_scalar_after(tokens, body_node, depth + 1)?;
+                } else {
+                    self.match_exec(tokens, elem, node, depth + 1)?;
+                }
+            }
+        }
+        Ok(true)
+    }
+
+    /// Read one object member for [`Self::match_loop`] and run the loop body over the part of
+    /// it the loop iterates, or return `false` at the closing `}`.
+    ///
+    /// A value is an element like any other, so it goes to [`Self::loop_element`]. A name is a
+    /// string the key token already holds, and a pair is the name and the value fed to the
+    /// body's `name` and `val` children: the compiler allows a pairs body nothing else, since
+    /// the pair object itself is nowhere in the document.
+    fn loop_member<'a, S: Scan>(
+        &mut self,
+        tokens: &mut GenericTokenizer<'a, S>,
+        lp: &'d LoopNode,
+        body_node: &'d ExecNode,
+        depth: usize,
+    ) -> Result<bool, MatchError>
+    where
+        'd: 'a,
+    {
+        let (body, node, clear) = (lp.bucket, lp.node, lp.clear_slots.as_slice());
+        let key = tokens.step()?;
+        match key.token_type {
+            TokenType::ObjectEnd => return Ok(false),
+            TokenType::String | TokenType::EscString => {}
+            _ => return Err(MatchError::Structure("expected an object key")),
+        }
+        let colon = |tokens: &mut GenericTokenizer<'a, S>| {
+            if !take_structural(tokens, b':')
+                && tokens.step()?.token_type != TokenType::ObjectKeyDelim
+            {
+                return Err(MatchError::Structure("expected ':' after object key"));
+            }
+            Ok(())
+        };
+        if lp.over == LoopOver::Values {
+            colon(tokens)?;
+            return self.loop_element(tokens, body, node, body_node, clear, depth);
+        }
+
+        self.state.reset_node(body);
+        for &slot in clear {
+            self.slots[slot] = None;
+        }
+        if lp.over == LoopOver::Names {
+            self.match_literal(tokens, key, node);
+            colon(tokens)?;
+            skip_unnamed_value(tokens, depth)?;
+        } else {
+            if let Some(name) = body_node.elems.get(b"name") {
+                self.match_literal(tokens, key, name);
+            }
+            colon(tokens)?;
+            match body_node.elems.get(b"val") {
+                Some(val) => {
+                    let tok = tokens.step()?;
+                    if tok.token_type.is_literal() {
+                        self.match_literal(tokens, tok, val);
+                    } else {
+                        self.match_exec(tokens, tok, val, depth + 2)?;
+                    }
+                }
+                None => skip_unnamed_value(tokens, depth)?,
+            }
+        }
+        // A name is a scalar, and a pair has no bytes of its own, so neither has a close to
+        // run the body's after-node at.
+        self.run_scalar_after(tokens, body_node, depth + 1)?;
+        Ok(true)
+    }
+
     /// Evaluate every op on `exec` against the active value, recording results.
     ///
     /// `#[inline(always)]` because the active value reaches this by *reference*: outlined, the
@@ -1029,6 +1265,14 @@ impl<'d, C: Collation> FastMatcher<'d, C> {
                 let v = self.resolve_ref(tokens, of, active);
                 Tri::from_bool(!matches!(v, FastVal::Missing))
             }
+            // The same reasoning as `Exists`, with `NULL` counted as not there.
+            OpKind::Valued { of } => {
+                let v = self.resolve_ref(tokens, of, active);
+                Tri::from_bool(!matches!(v, FastVal::Missing | FastVal::Null))
+            }
+            OpKind::Known(cond) => {
+                Tri::from_bool(self.eval_cond(tokens, cond, active) != Tri::Unknown)
+            }
             OpKind::Matches { matcher, of } => {
                 let v = self.resolve_ref(tokens, of, active);
                 // Unlike `Exists`, a pattern match against a value that is not there has no
@@ -1039,7 +1283,34 @@ impl<'d, C: Collation> FastMatcher<'d, C> {
                     Tri::from_bool(matcher.matches(&v))
                 }
             }
+            // Nor does one against a pattern that is not there, or is not a regex: a document
+            // carrying a bad rule is a document the rule says nothing about.
+            OpKind::MatchesDynamic { pattern, of } => {
+                let v = self.resolve_ref(tokens, of, active);
+                if matches!(v, FastVal::Missing) {
+                    return Tri::Unknown;
+                }
+                let p = self.resolve_ref(tokens, pattern, active);
+                match self.patterns.borrow_mut().matches(&p, &v, &self.collation) {
+                    Some(m) => Tri::from_bool(m),
+                    None => Tri::Unknown,
+                }
+            }
+            OpKind::Between { of, low, high } => self.eval_between(tokens, of, low, high, active),
+            // Membership is the `Or` of the equalities it stands for, and those are all definite
+            // once the value is present: UNKNOWN only for a missing one.
+            OpKind::In { of, set } => {
+                let v = self.resolve_ref(tokens, of, active);
+                if matches!(v, FastVal::Missing) {
+                    Tri::Unknown
+                } else {
+                    Tri::from_bool(set.contains(&v, &self.collation))
+                }
+            }
             OpKind::Compare { op, lhs, rhs } => {
+                if let Some(result) = self.eval_set_compare(tokens, *op, lhs, rhs, active) {
+                    return result;
+                }
                 // Reached only when [`Self::eval_op`]'s arm declined, so at least one operand
                 // is a slot or a function result and has to be built. Both are produced by
                 // value, which is what `operand_ref` exists to avoid where it can.
@@ -1054,12 +1325,97 @@ impl<'d, C: Collation> FastMatcher<'d, C> {
         }
     }
 
+    /// A comparison with a set-valued parameter on one side: `=` is a membership test, and
+    /// every ordering comparison is [`Tri::Unknown`] because a set has no single value to order
+    /// against. `None` when neither side is a set, for the ordinary comparison to proceed.
+    fn eval_set_compare<'a, S: Scan>(
+        &self,
+        tokens: &mut GenericTokenizer<'a, S>,
+        op: CmpOp,
+        lhs: &'a DataRef,
+        rhs: &'a DataRef,
+        active: Option<&FastVal<'a>>,
+    ) -> Option<Tri>
+    where
+        'd: 'a,
+    {
+        let (set, other) = match (self.param_set(lhs), self.param_set(rhs)) {
+            (None, None) => return None,
+            (Some(set), None) => (set, rhs),
+            (None, Some(set)) => (set, lhs),
+            (Some(_), Some(_)) => return Some(Tri::Unknown),
+        };
+        if op != CmpOp::Eq {
+            return Some(Tri::Unknown);
+        }
+        let v = self.resolve_ref(tokens, other, active);
+        if matches!(v, FastVal::Missing) {
+            return Some(Tri::Unknown);
+        }
+        Some(Tri::from_bool(set.contains(&v, &self.collation)))
+    }
+
+    /// `low <= of <= high`, evaluated as the `And` of the two comparisons it stands for, so a
+    /// missing bound is UNKNOWN only where the other comparison does not already settle it
+    /// `False`. Operands are borrowed where [`Self::operand_ref`] can, and built otherwise.
+    fn eval_between<'a, S: Scan>(
+        &self,
+        tokens: &mut GenericTokenizer<'a, S>,
+        of: &'a DataRef,
+        low: &'a DataRef,
+        high: &'a DataRef,
+        active: Option<&FastVal<'a>>,
+    ) -> Tri
+    where
+        'd: 'a,
+    {
+        let built;
+        let v = match self.operand_ref(of, active) {
+            Some(v) => v,
+            None => {
+                built = self.resolve_ref(tokens, of, active);
+                &built
+            }
+        };
+        if matches!(v, FastVal::Missing) {
+            return Tri::Unknown;
+        }
+        let mut bound = |r: &'a DataRef, op: CmpOp| {
+            let built;
+            let b = match self.operand_ref(r, active) {
+                Some(b) => b,
+                None => {
+                    built = self.resolve_ref(tokens, r, active);
+                    &built
+                }
+            };
+            if matches!(b, FastVal::Missing) {
+                return Tri::Unknown;
+            }
+            Tri::from_bool(apply_cmp(op, self.collation.compare(v, b).ordering))
+        };
+        bound(low, CmpOp::Ge).and(bound(high, CmpOp::Le))
+    }
+
+    /// The set bound to `r`, if `r` is a parameter bound to one.
+    fn param_set(&self, r: &DataRef) -> Option<&ValueSet> {
+        match r {
+            DataRef::Param(id) => match self.params[*id].as_deref() {
+                Some(ParamValue::Set(set)) => Some(set),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
     /// Borrow an operand that already exists, instead of producing one.
     ///
     /// `Active` is the value currently being scanned and `Const` was built by the compiler
     /// and lives in the [`MatchDef`], so both are already in memory and a comparison can take
-    /// their addresses. `Slot` and `Func` have to be constructed, and decline here so the
-    /// caller falls back to [`Self::resolve_ref`].
+    /// their addresses. A scalar `Param` was built the same way and is held by the matcher for
+    /// the call, so it is borrowed exactly as a `Const` is. `Slot` and `Func` have to be
+    /// constructed, and a set-valued `Param` is not a value at all; those decline here so the
+    /// caller falls back to [`Self::eval_op_slow`].
     ///
     /// The lifetimes work out because `FastVal` is covariant: a `FastVal<'static>` is usable
     /// wherever a `FastVal<'a>` is wanted, which is exactly what storing constants with no
@@ -1073,7 +1429,12 @@ impl<'d, C: Collation> FastMatcher<'d, C> {
         match r {
             DataRef::Active => active,
             DataRef::Const(v) => Some(v),
-            DataRef::Slot(_) | DataRef::Func(_) => None,
+            DataRef::Param(id) => match self.params[*id].as_deref() {
+                Some(ParamValue::Scalar(v)) => Some(v),
+                Some(ParamValue::Set(_)) => None,
+                None => Some(&MISSING),
+            },
+            DataRef::Slot(_) | DataRef::Func(_) | DataRef::Case(_) => None,
         }
     }
 
@@ -1103,6 +1464,20 @@ impl<'d, C: Collation> FastMatcher<'d, C> {
             DataRef::Const(c) => borrow_const(c),
             DataRef::Slot(slot) => self.literal_from_slot(tokens, *slot),
             DataRef::Func(func) => self.resolve_func(tokens, func, active),
+            DataRef::Param(id) => self.param_value(*id),
+            DataRef::Case(case) => self.resolve_case(tokens, case, active),
+        }
+    }
+
+    /// A parameter's value as an operand to be produced: reached as a function argument or
+    /// beside a slot, where [`Self::operand_ref`] could not borrow it. The matcher holds the
+    /// binding only for the call, so the value is copied out; a set, or nothing bound, is
+    /// missing.
+    #[inline(never)]
+    fn param_value<'a>(&self, id: usize) -> FastVal<'a> {
+        match self.params[id].as_deref() {
+            Some(ParamValue::Scalar(v)) => v.clone(),
+            Some(ParamValue::Set(_)) | None => FastVal::Missing,
         }
     }
 
@@ -1124,7 +1499,63 @@ impl<'d, C: Collation> FastMatcher<'d, C> {
         for p in &func.params {
             args.push(self.resolve_ref(tokens, p, active));
         }
-        crate::func::apply(&func.name, &args)
+        match (&func.custom, &func.prepared) {
+            (Some(custom), _) => custom.call(&args),
+            (None, Some(prepared)) => crate::func::apply_prepared(prepared, &args),
+            (None, None) => crate::func::apply(&func.name, &args, &self.collation, self.now),
+        }
+    }
+
+    /// The operand a `CASE` selects: that of the first condition that is `True`. An `Unknown`
+    /// condition selects nothing, the same as a `False` one, so an absent field in a condition
+    /// falls through to the next. Outlined for the reason [`Self::resolve_func`] is.
+    #[inline(never)]
+    fn resolve_case<'a, S: Scan>(
+        &self,
+        tokens: &mut GenericTokenizer<'a, S>,
+        case: &'a CaseRef,
+        active: Option<&FastVal<'a>>,
+    ) -> FastVal<'a>
+    where
+        'd: 'a,
+    {
+        for (cond, then) in &case.whens {
+            if self.eval_cond(tokens, cond, active) == Tri::True {
+                return self.resolve_ref(tokens, then, active);
+            }
+        }
+        self.resolve_ref(tokens, &case.otherwise, active)
+    }
+
+    /// Evaluate a `CASE` condition by Kleene's tables, each op as it would be evaluated in the
+    /// after-node.
+    fn eval_cond<'a, S: Scan>(
+        &self,
+        tokens: &mut GenericTokenizer<'a, S>,
+        cond: &'a Cond,
+        active: Option<&FastVal<'a>>,
+    ) -> Tri
+    where
+        'd: 'a,
+    {
+        match cond {
+            Cond::Op(kind) => self.eval_op_slow(tokens, kind, active),
+            Cond::Not(sub) => self.eval_cond(tokens, sub, active).not(),
+            Cond::And(subs) => subs.iter().fold(Tri::True, |acc, c| {
+                if acc == Tri::False {
+                    acc
+                } else {
+                    acc.and(self.eval_cond(tokens, c, active))
+                }
+            }),
+            Cond::Or(subs) => subs.iter().fold(Tri::False, |acc, c| {
+                if acc == Tri::True {
+                    acc
+                } else {
+                    acc.or(self.eval_cond(tokens, c, active))
+                }
+            }),
+        }
     }
 
     /// Read the value stored in `slot` by seeking back to its recorded byte range and
@@ -1145,6 +1576,9 @@ impl<'d, C: Collation> FastMatcher<'d, C> {
     }
 }
 
+/// What an unbound parameter reads as, with an address [`FastMatcher::operand_ref`] can lend.
+static MISSING: FastVal<'static> = FastVal::Missing;
+
 /// Re-parse the value in `range` from a tokenizer already positioned at its start. Scalars
 /// come back in their lazy/borrowed form; containers as their raw document bytes.
 fn value_at<'a, S: Scan>(
@@ -2844,6 +3278,323 @@ mod tests {
         assert!(!run(&e, r#"{"other": 1}"#)); // missing -> false
     }
 
+    #[test]
+    fn regex_read_from_the_document() {
+        let e = Expr::Matches {
+            lhs: Box::new(field(&["value"])),
+            pattern: Box::new(field(&["rule"])),
+        };
+        let def = compile(
+            std::slice::from_ref(&e),
+            &Projection::new(),
+            &DefaultCollation,
+        )
+        .unwrap();
+        let mut m = FastMatcher::new(&def);
+        for (doc, want) in [
+            (r#"{"value": "abc", "rule": "^ab.*"}"#, true),
+            (r#"{"rule": "^ab.*", "value": "xabc"}"#, false), // the cached regex, reused
+            (r#"{"value": "a.c", "rule": "^a\\.c$"}"#, true), // decoded before compiling
+            (r#"{"value": "abc", "rule": "^a\\.c$"}"#, false),
+        ] {
+            assert_eq!(m.matches(doc.as_bytes()).unwrap().matched(), want, "{doc}");
+        }
+        assert_eq!(m.patterns.borrow().len(), 2);
+        // A document whose rule is absent, not a string or not a regex is UNKNOWN: neither it
+        // nor its negation matches.
+        let not = Expr::Not(Box::new(e));
+        for doc in [
+            r#"{"value": "abc"}"#,
+            r#"{"value": "abc", "rule": 1}"#,
+            r#"{"value": "abc", "rule": "("}"#,
+        ] {
+            assert!(!run(&not, doc), "{doc}");
+        }
+    }
+
+    #[test]
+    fn like_matches() {
+        let like = |pattern: &str| Expr::Like {
+            lhs: Box::new(field(&["name"])),
+            pattern: Box::new(Expr::Value(Literal::String(pattern.into()))),
+            escape: Some('\\'),
+        };
+        assert!(run(&like("Br%"), r#"{"name": "Brett"}"#));
+        assert!(!run(&like("Br%"), r#"{"name": "aBrett"}"#)); // anchored
+        assert!(run(&like("%tt"), r#"{"name": "Brett"}"#));
+        assert!(run(&like("%re%"), r#"{"name": "Brett"}"#));
+        assert!(run(&like("B_e%t"), r#"{"name": "Brett"}"#));
+        assert!(!run(&like("B_e%t"), r#"{"name": "Bart"}"#));
+        // Matched against the decoded string, with escapes applying to the pattern only.
+        assert!(run(&like(r"100\%"), r#"{"name": "100%"}"#));
+        assert!(!run(&like(r"100\%"), r#"{"name": "1000"}"#));
+        assert!(!run(&like("%"), r#"{"name": 1}"#)); // not a string
+        assert!(!run(&like("%"), r#"{"other": 1}"#)); // missing -> false
+        assert!(run(&Expr::Not(Box::new(like("%"))), r#"{"name": null}"#));
+        assert!(!run(&Expr::Not(Box::new(like("%"))), r#"{"other": 1}"#));
+    }
+
+    #[test]
+    fn case_operands() {
+        use jsonsm_ast::Func;
+        // CASE WHEN type = "a" THEN price * 1.2 ELSE price END > 100
+        let e = Expr::compare(
+            CompareOp::GreaterThan,
+            Expr::Case {
+                whens: vec![(
+                    Expr::compare(
+                        CompareOp::Equals,
+                        field(&["type"]),
+                        Expr::Value(Literal::String("a".into())),
+                    ),
+                    Expr::Func(Func {
+                        name: "mathMultiply".into(),
+                        args: vec![field(&["price"]), Expr::Value(Literal::Float(1.2))],
+                    }),
+                )],
+                otherwise: Some(Box::new(field(&["price"]))),
+            },
+            Expr::Value(Literal::Int(100)),
+        );
+        assert!(run(&e, r#"{"type": "a", "price": 90}"#));
+        assert!(!run(&e, r#"{"type": "b", "price": 90}"#));
+        assert!(run(&e, r#"{"type": "b", "price": 101}"#));
+        // The branch is chosen after the scope is read, whatever the field order.
+        assert!(run(&e, r#"{"price": 90, "type": "a"}"#));
+        // An unknown condition selects ELSE, as a false one does.
+        assert!(!run(&e, r#"{"price": 90}"#));
+        assert!(run(&e, r#"{"price": 101}"#));
+        // The selected operand may itself be missing.
+        assert!(!run(&e, r#"{"type": "a"}"#));
+        assert!(!run(&Expr::Not(Box::new(e.clone())), r#"{"type": "a"}"#));
+
+        // Without ELSE, nothing selected is NULL; conditions combine by Kleene logic.
+        let tier = Expr::Case {
+            whens: vec![
+                (
+                    Expr::And(vec![
+                        Expr::Exists(Box::new(field(&["vip"]))),
+                        Expr::compare(
+                            CompareOp::NotEquals,
+                            field(&["vip"]),
+                            Expr::Value(Literal::Bool(false)),
+                        ),
+                    ]),
+                    Expr::Value(Literal::String("gold".into())),
+                ),
+                (
+                    Expr::Between {
+                        lhs: Box::new(field(&["spend"])),
+                        low: Box::new(Expr::Value(Literal::Int(10))),
+                        high: Box::new(Expr::Value(Literal::Int(20))),
+                    },
+                    Expr::Value(Literal::String("silver".into())),
+                ),
+            ],
+            otherwise: None,
+        };
+        let is = |v: Literal| Expr::compare(CompareOp::Equals, tier.clone(), Expr::Value(v));
+        let silver = is(Literal::String("silver".into()));
+        let gold = is(Literal::String("gold".into()));
+        assert!(run(&gold, r#"{"vip": true, "spend": 15}"#));
+        assert!(run(&silver, r#"{"vip": false, "spend": 15}"#));
+        assert!(run(&silver, r#"{"spend": 15}"#));
+        assert!(run(&is(Literal::Null), r#"{"spend": 25}"#));
+        assert!(run(&is(Literal::Null), r#"{}"#));
+
+        // In a loop body, per element.
+        let any = Expr::Loop {
+            loop_type: LoopType::Any,
+            var: 1,
+            in_expr: Box::new(field(&["items"])),
+            sub_expr: Box::new(Expr::compare(
+                CompareOp::Equals,
+                Expr::Case {
+                    whens: vec![(
+                        Expr::Exists(Box::new(Expr::Field(Field {
+                            root: 1,
+                            path: vec![PathComponent::Key("alt".into())],
+                        }))),
+                        Expr::Field(Field {
+                            root: 1,
+                            path: vec![PathComponent::Key("alt".into())],
+                        }),
+                    )],
+                    otherwise: Some(Box::new(Expr::Field(Field {
+                        root: 1,
+                        path: vec![PathComponent::Key("name".into())],
+                    }))),
+                },
+                Expr::Value(Literal::String("x".into())),
+            )),
+        };
+        assert!(run(&any, r#"{"items": [{"name": "y", "alt": "x"}]}"#));
+        assert!(run(&any, r#"{"items": [{"alt": "y"}, {"name": "x"}]}"#));
+        assert!(!run(&any, r#"{"items": [{"name": "x", "alt": "y"}, {}]}"#));
+    }
+
+    #[test]
+    fn valued_and_known() {
+        let valued = Expr::Valued(Box::new(field(&["a", "x"])));
+        assert!(run(&valued, r#"{"a": {"x": 0}}"#));
+        assert!(!run(&valued, r#"{"a": {"x": null}}"#));
+        assert!(!run(&valued, r#"{"a": {}}"#));
+        assert!(!run(&valued, r#"{"a": 5}"#));
+        // Never UNKNOWN, so its negation is exact.
+        let not_valued = Expr::Not(Box::new(valued));
+        assert!(run(&not_valued, r#"{"a": {"x": null}}"#));
+        assert!(run(&not_valued, r#"{}"#));
+        assert!(!run(&not_valued, r#"{"a": {"x": "v"}}"#));
+
+        let gt = Expr::compare(
+            CompareOp::GreaterThan,
+            field(&["n"]),
+            Expr::Value(Literal::Int(1)),
+        );
+        let known = Expr::Known(Box::new(gt.clone()));
+        assert!(run(&known, r#"{"n": 0}"#));
+        assert!(run(&known, r#"{"n": 5}"#));
+        assert!(!run(&known, r#"{"m": 5}"#));
+        assert!(run(&Expr::Not(Box::new(known)), r#"{}"#));
+        // Kleene logic first: a false conjunct settles the `And` despite the absent `n`.
+        let either = Expr::Known(Box::new(Expr::And(vec![
+            gt,
+            Expr::Exists(Box::new(field(&["m"]))),
+        ])));
+        assert!(run(&either, r#"{}"#));
+        assert!(!run(&either, r#"{"m": 1}"#));
+
+        // Per element, scalar elements included.
+        let unknown_x = Expr::Loop {
+            loop_type: LoopType::Any,
+            var: 1,
+            in_expr: Box::new(field(&["xs"])),
+            sub_expr: Box::new(Expr::Not(Box::new(Expr::Known(Box::new(Expr::compare(
+                CompareOp::LessThan,
+                Expr::Field(Field {
+                    root: 1,
+                    path: vec![PathComponent::Key("x".into())],
+                }),
+                Expr::Value(Literal::Int(3)),
+            )))))),
+        };
+        assert!(!run(&unknown_x, r#"{"xs": [{"x": 1}, {"x": 9}]}"#));
+        assert!(run(&unknown_x, r#"{"xs": [{"x": 1}, {"y": 9}]}"#));
+        assert!(run(&unknown_x, r#"{"xs": [{"x": 1}, 7]}"#));
+        // An element lacking the field answers `False`, not `Unknown`, so `NOT EVERY` holds.
+        let every_valued = Expr::Loop {
+            loop_type: LoopType::Every,
+            var: 1,
+            in_expr: Box::new(field(&["xs"])),
+            sub_expr: Box::new(Expr::Valued(Box::new(Expr::Field(Field {
+                root: 1,
+                path: vec![PathComponent::Key("x".into())],
+            })))),
+        };
+        assert!(run(&every_valued, r#"{"xs": [{"x": 1}]}"#));
+        assert!(run(
+            &Expr::Not(Box::new(every_valued)),
+            r#"{"xs": [{"x": 1}, {"y": 1}]}"#
+        ));
+
+        // A loop under it: `ANY` is known once an element is true or every element is known,
+        // and not over an absent array. Inline, and deferred for the outer `n` it reads.
+        for rhs in [Expr::Value(Literal::Int(3)), field(&["n"])] {
+            let any_known = Expr::Known(Box::new(Expr::Loop {
+                loop_type: LoopType::Any,
+                var: 1,
+                in_expr: Box::new(field(&["xs"])),
+                sub_expr: Box::new(Expr::compare(
+                    CompareOp::LessThan,
+                    Expr::Field(Field {
+                        root: 1,
+                        path: vec![PathComponent::Key("x".into())],
+                    }),
+                    rhs,
+                )),
+            }));
+            assert!(run(&any_known, r#"{"n": 3, "xs": [{"y": 1}, {"x": 1}]}"#));
+            assert!(run(&any_known, r#"{"xs": [{"x": 5}], "n": 3}"#));
+            assert!(!run(&any_known, r#"{"n": 3, "xs": [{"x": 5}, {"y": 1}]}"#));
+            assert!(!run(&any_known, r#"{"n": 3}"#));
+            let unknown = Expr::Not(Box::new(any_known));
+            assert!(run(&unknown, r#"{"n": 3, "xs": [{"x": 5}, 7]}"#));
+            assert!(!run(&unknown, r#"{"n": 3, "xs": []}"#));
+        }
+    }
+
+    #[test]
+    fn type_predicates_as_conditions() {
+        let is_object = Expr::Func(jsonsm_ast::Func {
+            name: "isObject".into(),
+            args: vec![field(&["a"])],
+        });
+        assert!(run(&is_object, r#"{"a": {"b": 1}}"#));
+        assert!(!run(&is_object, r#"{"a": "s"}"#));
+        let not_object = Expr::Not(Box::new(is_object));
+        assert!(run(&not_object, r#"{"a": null}"#));
+        // An absent argument is UNKNOWN, so neither the predicate nor its negation holds.
+        assert!(!run(&not_object, r#"{}"#));
+
+        let type_is = |name: &str| {
+            Expr::compare(
+                CompareOp::Equals,
+                Expr::Func(jsonsm_ast::Func {
+                    name: "type".into(),
+                    args: vec![field(&["a"])],
+                }),
+                Expr::Value(Literal::String(name.into())),
+            )
+        };
+        assert!(run(&type_is("array"), r#"{"a": [1]}"#));
+        assert!(run(&type_is("number"), r#"{"a": -1.5e3}"#));
+        assert!(!run(&type_is("missing"), r#"{}"#));
+    }
+
+    #[test]
+    fn defaults_for_absent_fields() {
+        let if_missing = |f: Expr| {
+            Expr::Func(jsonsm_ast::Func {
+                name: "ifMissing".into(),
+                args: vec![f, Expr::Value(Literal::Int(0))],
+            })
+        };
+        // The op has to run for an absent `p` as well, so it cannot wait on `p`'s node.
+        let low = Expr::compare(
+            CompareOp::LessThan,
+            if_missing(field(&["p"])),
+            Expr::Value(Literal::Int(3)),
+        );
+        assert!(run(&low, r#"{}"#));
+        assert!(run(&low, r#"{"p": 1}"#));
+        assert!(!run(&low, r#"{"p": 5}"#));
+        // Only an absent field takes the default; null is a value.
+        let zero = Expr::compare(
+            CompareOp::Equals,
+            if_missing(field(&["p"])),
+            Expr::Value(Literal::Int(0)),
+        );
+        assert!(run(&zero, r#"{"q": 1}"#));
+        assert!(!run(&zero, r#"{"p": null}"#));
+
+        // Per element, scalar elements included.
+        let every_low = Expr::Loop {
+            loop_type: LoopType::Every,
+            var: 1,
+            in_expr: Box::new(field(&["xs"])),
+            sub_expr: Box::new(Expr::compare(
+                CompareOp::LessThan,
+                if_missing(Expr::Field(Field {
+                    root: 1,
+                    path: vec![PathComponent::Key("p".into())],
+                })),
+                Expr::Value(Literal::Int(3)),
+            )),
+        };
+        assert!(run(&every_low, r#"{"xs": [{"p": 1}, {}, 7]}"#));
+        assert!(!run(&every_low, r#"{"xs": [{"p": 1}, {"p": 4}]}"#));
+    }
+
     #[test]
     fn function_over_field() {
         use jsonsm_ast::Func;
@@ -3091,6 +3842,102 @@ mod tests {
         assert!(!run(&e, r#"{"index": 2}"#));
     }
 
+    #[test]
+    fn loops_over_object_members() {
+        use jsonsm_ast::Func;
+        let var = |path: &[&str]| {
+            Expr::Field(Field {
+                root: 1,
+                path: path
+                    .iter()
+                    .map(|k| PathComponent::Key((*k).into()))
+                    .collect(),
+            })
+        };
+        let over = |loop_type, list: &str, sub_expr| Expr::Loop {
+            loop_type,
+            var: 1,
+            in_expr: Box::new(Expr::Func(Func {
+                name: list.into(),
+                args: vec![field(&["o"])],
+            })),
+            sub_expr: Box::new(sub_expr),
+        };
+        let str_lit = |s: &str| Expr::Value(Literal::String(s.into()));
+        let check = |e: &Expr, doc: &str, want: bool| {
+            assert_eq!(run_all_backends(e, doc), want, "{doc}");
+            assert_eq!(run_all_backends(e, &spaced(doc)), want, "{doc} spaced");
+        };
+
+        // ANY v IN objectValues(o) SATISFIES v.k = 1 — values are elements, containers included.
+        let values = over(
+            LoopType::Any,
+            "objectValues",
+            Expr::compare(CompareOp::Equals, var(&["k"]), Expr::Value(Literal::Int(1))),
+        );
+        check(&values, r#"{"o":{"a":{"k":2},"b":{"k":1}}}"#, true);
+        check(&values, r#"{"o":{"a":{"k":2},"b":[{"k":1}]}}"#, false);
+        check(&values, r#"{"o":{}}"#, false);
+        // Not an object, so there are no members to quantify over.
+        check(&values, r#"{"o":[{"k":1}]}"#, false);
+        check(&values, r#"{"p":{"a":{"k":1}}}"#, false);
+
+        // EVERY n IN objectNames(o) SATISFIES n >= 'b' — names compare decoded.
+        let names = over(
+            LoopType::Every,
+            "objectNames",
+            Expr::compare(CompareOp::GreaterEquals, var(&[]), str_lit("b")),
+        );
+        check(&names, r#"{"o":{"b":[1,{"x":2}],"c":null}}"#, true);
+        check(&names, r#"{"o":{"b":1,"\u0061":2}}"#, false);
+        check(&names, r#"{"o":{}}"#, true);
+
+        // ANY p IN objectPairs(o) SATISFIES p.name = 'b' AND p.val = o2 — the body reads an
+        // outer field, so the loop runs after the document, from the object's stored range.
+        let pairs = over(
+            LoopType::Any,
+            "objectPairs",
+            Expr::And(vec![
+                Expr::compare(CompareOp::Equals, var(&["name"]), str_lit("b")),
+                Expr::compare(CompareOp::Equals, var(&["val"]), field(&["o2"])),
+            ]),
+        );
+        check(&pairs, r#"{"o":{"a":2,"b":3},"o2":3}"#, true);
+        check(&pairs, r#"{"o2":3,"o":{"b":3,"a":2}}"#, true);
+        check(&pairs, r#"{"o":{"a":3,"b":2},"o2":3}"#, false);
+        check(&pairs, r#"{"o":{"a":2,"b":3}}"#, false);
+
+        // A member loop and an element loop on the same field, beside a plain child of it:
+        // each only applies to its own kind of container, and a decided member loop leaves
+        // the object for the rest of the scan to read.
+        let mixed = Expr::And(vec![
+            values.clone(),
+            Expr::compare(
+                CompareOp::Equals,
+                field(&["o", "z"]),
+                Expr::Value(Literal::Int(5)),
+            ),
+        ]);
+        check(&mixed, r#"{"o":{"b":{"k":1},"z":5}}"#, true);
+        check(&mixed, r#"{"o":{"b":{"k":1},"z":6}}"#, false);
+        let both = Expr::Or(vec![
+            values,
+            Expr::Loop {
+                loop_type: LoopType::Any,
+                var: 1,
+                in_expr: Box::new(field(&["o"])),
+                sub_expr: Box::new(Expr::compare(
+                    CompareOp::Equals,
+                    var(&["k"]),
+                    Expr::Value(Literal::Int(1)),
+                )),
+            },
+        ]);
+        check(&both, r#"{"o":{"a":{"k":1}}}"#, true);
+        check(&both, r#"{"o":[{"k":1}]}"#, true);
+        check(&both, r#"{"o":[{"k":2}]}"#, false);
+    }
+
     /// A field path with an array index, e.g. `$doc.a[1].b`.
     fn indexed_field(parts: &[(&str, Option<usize>)]) -> Expr {
         let mut path = Vec::new();
@@ -3456,6 +4303,113 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn loops_over_an_enclosing_scopes_array() {
+        // ANY o IN orders SATISFIES (ANY t IN <array> SATISFIES t = o.tag END) END
+        let nested = |mode: LoopType, array: Expr| Expr::Loop {
+            loop_type: LoopType::Any,
+            var: 1,
+            in_expr: Box::new(field(&["orders"])),
+            sub_expr: Box::new(Expr::Loop {
+                loop_type: mode,
+                var: 2,
+                in_expr: Box::new(array),
+                sub_expr: Box::new(Expr::compare(
+                    CompareOp::Equals,
+                    Expr::Field(Field {
+                        root: 2,
+                        path: vec![],
+                    }),
+                    Expr::Field(Field {
+                        root: 1,
+                        path: vec![PathComponent::Key("tag".into())],
+                    }),
+                )),
+            }),
+        };
+
+        // (a) `tags` at the document root, before or after `orders`.
+        let from_doc = nested(LoopType::Any, field(&["tags"]));
+        assert!(run(
+            &from_doc,
+            r#"{"tags": ["a", "b"], "orders": [{"tag": "z"}, {"tag": "b"}]}"#
+        ));
+        assert!(run(
+            &from_doc,
+            r#"{"orders": [{"tag": "z"}, {"tag": "b"}], "tags": ["a", "b"]}"#
+        ));
+        assert!(!run(
+            &from_doc,
+            r#"{"orders": [{"tag": "z"}], "tags": ["a", "b"]}"#
+        ));
+        // An absent array answers nothing, for every element.
+        assert!(!run(&from_doc, r#"{"orders": [{"tag": "z"}]}"#));
+        assert!(!run(
+            &Expr::Not(Box::new(from_doc)),
+            r#"{"orders": [{"tag": "z"}]}"#
+        ));
+
+        // (b) EVERY: an order qualifies when every tag equals its own, the array being read
+        // afresh from its slot for each order.
+        let every = nested(LoopType::Every, field(&["tags"]));
+        assert!(run(
+            &every,
+            r#"{"orders": [{"tag": "b"}, {"tag": "a"}], "tags": ["a", "a"]}"#
+        ));
+        assert!(!run(
+            &every,
+            r#"{"orders": [{"tag": "a"}], "tags": ["a", "b"]}"#
+        ));
+
+        // (c) two loops deep, over the middle scope's array: `o.tags` from inside a loop over
+        // `o.lines`, whichever of the two comes first in the order.
+        let deep = Expr::Loop {
+            loop_type: LoopType::Any,
+            var: 1,
+            in_expr: Box::new(field(&["orders"])),
+            sub_expr: Box::new(Expr::Loop {
+                loop_type: LoopType::Any,
+                var: 3,
+                in_expr: Box::new(Expr::Field(Field {
+                    root: 1,
+                    path: vec![PathComponent::Key("lines".into())],
+                })),
+                sub_expr: Box::new(Expr::Loop {
+                    loop_type: LoopType::Any,
+                    var: 2,
+                    in_expr: Box::new(Expr::Field(Field {
+                        root: 1,
+                        path: vec![PathComponent::Key("tags".into())],
+                    })),
+                    sub_expr: Box::new(Expr::compare(
+                        CompareOp::Equals,
+                        Expr::Field(Field {
+                            root: 2,
+                            path: vec![],
+                        }),
+                        Expr::Field(Field {
+                            root: 3,
+                            path: vec![PathComponent::Key("tag".into())],
+                        }),
+                    )),
+                }),
+            }),
+        };
+        assert!(run(
+            &deep,
+            r#"{"orders": [{"lines": [{"tag": "x"}], "tags": ["x"]}]}"#
+        ));
+        assert!(run(
+            &deep,
+            r#"{"orders": [{"tags": ["x"], "lines": [{"tag": "x"}]}]}"#
+        ));
+        // Each order's tags are its own: the first order's do not leak into the second.
+        assert!(!run(
+            &deep,
+            r#"{"orders": [{"tags": ["x"], "lines": []}, {"lines": [{"tag": "x"}]}]}"#
+        ));
+    }
+
     #[test]
     fn multi_expression() {
         // Three independent expressions matched in one pass.
@@ -4076,4 +5030,269 @@ mod tests {
             );
         }
     }
+
+    fn run_with(expr: &Expr, doc: &str, params: &Params) -> bool {
+        let def = compile(
+            std::slice::from_ref(expr),
+            &Projection::new(),
+            &DefaultCollation,
+        )
+        .unwrap();
+        let mut m = FastMatcher::new(&def);
+        m.matches_with(doc.as_bytes(), params).unwrap().matched()
+    }
+
+    fn param(name: &str) -> Expr {
+        Expr::Param(name.into())
+    }
+
+    #[test]
+    fn scalar_parameters_compare_like_constants() {
+        let e = Expr::compare(CompareOp::LessThan, field(&["age"]), param("max"));
+        let p = Params::new().scalar("max", Literal::Int(50));
+        assert!(run_with(&e, r#"{"age": 30}"#, &p));
+        assert!(!run_with(&e, r#"{"age": 80}"#, &p));
+        // A string parameter compares against the decoded document string.
+        let e = Expr::compare(CompareOp::Equals, field(&["name"]), param("who"));
+        let p = Params::new().scalar("who", Literal::String("a\"b".into()));
+        assert!(run_with(&e, r#"{"name": "a\"b"}"#, &p));
+        assert!(run_with(&e, r#"{"name": "\u0061\"b"}"#, &p));
+        assert!(!run_with(&e, r#"{"name": "ab"}"#, &p));
+    }
+
+    #[test]
+    fn unbound_parameters_are_missing() {
+        let e = Expr::compare(CompareOp::Equals, field(&["a"]), param("x"));
+        assert!(!run_with(&e, r#"{"a": 1}"#, &Params::new()));
+        // UNKNOWN, not FALSE: negating it does not match either.
+        assert!(!run_with(
+            &Expr::Not(Box::new(e.clone())),
+            r#"{"a": 1}"#,
+            &Params::new()
+        ));
+        // A binding lasts one call; plain `matches` sees the parameter unbound again.
+        let def = compile(
+            std::slice::from_ref(&e),
+            &Projection::new(),
+            &DefaultCollation,
+        )
+        .unwrap();
+        let mut m = FastMatcher::new(&def);
+        let p = Params::new().scalar("x", Literal::Int(1));
+        assert!(m.matches_with(br#"{"a": 1}"#, &p).unwrap().matched());
+        assert!(!m.matches(br#"{"a": 1}"#).unwrap().matched());
+    }
+
+    #[test]
+    fn set_parameters_test_membership() {
+        let c = DefaultCollation;
+        let p = Params::new().set(
+            "ids",
+            [
+                Literal::Int(3),
+                Literal::Int(7),
+                Literal::String("x".into()),
+            ],
+            &c,
+        );
+        let eq = Expr::compare(CompareOp::Equals, field(&["id"]), param("ids"));
+        assert!(run_with(&eq, r#"{"id": 7}"#, &p));
+        assert!(run_with(&eq, r#"{"id": 7.0}"#, &p));
+        assert!(run_with(&eq, r#"{"id": "x"}"#, &p));
+        assert!(!run_with(&eq, r#"{"id": 4}"#, &p));
+        assert!(!run_with(&eq, r#"{"id": "7"}"#, &p));
+        assert!(!run_with(&eq, r#"{"other": 7}"#, &p));
+        // The set may be on either side.
+        let flipped = Expr::compare(CompareOp::Equals, param("ids"), field(&["id"]));
+        assert!(run_with(&flipped, r#"{"id": 3}"#, &p));
+        // `!=` is the negated membership test.
+        let ne = Expr::compare(CompareOp::NotEquals, field(&["id"]), param("ids"));
+        assert!(run_with(&ne, r#"{"id": 4}"#, &p));
+        assert!(!run_with(&ne, r#"{"id": 3}"#, &p));
+        assert!(!run_with(&ne, r#"{"other": 4}"#, &p));
+        // Ordering against a set is UNKNOWN, under NOT too.
+        let lt = Expr::compare(CompareOp::LessThan, field(&["id"]), param("ids"));
+        assert!(!run_with(&lt, r#"{"id": 1}"#, &p));
+        assert!(!run_with(&Expr::Not(Box::new(lt)), r#"{"id": 1}"#, &p));
+    }
+
+    #[test]
+    fn parameters_inside_functions_and_loops() {
+        use jsonsm_ast::Func;
+        // mathAdd(x, $d) = 10
+        let e = Expr::compare(
+            CompareOp::Equals,
+            Expr::Func(Func {
+                name: "mathAdd".into(),
+                args: vec![field(&["x"]), param("d")],
+            }),
+            Expr::Value(Literal::Int(10)),
+        );
+        let p = Params::new().scalar("d", Literal::Int(4));
+        assert!(run_with(&e, r#"{"x": 6}"#, &p));
+        assert!(!run_with(&e, r#"{"x": 5}"#, &p));
+        assert!(!run_with(&e, r#"{"x": 6}"#, &Params::new()));
+
+        // ANY t IN tags SATISFIES t = $tags END, and the cross-field a = $x AND b = a
+        let any_tag = Expr::Loop {
+            loop_type: LoopType::Any,
+            var: 1,
+            in_expr: Box::new(field(&["tags"])),
+            sub_expr: Box::new(Expr::compare(
+                CompareOp::Equals,
+                Expr::Field(Field {
+                    root: 1,
+                    path: vec![],
+                }),
+                param("tags"),
+            )),
+        };
+        let p = Params::new().set(
+            "tags",
+            [
+                Literal::String("red".into()),
+                Literal::String("blue".into()),
+            ],
+            &DefaultCollation,
+        );
+        assert!(run_with(&any_tag, r#"{"tags": ["green", "blue"]}"#, &p));
+        assert!(!run_with(&any_tag, r#"{"tags": ["green"]}"#, &p));
+
+        let e = Expr::And(vec![
+            Expr::compare(CompareOp::Equals, field(&["b"]), field(&["a"])),
+            Expr::compare(CompareOp::GreaterThan, field(&["a"]), param("min")),
+        ]);
+        let p = Params::new().scalar("min", Literal::Int(1));
+        assert!(run_with(&e, r#"{"b": 2, "a": 2}"#, &p));
+        assert!(!run_with(&e, r#"{"b": 1, "a": 1}"#, &p));
+    }
+
+    #[test]
+    fn in_lists() {
+        let ints = |v: &[i64]| v.iter().map(|&i| Literal::Int(i)).collect::<Vec<_>>();
+        let in_list = |lhs: Expr, list: Vec<Literal>| Expr::In {
+            lhs: Box::new(lhs),
+            list,
+        };
+        let e = in_list(field(&["n"]), ints(&[1, 5, 9]));
+        assert!(run(&e, r#"{"n": 5}"#));
+        assert!(run(&e, r#"{"n": 9.0}"#));
+        assert!(!run(&e, r#"{"n": 4}"#));
+        assert!(!run(&e, r#"{"n": "5"}"#));
+        // An absent value is UNKNOWN, so NOT IN does not match it either — even for an empty
+        // list, which a present value is simply not in.
+        let not_in = |e: Expr| Expr::Not(Box::new(e));
+        assert!(!run(&e, r#"{"m": 5}"#));
+        assert!(!run(&not_in(e.clone()), r#"{"m": 5}"#));
+        assert!(run(&not_in(e.clone()), r#"{"n": 4}"#));
+        let empty = in_list(field(&["n"]), vec![]);
+        assert!(run(&not_in(empty.clone()), r#"{"n": 4}"#));
+        assert!(!run(&not_in(empty), r#"{"m": 4}"#));
+
+        // A list long enough to be hashed, with strings compared decoded.
+        let big = in_list(
+            field(&["s"]),
+            (0..100).map(|i| Literal::String(format!("v{i}"))).collect(),
+        );
+        assert!(run(&big, r#"{"s": "v42"}"#));
+        assert!(run(&big, r#"{"s": "\u0076\u0034\u0032"}"#));
+        assert!(!run(&big, r#"{"s": "v100"}"#));
+
+        // Over a function, over an enclosing scope's field from a loop body, and two fields
+        // deferred to the after-node.
+        let round = in_list(
+            Expr::Func(jsonsm_ast::Func {
+                name: "mathRound".into(),
+                args: vec![field(&["x"])],
+            }),
+            ints(&[2, 3]),
+        );
+        assert!(run(&round, r#"{"x": 2.6}"#));
+        assert!(!run(&round, r#"{"x": 3.6}"#));
+        let outer = Expr::Loop {
+            loop_type: LoopType::Any,
+            var: 1,
+            in_expr: Box::new(field(&["tags"])),
+            sub_expr: Box::new(in_list(field(&["n"]), ints(&[1]))),
+        };
+        assert!(run(&outer, r#"{"tags": [0], "n": 1}"#));
+        assert!(!run(&outer, r#"{"tags": [0], "n": 2}"#));
+        let sum = in_list(
+            Expr::Func(jsonsm_ast::Func {
+                name: "mathAdd".into(),
+                args: vec![field(&["a"]), field(&["b"])],
+            }),
+            ints(&[10]),
+        );
+        assert!(run(&sum, r#"{"a": 4, "b": 6}"#));
+        assert!(!run(&sum, r#"{"a": 4}"#));
+    }
+
+    #[test]
+    fn between() {
+        let between = |lhs: Expr, low: Expr, high: Expr| Expr::Between {
+            lhs: Box::new(lhs),
+            low: Box::new(low),
+            high: Box::new(high),
+        };
+        let int = |i| Expr::Value(Literal::Int(i));
+        let not = |e: Expr| Expr::Not(Box::new(e));
+        let e = between(field(&["ts"]), int(100), int(200));
+        for (doc, want) in [
+            (r#"{"ts": 100}"#, true), // inclusive at both ends
+            (r#"{"ts": 200}"#, true),
+            (r#"{"ts": 150.5}"#, true),
+            (r#"{"ts": 99.999}"#, false),
+            (r#"{"ts": 200.0001}"#, false),
+            (r#"{"ts": "150"}"#, false), // strings sort after numbers: above the range
+            (r#"{"ts": null}"#, false),
+        ] {
+            assert_eq!(run(&e, doc), want, "{doc}");
+            assert_eq!(run(&not(e.clone()), doc), !want, "NOT {doc}");
+        }
+        // Missing: UNKNOWN either way round.
+        assert!(!run(&e, r#"{"x": 1}"#));
+        assert!(!run(&not(e), r#"{"x": 1}"#));
+
+        // Exact at magnitudes an f64 cannot hold: 2^63 sits just above i64::MAX.
+        let big = between(
+            field(&["n"]),
+            Expr::Value(Literal::Uint(1 << 63)),
+            Expr::Value(Literal::Uint(u64::MAX)),
+        );
+        assert!(!run(&big, r#"{"n": 9223372036854775807}"#));
+        assert!(run(&big, r#"{"n": 9223372036854775808}"#));
+
+        // Strings compare decoded.
+        let s = |v: &str| Expr::Value(Literal::String(v.into()));
+        let names = between(field(&["name"]), s("b"), s("d"));
+        assert!(run(&names, r#"{"name": "c"}"#));
+        assert!(run(&names, r#"{"name": "\u0063"}"#));
+        assert!(!run(&names, r#"{"name": "da"}"#));
+
+        // A missing bound is UNKNOWN only where the other comparison does not settle it.
+        let open = between(field(&["ts"]), field(&["lo"]), int(200));
+        assert!(!run(&open, r#"{"ts": 150}"#));
+        assert!(!run(&not(open.clone()), r#"{"ts": 150}"#));
+        assert!(run(&not(open.clone()), r#"{"ts": 250}"#));
+        assert!(run(&open, r#"{"ts": 150, "lo": 100}"#));
+
+        // Bounds from parameters, unbound ones missing.
+        let p = between(
+            field(&["ts"]),
+            Expr::Param("from".into()),
+            Expr::Param("to".into()),
+        );
+        let params = Params::new()
+            .scalar("from", Literal::Int(10))
+            .scalar("to", Literal::Int(20));
+        assert!(run_with(&p, r#"{"ts": 15}"#, &params));
+        assert!(!run_with(&p, r#"{"ts": 25}"#, &params));
+        assert!(!run_with(&not(p.clone()), r#"{"ts": 15}"#, &Params::new()));
+        assert!(run_with(
+            &not(p),
+            r#"{"ts": 5}"#,
+            &Params::new().scalar("from", Literal::Int(10))
+        ));
+    }
 }
@@ -0,0 +1,281 @@
+//! Match-time parameters: values for an expression's `$name` operands, bound per call.
+//!
+//! A [`MatchDef`](crate::compile::MatchDef) compiled from an expression containing
+//! [`Expr::Param`](crate::ast::Expr::Param) keeps each parameter symbolic, so one definition
+//! serves every binding of it — thousands of filters that differ only in their constants share
+//! a single compile. [`FastMatcher::matches_with`](crate::matcher::FastMatcher::matches_with)
+//! supplies the values for one document.
+//!
+//! A parameter is bound to either
+//! - a **scalar**, which compares exactly as the same literal written into the expression
+//!   would — it is built by the same routine the compiler uses for constants; or
+//! - a **set** of scalars, which answers `=` as a membership test (`id = $allowed_ids` is true
+//!   when `id` equals any member). A set is indexed once, when it is built, so the cost per
+//!   comparison does not grow linearly with its size; see [`ValueSet`].
+//!
+//! An unbound parameter is missing, so a comparison against it is UNKNOWN like a comparison
+//! against an absent field. A set has no single value to order against, so `<`, `>` and the
+//! other ordering comparisons against one are UNKNOWN too, as is a set used as a function
+//! argument.
+
+use crate::collation::Collation;
+use crate::compile::fastval_from_literal;
+use crate::value::FastVal;
+use jsonsm_ast::Literal;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::Hasher;
+use std::sync::Arc;
+
+/// The values bound to an expression's named parameters for a match call.
+///
+/// ```
+/// use jsonsm::ast::Literal;
+/// use jsonsm::collation::DefaultCollation;
+/// use jsonsm::params::Params;
+///
+/// let params = Params::new()
+///     .scalar("min_age", Literal::Int(21))
+///     .set("allowed_ids", [Literal::Int(3), Literal::Int(7)], &DefaultCollation);
+/// assert!(params.get("min_age").is_some());
+/// assert!(params.get("other").is_none());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Params {
+    /// Held behind an `Arc` so binding a value into a matcher is a reference-count increment
+    /// rather than a copy of whatever strings or sets it holds.
+    values: HashMap<String, Arc<ParamValue>>,
+}
+
+/// The value of one parameter.
+#[derive(Debug, Clone)]
+pub enum ParamValue {
+    /// A single value, compared like an expression constant.
+    Scalar(FastVal<'static>),
+    /// A set of values, tested for membership by `=`.
+    Set(ValueSet),
+}
+
+impl Params {
+    /// No parameters bound.
+    pub fn new() -> Self {
+        Params::default()
+    }
+
+    /// Bind `name` to a scalar, returning `self` for chaining.
+    pub fn scalar(mut self, name: impl Into<String>, value: Literal) -> Self {
+        self.insert(name, ParamValue::Scalar(fastval_from_literal(&value)));
+        self
+    }
+
+    /// Bind `name` to a set of values, sorted under `collation`, returning `self` for
+    /// chaining. The collation should be the one the matcher runs with.
+    pub fn set<C: Collation>(
+        mut self,
+        name: impl Into<String>,
+        values: impl IntoIterator<Item = Literal>,
+        collation: &C,
+    ) -> Self {
+        self.insert(name, ParamValue::Set(ValueSet::new(values, collation)));
+        self
+    }
+
+    /// Bind `name`, replacing any previous binding.
+    pub fn insert(&mut self, name: impl Into<String>, value: ParamValue) {
+        self.values.insert(name.into(), Arc::new(value));
+    }
+
+    /// The value bound to `name`, if any.
+    pub fn get(&self, name: &str) -> Option<&ParamValue> {
+        self.values.get(name).map(Arc::as_ref)
+    }
+
+    /// The shared handle a matcher holds for the duration of a call.
+    pub(crate) fn get_shared(&self, name: &str) -> Option<Arc<ParamValue>> {
+        self.values.get(name).cloned()
+    }
+}
+
+/// A set of scalar values with a fast membership test: the values of a set-valued parameter,
+/// and the constants of an `IN` list.
+///
+/// The members are sorted and deduplicated under the collation the set is built with, and
+/// [`ValueSet::contains`] binary-searches them under the collation it is given. A set of at
+/// least [`ValueSet::HASH_MIN_LEN`] members is also hashed, when the collation provides a
+/// [`hash`](Collation::hash), and then probed in constant time instead.
+///
+/// The build and probe collations must agree, which holds whenever the set is built with the
+/// matcher's own collation; nothing checks it, in the same way nothing checks that a matcher
+/// runs with the collation its definition was compiled with.
+#[derive(Debug, Clone, Default)]
+pub struct ValueSet {
+    members: Vec<FastVal<'static>>,
+    /// Member indices by hash, for a large set under a hashing collation.
+    index: Option<HashMap<u64, Vec<usize>>>,
+}
+
+impl ValueSet {
+    /// The size from which a set is hashed. Below it, a binary search touches a few adjacent
+    /// members and costs less than hashing the probe value.
+    pub const HASH_MIN_LEN: usize = 16;
+
+    /// Build a set from literals, sorting and deduplicating them under `collation`.
+    pub fn new<C: Collation>(values: impl IntoIterator<Item = Literal>, collation: &C) -> Self {
+        let mut members: Vec<FastVal<'static>> = values
+            .into_iter()
+            .map(|v| fastval_from_literal(&v))
+            .collect();
+        members.sort_by(|a, b| collation.compare(a, b).ordering);
+        members.dedup_by(|a, b| collation.equals(a, b));
+        let index = if members.len() >= Self::HASH_MIN_LEN {
+            Self::build_index(&members, collation)
+        } else {
+            None
+        };
+        ValueSet { members, index }
+    }
+
+    fn build_index<C: Collation>(
+        members: &[FastVal<'static>],
+        collation: &C,
+    ) -> Option<HashMap<u64, Vec<usize>>> {
+        let mut index: HashMap<u64, Vec<usize>> = HashMap::with_capacity(members.len());
+        for (i, m) in members.iter().enumerate() {
+            index.entry(hash_value(m, collation)?).or_default().push(i);
+        }
+        Some(index)
+    }
+
+    /// Whether `value` equals some member under `collation`.
+    pub fn contains<C: Collation>(&self, value: &FastVal<'_>, collation: &C) -> bool {
+        if let Some(index) = &self.index {
+            if let Some(h) = hash_value(value, collation) {
+                return index.get(&h).is_some_and(|candidates| {
+                    candidates
+                        .iter()
+                        .any(|&i| collation.equals(&self.members[i], value))
+                });
+            }
+        }
+        self.members
+            .binary_search_by(|m| collation.compare(m, value).ordering)
+            .is_ok()
+    }
+
+    /// The members, in collation order.
+    pub fn iter(&self) -> impl Iterator<Item = &FastVal<'static>> + '_ {
+        self.members.iter()
+    }
+
+    /// Number of distinct members.
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    /// Whether the set has no members.
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+}
+
+/// `value`'s hash under `collation`, if the collation hashes values.
+fn hash_value<C: Collation>(value: &FastVal<'_>, collation: &C) -> Option<u64> {
+    let mut state = DefaultHasher::new();
+    collation.hash(value, &mut state).then(|| state.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collation::DefaultCollation;
+    use crate::value::FastStr;
+
+    #[test]
+    fn set_membership_follows_the_collation() {
+        let set = ValueSet::new(
+            [
+                Literal::String("b".into()),
+                Literal::Int(2),
+                Literal::Float(2.0), // the same number as `2`: deduplicated
+                Literal::Null,
+                Literal::String("a".into()),
+            ],
+            &DefaultCollation,
+        );
+        assert_eq!(set.len(), 4);
+        let c = DefaultCollation;
+        assert!(set.contains(&FastVal::Int(2), &c));
+        assert!(set.contains(&FastVal::Uint(2), &c));
+        assert!(set.contains(&FastVal::Null, &c));
+        // A document string is compared decoded, so an escaped spelling is still a member.
+        assert!(set.contains(&FastVal::Str(FastStr::from_content(br"\u0061", true)), &c));
+        // No cross-type equality: the string "2" is not the number 2.
+        assert!(!set.contains(&FastVal::Str(FastStr::Unescaped(b"2")), &c));
+        assert!(!set.contains(&FastVal::Bool(true), &c));
+        assert!(!ValueSet::default().contains(&FastVal::Null, &c));
+    }
+
+    #[test]
+    fn large_sets_are_hashed_consistently_with_equality() {
+        let c = DefaultCollation;
+        let set = ValueSet::new(
+            (0..100)
+                .map(Literal::Int)
+                .chain((0..100).map(|i| Literal::String(format!("s{i}")))),
+            &c,
+        );
+        assert_eq!(set.len(), 200);
+        assert!(set.index.is_some());
+        // Every numeric spelling of a member, in every representation, is found.
+        for v in [
+            FastVal::Int(42),
+            FastVal::Uint(42),
+            FastVal::Float(42.0),
+            FastVal::IntBytes(b"42"),
+            FastVal::FloatBytes(b"4.2e1"),
+            FastVal::Float(-0.0),
+        ] {
+            assert!(set.contains(&v, &c), "{v:?}");
+        }
+        assert!(set.contains(
+            &FastVal::Str(FastStr::from_content(br"\u0073\u0037", true)),
+            &c
+        ));
+        for v in [
+            FastVal::Float(42.5),
+            FastVal::Int(100),
+            FastVal::Uint(u64::MAX),
+            FastVal::Float(f64::INFINITY),
+            FastVal::Str(FastStr::Unescaped(b"42")),
+            FastVal::Bool(false),
+            FastVal::Null,
+        ] {
+            assert!(!set.contains(&v, &c), "{v:?}");
+        }
+    }
+
+    #[test]
+    fn sets_fall_back_to_binary_search_without_a_hash() {
+        /// Orders by the default rules but provides no hash.
+        struct Unhashed;
+        impl Collation for Unhashed {
+            fn compare(&self, a: &FastVal<'_>, b: &FastVal<'_>) -> crate::collation::Comparison {
+                DefaultCollation.compare(a, b)
+            }
+        }
+        let set = ValueSet::new((0..100).map(Literal::Int), &Unhashed);
+        assert!(set.index.is_none());
+        assert!(set.contains(&FastVal::FloatBytes(b"99.0"), &Unhashed));
+        assert!(!set.contains(&FastVal::Int(100), &Unhashed));
+    }
+
+    #[test]
+    fn later_bindings_replace_earlier_ones() {
+        let mut p = Params::new().scalar("x", Literal::Int(1));
+        p.insert("x", ParamValue::Scalar(FastVal::Int(2)));
+        assert!(matches!(
+            p.get("x"),
+            Some(ParamValue::Scalar(FastVal::Int(2)))
+        ));
+    }
+}
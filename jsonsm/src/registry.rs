@@ -0,0 +1,303 @@
+//! Functions supplied by the caller, resolved by name when an expression is compiled.
+//!
+//! An expression may call a function the engine does not build in — a business rule, a
+//! checksum, a lookup into a static table — by registering it in a [`FunctionRegistry`] and
+//! compiling with [`compile_with`](crate::compile::compile_with). A [`CustomFunction`] is a
+//! name's arity and a **pure** implementation over already-resolved argument values: it sees
+//! nothing but its arguments, and must give the same answer for the same ones, since the fast
+//! matcher and the reference oracle each call it and the two must agree.
+//!
+//! Every call is resolved at compile time ([`resolve`]). A built-in's name always means the
+//! built-in; any other name must be in the registry, and the call must pass an argument count
+//! the function takes. Anything else is a [`CallError`], so a misspelled function name fails
+//! the compile rather than quietly making every comparison on it UNKNOWN.
+//!
+//! Arguments arrive as the matcher holds them, so a document's number may still be its raw
+//! bytes ([`FastVal::IntBytes`]); [`FastVal::as_num`] reads any numeric form. A custom function
+//! is not called with a missing argument: the call is missing instead, as a built-in's mostly
+//! is. One that returns a boolean may be registered as a *predicate*, which stands alone as a
+//! condition like a built-in predicate does.
+
+use crate::func;
+use crate::value::FastVal;
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+
+/// The implementation of a custom function.
+type Call = dyn Fn(&[FastVal<'_>]) -> FastVal<'static> + Send + Sync;
+
+/// A source of custom functions by name.
+///
+/// `Send + Sync` so one registry can be shared, behind an `Arc`, by an oracle per thread.
+pub trait FunctionRegistry: Send + Sync {
+    /// The function registered as `name`, if any.
+    fn get(&self, name: &str) -> Option<Arc<CustomFunction>>;
+}
+
+/// The empty registry: only built-in functions resolve. What
+/// [`compile`](crate::compile::compile) uses.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Builtins;
+
+impl FunctionRegistry for Builtins {
+    fn get(&self, _name: &str) -> Option<Arc<CustomFunction>> {
+        None
+    }
+}
+
+/// One custom function: the argument counts it takes, whether it is a predicate, and its
+/// implementation.
+pub struct CustomFunction {
+    arity: RangeInclusive<usize>,
+    predicate: bool,
+    call: Box<Call>,
+}
+
+impl CustomFunction {
+    /// A function taking a number of arguments in `arity`.
+    pub fn new(
+        arity: RangeInclusive<usize>,
+        call: impl Fn(&[FastVal<'_>]) -> FastVal<'static> + Send + Sync + 'static,
+    ) -> Self {
+        CustomFunction {
+            arity,
+            predicate: false,
+            call: Box::new(call),
+        }
+    }
+
+    /// A predicate taking a number of arguments in `arity`. It should return a
+    /// [`FastVal::Bool`], or [`FastVal::Missing`] for no answer.
+    pub fn predicate(
+        arity: RangeInclusive<usize>,
+        call: impl Fn(&[FastVal<'_>]) -> FastVal<'static> + Send + Sync + 'static,
+    ) -> Self {
+        CustomFunction {
+            predicate: true,
+            ..CustomFunction::new(arity, call)
+        }
+    }
+
+    /// The argument counts the function takes.
+    pub fn arity(&self) -> &RangeInclusive<usize> {
+        &self.arity
+    }
+
+    /// Whether a call to the function may stand alone as a condition.
+    pub fn is_predicate(&self) -> bool {
+        self.predicate
+    }
+
+    /// Apply the function to `args`; missing if any of them is.
+    pub fn call(&self, args: &[FastVal<'_>]) -> FastVal<'static> {
+        if args.iter().any(|a| matches!(a, FastVal::Missing)) {
+            return FastVal::Missing;
+        }
+        (self.call)(args)
+    }
+}
+
+impl fmt::Debug for CustomFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CustomFunction")
+            .field("arity", &self.arity)
+            .field("predicate", &self.predicate)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Custom functions registered by name.
+///
+/// ```
+/// use jsonsm::registry::{FunctionRegistry, Functions};
+/// use jsonsm::value::{FastVal, Num};
+///
+/// let functions = Functions::new()
+///     .function("double", 1..=1, |args| match args[0].as_num() {
+///         Some(n) => FastVal::Float(n.as_f64() * 2.0),
+///         None => FastVal::Missing,
+///     })
+///     .predicate("isEven", 1..=1, |args| match args[0].as_num() {
+///         Some(Num::I(n)) => FastVal::Bool(n % 2 == 0),
+///         _ => FastVal::Missing,
+///     });
+/// assert!(functions.get("isEven").unwrap().is_predicate());
+/// assert!(functions.get("triple").is_none());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Functions {
+    by_name: HashMap<String, Arc<CustomFunction>>,
+}
+
+impl Functions {
+    /// No functions registered.
+    pub fn new() -> Self {
+        Functions::default()
+    }
+
+    /// Register `name` as a function taking a number of arguments in `arity`, returning
+    /// `self` for chaining. A later registration of the same name replaces an earlier one.
+    pub fn function(
+        self,
+        name: impl Into<String>,
+        arity: RangeInclusive<usize>,
+        call: impl Fn(&[FastVal<'_>]) -> FastVal<'static> + Send + Sync + 'static,
+    ) -> Self {
+        self.with(name, CustomFunction::new(arity, call))
+    }
+
+    /// Register `name` as a predicate, returning `self` for chaining.
+    pub fn predicate(
+        self,
+        name: impl Into<String>,
+        arity: RangeInclusive<usize>,
+        call: impl Fn(&[FastVal<'_>]) -> FastVal<'static> + Send + Sync + 'static,
+    ) -> Self {
+        self.with(name, CustomFunction::predicate(arity, call))
+    }
+
+    /// Register `name` as `function`, returning `self` for chaining.
+    pub fn with(mut self, name: impl Into<String>, function: CustomFunction) -> Self {
+        self.by_name.insert(name.into(), Arc::new(function));
+        self
+    }
+}
+
+impl FunctionRegistry for Functions {
+    fn get(&self, name: &str) -> Option<Arc<CustomFunction>> {
+        self.by_name.get(name).cloned()
+    }
+}
+
+/// A call that does not resolve to a function taking its arguments.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum CallError {
+    #[error("unknown function `{0}`")]
+    Unknown(String),
+    #[error("function `{name}` takes {}, but was given {got}", Arity(.expected))]
+    Arity {
+        name: String,
+        expected: RangeInclusive<usize>,
+        got: usize,
+    },
+    #[error("function `{name}` takes its arguments in pairs, but was given {got}")]
+    Unpaired { name: String, got: usize },
+    #[error("argument {} of function `{name}` is a constant that does not parse", .arg + 1)]
+    Malformed { name: String, arg: usize },
+    #[error("function `{0}` is built in, and cannot be registered")]
+    Redefined(String),
+}
+
+/// Resolve a call to `name` with `argc` arguments: `None` for a built-in, or the registered
+/// custom function. A name both built in and registered is an error, rather than one of the
+/// two silently losing.
+pub fn resolve(
+    name: &str,
+    argc: usize,
+    functions: &dyn FunctionRegistry,
+) -> Result<Option<Arc<CustomFunction>>, CallError> {
+    let custom = functions.get(name);
+    let expected = match (func::arity(name), &custom) {
+        (Some(_), Some(_)) => return Err(CallError::Redefined(name.to_owned())),
+        (Some(arity), None) => arity,
+        (None, Some(custom)) => custom.arity().clone(),
+        (None, None) => return Err(CallError::Unknown(name.to_owned())),
+    };
+    if !expected.contains(&argc) {
+        return Err(CallError::Arity {
+            name: name.to_owned(),
+            expected,
+            got: argc,
+        });
+    }
+    if custom.is_none() && func::paired(name) && argc % 2 == 1 {
+        return Err(CallError::Unpaired {
+            name: name.to_owned(),
+            got: argc,
+        });
+    }
+    Ok(custom)
+}
+
+/// An arity, as an error message words it.
+struct Arity<'r>(&'r RangeInclusive<usize>);
+
+impl fmt::Display for Arity<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let plural = |n: usize| if n == 1 { "" } else { "s" };
+        match (*self.0.start(), *self.0.end()) {
+            (lo, usize::MAX) => write!(f, "{lo} or more arguments"),
+            (lo, hi) if lo == hi => write!(f, "{lo} argument{}", plural(lo)),
+            (lo, hi) => write!(f, "{lo} to {hi} arguments"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry() -> Functions {
+        Functions::new()
+            .function("double", 1..=1, |args| match args[0].as_num() {
+                Some(n) => FastVal::Float(n.as_f64() * 2.0),
+                None => FastVal::Missing,
+            })
+            .function("strLower", 1..=1, |_| FastVal::Null)
+    }
+
+    #[test]
+    fn resolves_builtins_then_the_registry() {
+        let functions = registry();
+        assert!(matches!(resolve("mathAbs", 1, &functions), Ok(None)));
+        assert!(matches!(resolve("double", 1, &functions), Ok(Some(_))));
+        assert_eq!(
+            resolve("dubble", 1, &functions).unwrap_err(),
+            CallError::Unknown("dubble".into())
+        );
+        assert_eq!(
+            resolve("strLower", 1, &functions).unwrap_err(),
+            CallError::Redefined("strLower".into())
+        );
+        assert!(matches!(resolve("strLower", 1, &Builtins), Ok(None)));
+    }
+
+    #[test]
+    fn checks_arity() {
+        let err = |name: &str, argc: usize| resolve(name, argc, &registry()).unwrap_err();
+        assert_eq!(
+            err("double", 2).to_string(),
+            "function `double` takes 1 argument, but was given 2"
+        );
+        assert_eq!(
+            err("mathPi", 1).to_string(),
+            "function `mathPi` takes 0 arguments, but was given 1"
+        );
+        assert_eq!(
+            err("strSubstr", 1).to_string(),
+            "function `strSubstr` takes 2 to 3 arguments, but was given 1"
+        );
+        assert_eq!(
+            err("geoWithinPolygon", 6).to_string(),
+            "function `geoWithinPolygon` takes 8 or more arguments, but was given 6"
+        );
+        assert_eq!(
+            err("geoWithinPolygon", 9).to_string(),
+            "function `geoWithinPolygon` takes its arguments in pairs, but was given 9"
+        );
+        assert!(resolve("geoWithinPolygon", 10, &Builtins).is_ok());
+        assert!(resolve("bitAnd", 5, &Builtins).is_ok());
+    }
+
+    #[test]
+    fn a_missing_argument_is_not_passed() {
+        let f = CustomFunction::new(1..=2, |_| FastVal::Int(1));
+        assert!(matches!(f.call(&[FastVal::Null]), FastVal::Int(1)));
+        assert!(matches!(
+            f.call(&[FastVal::Int(0), FastVal::Missing]),
+            FastVal::Missing
+        ));
+    }
+}
@@ -0,0 +1,171 @@
+//! Semantic versions, for `semverCmp` and `semverLess`.
+//!
+//! A version is read strictly by the SemVer 2.0.0 grammar: `MAJOR.MINOR.PATCH`, each a decimal
+//! with no leading zero, then optionally `-` and dot-separated pre-release identifiers, then
+//! optionally `+` and build metadata. Identifiers are non-empty runs of ASCII letters, digits
+//! and hyphens, and a numeric pre-release identifier has no leading zero either. Anything else
+//! — a `v` prefix, a missing component, surrounding whitespace — is not a version.
+//!
+//! Versions order by SemVer precedence ([`Version::cmp`]): the three numbers in turn; then a
+//! version with a pre-release before the same version without; then the pre-release
+//! identifiers left to right, numeric ones numerically and before any alphanumeric one,
+//! alphanumeric ones by ASCII, and a shorter list first when it is a prefix of the longer.
+//! Build metadata takes no part, so `1.0.0+a` and `1.0.0+b` are equal. Numbers of any length
+//! compare exactly, without overflow.
+
+use std::cmp::Ordering;
+
+/// A parsed version, borrowing its text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Version<'s> {
+    /// `MAJOR`, `MINOR` and `PATCH`, as their digits.
+    core: [&'s str; 3],
+    /// The pre-release identifiers; empty for a release.
+    pre: Vec<&'s str>,
+}
+
+impl<'s> Version<'s> {
+    /// Read a version; `None` for anything the grammar does not allow.
+    pub fn parse(s: &'s str) -> Option<Version<'s>> {
+        let (rest, build) = match s.split_once('+') {
+            Some((rest, build)) => (rest, Some(build)),
+            None => (s, None),
+        };
+        if let Some(build) = build {
+            if !build.split('.').all(is_identifier) {
+                return None;
+            }
+        }
+        let (core, pre) = match rest.split_once('-') {
+            Some((core, pre)) => (core, pre.split('.').collect::<Vec<_>>()),
+            None => (rest, Vec::new()),
+        };
+        let pre_ok = pre
+            .iter()
+            .all(|id| is_identifier(id) && !(is_numeric(id) && has_leading_zero(id)));
+        let mut parts = core.split('.');
+        let mut number = || {
+            parts
+                .next()
+                .filter(|n| is_numeric(n) && !has_leading_zero(n))
+        };
+        let core = [number()?, number()?, number()?];
+        (pre_ok && parts.next().is_none()).then_some(Version { core, pre })
+    }
+}
+
+impl Ord for Version<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let core = self
+            .core
+            .iter()
+            .zip(&other.core)
+            .map(|(a, b)| cmp_numeric(a, b))
+            .find(|o| o.is_ne())
+            .unwrap_or(Ordering::Equal);
+        core.then_with(|| match (self.pre.is_empty(), other.pre.is_empty()) {
+            (true, true) => Ordering::Equal,
+            (true, false) => Ordering::Greater,
+            (false, true) => Ordering::Less,
+            (false, false) => self
+                .pre
+                .iter()
+                .zip(&other.pre)
+                .map(|(a, b)| cmp_identifier(a, b))
+                .find(|o| o.is_ne())
+                .unwrap_or_else(|| self.pre.len().cmp(&other.pre.len())),
+        })
+    }
+}
+
+impl PartialOrd for Version<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Two pre-release identifiers in precedence order: numeric below alphanumeric.
+fn cmp_identifier(a: &str, b: &str) -> Ordering {
+    match (is_numeric(a), is_numeric(b)) {
+        (true, true) => cmp_numeric(a, b),
+        (true, false) => Ordering::Less,
+        (false, true) => Ordering::Greater,
+        (false, false) => a.cmp(b),
+    }
+}
+
+/// Two decimals without leading zeros, compared by value: the longer is the larger.
+fn cmp_numeric(a: &str, b: &str) -> Ordering {
+    a.len().cmp(&b.len()).then_with(|| a.cmp(b))
+}
+
+fn is_identifier(s: &str) -> bool {
+    !s.is_empty() && s.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-')
+}
+
+fn is_numeric(s: &str) -> bool {
+    !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit())
+}
+
+fn has_leading_zero(s: &str) -> bool {
+    s.len() > 1 && s.starts_with('0')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(s: &str) -> Version<'_> {
+        Version::parse(s).unwrap_or_else(|| panic!("{s} should parse"))
+    }
+
+    #[test]
+    fn follows_the_spec_precedence_example() {
+        // The chain from SemVer 2.0.0, section 11, plus a few more.
+        let chain = [
+            "1.0.0-alpha",
+            "1.0.0-alpha.1",
+            "1.0.0-alpha.beta",
+            "1.0.0-beta",
+            "1.0.0-beta.2",
+            "1.0.0-beta.11",
+            "1.0.0-rc.1",
+            "1.0.0",
+            "1.9.0",
+            "1.10.0",
+            "1.10.1",
+            "2.0.0",
+            "18446744073709551616.0.0",
+        ];
+        for pair in chain.windows(2) {
+            assert!(v(pair[0]) < v(pair[1]), "{} < {}", pair[0], pair[1]);
+        }
+        assert_eq!(v("1.0.0+build.1").cmp(&v("1.0.0+other")), Ordering::Equal);
+        assert_eq!(v("1.0.0-rc.1+x").cmp(&v("1.0.0-rc.1")), Ordering::Equal);
+        assert!(v("1.0.0-a-b") > v("1.0.0-a"));
+    }
+
+    #[test]
+    fn rejects_what_the_grammar_does() {
+        for bad in [
+            "",
+            "1",
+            "1.0",
+            "1.0.0.0",
+            "v1.0.0",
+            " 1.0.0",
+            "01.0.0",
+            "1.0.0-",
+            "1.0.0-01",
+            "1.0.0-a..b",
+            "1.0.0+",
+            "1.0.0+a+b",
+            "1.0.0-é",
+            "1.-1.0",
+        ] {
+            assert_eq!(Version::parse(bad), None, "{bad:?}");
+        }
+        // A leading zero is fine in an alphanumeric identifier and in build metadata.
+        assert!(Version::parse("1.0.0-0a+001").is_some());
+    }
+}
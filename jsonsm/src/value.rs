@@ -397,6 +397,11 @@ pub enum FastVal<'a> {
     Array(&'a [u8]),
     /// An object kept as its raw document bytes (`{` … `}`).
     Object(&'a [u8]),
+    /// An array a function built (`objectNames`, …), as JSON bytes it owns: the value is
+    /// nowhere in the document to borrow.
+    OwnedArray(Box<[u8]>),
+    /// An object a function built or copied out of a built array, as JSON bytes it owns.
+    OwnedObject(Box<[u8]>),
 }
 
 /// A numeric value normalized to one of the three concrete numeric kinds, produced by
@@ -433,8 +438,8 @@ impl<'a> FastVal<'a> {
             | FastVal::IntBytes(_)
             | FastVal::FloatBytes(_) => ValueType::Number,
             FastVal::Str(_) => ValueType::String,
-            FastVal::Array(_) => ValueType::Array,
-            FastVal::Object(_) => ValueType::Object,
+            FastVal::Array(_) | FastVal::OwnedArray(_) => ValueType::Array,
+            FastVal::Object(_) | FastVal::OwnedObject(_) => ValueType::Object,
         }
     }
 
@@ -463,13 +468,42 @@ impl<'a> FastVal<'a> {
 
     /// The raw JSON bytes if this is an array or object container.
     #[inline]
-    pub fn container_bytes(&self) -> Option<&'a [u8]> {
+    pub fn container_bytes(&self) -> Option<&[u8]> {
         match self {
             FastVal::Array(b) | FastVal::Object(b) => Some(b),
+            FastVal::OwnedArray(b) | FastVal::OwnedObject(b) => Some(b),
             _ => None,
         }
     }
 
+    /// This value with nothing borrowed: lazy numbers parsed, strings decoded (lossily, should
+    /// the bytes not be UTF-8) and containers copied. For a function result taken from a value
+    /// the function built itself.
+    pub fn into_owned(self) -> FastVal<'static> {
+        match self {
+            FastVal::Missing => FastVal::Missing,
+            FastVal::Null => FastVal::Null,
+            FastVal::Bool(b) => FastVal::Bool(b),
+            FastVal::Int(i) => FastVal::Int(i),
+            FastVal::Uint(u) => FastVal::Uint(u),
+            FastVal::Float(f) => FastVal::Float(f),
+            FastVal::IntBytes(_) | FastVal::FloatBytes(_) => {
+                match self.as_num().expect("lazy numbers always parse") {
+                    Num::I(i) => FastVal::Int(i),
+                    Num::U(u) => FastVal::Uint(u),
+                    Num::F(f) => FastVal::Float(f),
+                }
+            }
+            FastVal::Str(s) => FastVal::Str(FastStr::Owned(
+                String::from_utf8_lossy(&s.to_decoded_bytes()).into_owned(),
+            )),
+            FastVal::Array(b) => FastVal::OwnedArray(b.into()),
+            FastVal::Object(b) => FastVal::OwnedObject(b.into()),
+            FastVal::OwnedArray(b) => FastVal::OwnedArray(b),
+            FastVal::OwnedObject(b) => FastVal::OwnedObject(b),
+        }
+    }
+
     /// Build a scalar value from a tokenizer [`Token`], choosing the representation
     /// directly from the token classification: `Integer`/`Number` become the lazy
     /// [`FastVal::IntBytes`]/[`FastVal::FloatBytes`], and `String`/`EscString` become the